@@ -0,0 +1,106 @@
+import { ChildProcess, execFileSync, spawn } from 'child_process';
+import { once } from 'events';
+
+/**
+ * The bus a private D-Bus daemon replaces.
+ */
+export type BusType = 'system' | 'session';
+
+/**
+ * A private D-Bus daemon, used as the system or session bus by the tests.
+ */
+export interface TestBus {
+    type: BusType;
+    address: string;
+    stop(): void;
+}
+
+function addressVariable(type: BusType): string {
+    return type === 'system'
+        ? 'DBUS_SYSTEM_BUS_ADDRESS'
+        : 'DBUS_SESSION_BUS_ADDRESS';
+}
+
+/**
+ * Start a private bus and use it as the system
+ * or session bus of this process.
+ */
+export async function startBus(type: BusType): Promise<TestBus> {
+    const daemon = spawn(
+        'dbus-daemon',
+        ['--session', '--nofork', '--print-address=1'],
+        { stdio: ['ignore', 'pipe', 'inherit'] }
+    );
+
+    const [data] = await once(daemon.stdout!, 'data');
+    const address = data.toString().trim();
+    const variable = addressVariable(type);
+    const previous = process.env[variable];
+    process.env[variable] = address;
+
+    return {
+        type,
+        address,
+        stop: () => {
+            if (previous === undefined) {
+                delete process.env[variable];
+            } else {
+                process.env[variable] = previous;
+            }
+
+            daemon.kill();
+        },
+    };
+}
+
+/**
+ * A service mocked using a python-dbusmock template.
+ */
+export class MockService {
+    private constructor(private readonly process: ChildProcess) {}
+
+    /**
+     * Start a mocked service on the given bus and wait until
+     * it owns its bus name.
+     */
+    public static start(
+        bus: TestBus,
+        template: string,
+        busName: string,
+        parameters: object = {}
+    ): MockService {
+        const env = {
+            ...process.env,
+            [addressVariable(bus.type)]: bus.address,
+        };
+        const mock = spawn(
+            'python3',
+            [
+                '-m',
+                'dbusmock',
+                `--${bus.type}`,
+                '--template',
+                template,
+                '--parameters',
+                JSON.stringify(parameters),
+            ],
+            { env, stdio: 'inherit' }
+        );
+
+        execFileSync(
+            'gdbus',
+            ['wait', `--${bus.type}`, '--timeout', '10', busName],
+            { env }
+        );
+        return new MockService(mock);
+    }
+
+    /**
+     * Stop the service and wait until it released its bus name.
+     */
+    public async stop(): Promise<void> {
+        const exited = once(this.process, 'exit');
+        this.process.kill();
+        await exited;
+    }
+}
@@ -1,13 +1,62 @@
-import test from 'ava';
+import test, { ExecutionContext } from 'ava';
 
 import {
+    AuditSink,
+    ConsentProvider,
+    DigestAlgorithm,
     KeyCreationOption,
+    Logger,
+    LogLevel,
+    LogRecord,
+    OtpAlgorithm,
     Passport,
+    PassportBackend,
     PublicKeyEncoding,
+    SshAgent,
     VerificationResult,
 } from '../.';
-import { createPublicKey, createVerify, randomBytes } from 'crypto';
+import {
+    createHash,
+    createPublicKey,
+    createVerify,
+    generateKeyPairSync,
+    KeyObject,
+    randomBytes,
+    sign,
+    X509Certificate,
+} from 'crypto';
+import {
+    existsSync,
+    mkdtempSync,
+    readdirSync,
+    readFileSync,
+    statSync,
+    writeFileSync,
+} from 'fs';
+import { tmpdir } from 'os';
+import { delimiter, join } from 'path';
 import isCi from 'is-ci';
+import { execFile, execFileSync } from 'child_process';
+import { connect } from 'net';
+import { promisify } from 'util';
+import { MockService, startBus } from './dbus';
+
+function verifySignature(
+    key: Buffer | KeyObject,
+    data: Buffer,
+    signature: Buffer
+): boolean {
+    const verify = createVerify('SHA256');
+    verify.write(data);
+    verify.end();
+
+    return verify.verify(
+        key instanceof KeyObject
+            ? key
+            : createPublicKey({ key, format: 'der', type: 'pkcs1' }),
+        signature
+    );
+}
 
 const MODULE_NOT_FOUND = {
     code: 'MODULE_NOT_FOUND',
@@ -17,7 +66,39 @@ const MODULE_NOT_FOUND = {
 const windowsTest = process.platform === 'win32' ? test : test.skip;
 const windowsLocalTest =
     process.platform === 'win32' && !isCi ? test : test.skip;
-const unixTest = process.platform !== 'win32' ? test : test.skip;
+// The native module is only available if it was built for this
+// platform, otherwise every export throws MODULE_NOT_FOUND
+const nativeAvailable = (() => {
+    try {
+        return PassportBackend.Software !== undefined;
+    } catch {
+        return false;
+    }
+})();
+const nativeTest = nativeAvailable ? test : test.skip;
+const nativeSerialTest = nativeAvailable ? test.serial : test.skip;
+// The D-Bus tests run the mocked services using python-dbusmock
+const dbusMockAvailable =
+    nativeAvailable &&
+    process.platform === 'linux' &&
+    !!process.env.PASSPORT_TEST_DBUS_MOCK;
+const dbusMockTest = dbusMockAvailable ? test.serial : test.skip;
+// The TPM tests run against a TPM (simulator) set in TPM2TOOLS_TCTI
+// and mock the consent prompt shown before signing on D-Bus
+const tpmAvailable = dbusMockAvailable && !!process.env.TPM2TOOLS_TCTI;
+const tpmTest = tpmAvailable ? test.serial : test.skip;
+// The PKCS#11 tests run against a token like SoftHSMv2
+const pkcs11Module = process.env.PASSPORT_TEST_PKCS11_MODULE;
+const pkcs11Test = nativeAvailable && pkcs11Module ? test.serial : test.skip;
+const unixTest =
+    process.platform !== 'win32' && !nativeAvailable ? test : test.skip;
+
+// Whether an executable of that name is on the PATH
+function onPath(command: string): boolean {
+    return (process.env.PATH ?? '')
+        .split(delimiter)
+        .some((directory) => directory && existsSync(join(directory, command)));
+}
 
 windowsTest('available', (t) => {
     t.notThrows(() => Passport.available());
@@ -35,6 +116,7 @@ windowsTest('check PublicKeyEncoding exists on windows', (t) => {
     t.notThrows(() => PublicKeyEncoding.BCryptEccFullPublicKey);
     t.notThrows(() => PublicKeyEncoding.BCryptPublicKey);
     t.notThrows(() => PublicKeyEncoding.Capi1PublicKey);
+    t.notThrows(() => PublicKeyEncoding.OpenSsh);
 });
 
 windowsTest('check VerificationResult exists on windows', (t) => {
@@ -82,12 +164,2586 @@ windowsLocalTest('sign and verify', async (t) => {
     t.false(Passport.accountWithIdExists('test'));
 });
 
-unixTest('check Passport exceptions on unix', (t) => {
-    t.throws(() => new Passport('test'), MODULE_NOT_FOUND);
-    t.throws(() => Passport.accountWithIdExists('test'), MODULE_NOT_FOUND);
-    t.throws(() => Passport.requestVerification('test'), MODULE_NOT_FOUND);
-    t.notThrows(() => Passport.available());
-    t.false(Passport.available());
+// Wait until a condition holds, e.g. because
+// callbacks are invoked asynchronously
+async function waitFor(condition: () => boolean): Promise<void> {
+    const deadline = Date.now() + 5000;
+    while (!condition()) {
+        if (Date.now() > deadline) {
+            throw new Error('Timed out waiting for the condition');
+        }
+
+        await new Promise((resolve) => setTimeout(resolve, 10));
+    }
+}
+
+nativeSerialTest('audit log records failed operations', async (t) => {
+    const path = join(mkdtempSync(join(tmpdir(), 'passport-')), 'audit.jsonl');
+    Passport.setAuditSink(AuditSink.file(path));
+
+    const passport = new Passport(
+        'audit-test-does-not-exist',
+        PassportBackend.Software
+    );
+    await t.throwsAsync(() => passport.sign(randomBytes(32)));
+    await t.throwsAsync(() => passport.deleteAccount());
+    Passport.clearAuditSink();
+
+    const entries = readFileSync(path, 'utf-8')
+        .trim()
+        .split('\n')
+        .map((line) => JSON.parse(line));
+
+    t.is(entries.length, 2);
+    t.is(entries[0].operation, 'sign');
+    t.is(entries[0].accountId, 'audit-test-does-not-exist');
+    t.is(entries[0].outcome, 'Error');
+    t.regex(entries[0].challengeHash, /^[0-9a-f]{64}$/);
+    t.is(entries[1].operation, 'deleteAccount');
+    t.is(entries[1].previousHash, entries[0].hash);
+    t.true(AuditSink.verifyFile(path).valid);
+
+    entries[0].accountId = 'someone-else';
+    writeFileSync(
+        path,
+        entries.map((entry) => JSON.stringify(entry)).join('\n') + '\n'
+    );
+
+    const result = AuditSink.verifyFile(path);
+    t.false(result.valid);
+    t.is(result.entries, 0);
+    t.is(result.invalidSequence, 0);
+
+    // Lines which are no entries are reported like modified entries
+    entries[0].accountId = 'audit-test-does-not-exist';
+    writeFileSync(
+        path,
+        `${JSON.stringify(entries[0])}\n{"sequence": 1,\n` +
+            `${JSON.stringify(entries[1])}\n`
+    );
+    const truncated = AuditSink.verifyFile(path);
+    t.false(truncated.valid);
+    t.is(truncated.entries, 1);
+    t.is(truncated.invalidSequence, 1);
+    t.regex(truncated.error!, /^Line 2 is not a valid audit entry/);
+    t.throws(() => AuditSink.file(path), { message: /can't be continued/ });
+});
+
+nativeSerialTest('audit log callback sink', async (t) => {
+    const entries: any[] = [];
+    Passport.setAuditSink(AuditSink.callback((entry) => entries.push(entry)));
+
+    const passport = new Passport(
+        'audit-test-does-not-exist',
+        PassportBackend.Software
+    );
+    await t.throwsAsync(() => passport.sign(randomBytes(32)));
+    await t.throwsAsync(() => passport.sign(randomBytes(32)));
+    Passport.clearAuditSink();
+
+    // Entries are delivered asynchronously
+    await waitFor(() => entries.length >= 2);
+    t.is(entries.length, 2);
+    t.true(AuditSink.verifyEntries(entries).valid);
+    t.false(AuditSink.verifyEntries(entries.slice(1)).valid);
+});
+
+nativeSerialTest('audit log records files which were not signed', async (t) => {
+    const dir = mkdtempSync(join(tmpdir(), 'passport-'));
+    const path = join(dir, 'audit.jsonl');
+    const passport = new Passport('audit-sign-file', PassportBackend.Software);
+    await passport.createAccount();
+
+    Passport.setAuditSink(AuditSink.file(path));
+    await t.throwsAsync(() => passport.signFile(join(dir, 'missing.bin')));
+    await passport.deleteAccount();
+    await t.throwsAsync(() => passport.signFile(path), {
+        message: /account does not exist/,
+    });
+    Passport.clearAuditSink();
+
+    const entries = readFileSync(path, 'utf-8')
+        .trim()
+        .split('\n')
+        .map((line) => JSON.parse(line));
+    t.deepEqual(
+        entries.map((entry) => [entry.operation, entry.outcome]),
+        [
+            ['signFile', 'Error'],
+            ['deleteAccount', 'Success'],
+            ['signFile', 'Error'],
+        ]
+    );
+    t.falsy(entries[0].challengeHash);
+    t.true(AuditSink.verifyFile(path).valid);
+});
+
+nativeSerialTest('log records are passed to the callback', async (t) => {
+    const records: LogRecord[] = [];
+    Logger.logToCallback((record) => records.push(record), LogLevel.Debug);
+
+    const passport = new Passport(
+        'log-test-does-not-exist',
+        PassportBackend.Software
+    );
+    await t.throwsAsync(() => passport.sign(randomBytes(32)));
+    Logger.disable();
+
+    // Records are delivered asynchronously
+    const isSigned = (record: LogRecord) =>
+        record.fields.operation === 'sign' && record.durationMs !== undefined;
+    await waitFor(() => records.some(isSigned));
+    const signed = records.find(isSigned);
+
+    t.truthy(signed);
+    t.is(signed!.fields.account_id, 'log-test-does-not-exist');
+    t.is(signed!.fields.outcome, 'Error');
+    t.true(signed!.durationMs! >= 0);
+});
+
+nativeTest('sign and verify with a software key', async (t) => {
+    t.true(Passport.available(PassportBackend.Software));
+
+    const passport = new Passport('software-sign', PassportBackend.Software);
+    t.false(passport.accountExists);
+    await passport.createAccount();
+
+    t.true(passport.accountExists);
+    t.true(
+        Passport.accountWithIdExists('software-sign', PassportBackend.Software)
+    );
+    t.false(Passport.accountWithIdExists('software-sign'));
+    await t.throwsAsync(() =>
+        passport.createAccount(KeyCreationOption.FailIfExists)
+    );
+
+    const challenge = randomBytes(32);
+    const signature = await passport.sign(challenge);
+    t.true(
+        verifySignature(await passport.getPublicKey(), challenge, signature)
+    );
+
+    const spki = createPublicKey({
+        key: await passport.getPublicKey(
+            PublicKeyEncoding.X509SubjectPublicKeyInfo
+        ),
+        format: 'der',
+        type: 'spki',
+    });
+    t.true(verifySignature(spki, challenge, signature));
+
+    await passport.deleteAccount();
+    t.false(passport.accountExists);
+    await t.throwsAsync(() => passport.sign(challenge));
+});
+
+nativeTest('sign multiple challenges at once', async (t) => {
+    const passport = new Passport('software-batch', PassportBackend.Software);
+    await t.throwsAsync(() => passport.signMany([randomBytes(32)]));
+    await passport.createAccount();
+
+    const challenges = [randomBytes(32), randomBytes(64), Buffer.alloc(0)];
+    const results = await passport.signMany(challenges);
+    const key = await passport.getPublicKey();
+
+    t.is(results.length, challenges.length);
+    results.forEach((result, i) => {
+        t.is(result.outcome, 'Success');
+        t.falsy(result.error);
+        t.true(verifySignature(key, challenges[i], result.signature!));
+    });
+
+    t.deepEqual(await passport.signMany([]), []);
+
+    // A challenge which can't be signed only fails on its own
+    const vaultChallenge = Buffer.from(
+        'passport-desktop vault v1\naccount: software-batch\n'
+    );
+    await t.throwsAsync(() => passport.sign(vaultChallenge), {
+        message: /vault/,
+    });
+    const partial = await passport.signMany([
+        challenges[0],
+        vaultChallenge,
+        challenges[1],
+    ]);
+    t.deepEqual(
+        partial.map((result) => result.outcome),
+        ['Success', 'Error', 'Success']
+    );
+    t.is(partial[1].signature, undefined);
+    t.regex(partial[1].error!, /vault/);
+    t.true(verifySignature(key, challenges[0], partial[0].signature!));
+    t.true(verifySignature(key, challenges[1], partial[2].signature!));
+
+    const [refused] = await passport.signMany([vaultChallenge]);
+    t.is(refused.outcome, 'Error');
+    await passport.deleteAccount();
+});
+
+nativeTest('sign a file and a stream using its digest', async (t) => {
+    const passport = new Passport('software-digest', PassportBackend.Software);
+    await passport.createAccount();
+    const key = await passport.getPublicKey();
+
+    const payload = randomBytes(3 * 1024 * 1024 + 17);
+    const file = join(mkdtempSync(join(tmpdir(), 'passport-')), 'payload.bin');
+    writeFileSync(file, payload);
+
+    const fromFile = await passport.signFile(file);
+    t.is(fromFile.algorithm, 'sha256');
+    t.true(
+        fromFile.digest.equals(createHash('sha256').update(payload).digest())
+    );
+    t.true(verifySignature(key, fromFile.digest, fromFile.signature));
+
+    const signer = passport.createSigner(DigestAlgorithm.Sha512);
+    for (let i = 0; i < payload.length; i += 65536) {
+        signer.update(payload.subarray(i, i + 65536));
+    }
+
+    const fromStream = await signer.final();
+    t.is(fromStream.algorithm, 'sha512');
+    t.true(
+        fromStream.digest.equals(createHash('sha512').update(payload).digest())
+    );
+    t.true(verifySignature(key, fromStream.digest, fromStream.signature));
+
+    t.throws(() => signer.update(payload));
+    await t.throwsAsync(() => signer.final());
+    await t.throwsAsync(() => passport.signFile(`${file}.missing`));
+
+    await passport.deleteAccount();
+    t.throws(() => passport.createSigner());
+});
+
+nativeTest('session signatures are bound to the account key', async (t) => {
+    const passport = new Passport('software-session', PassportBackend.Software);
+    await passport.createAccount();
+
+    t.is(passport.session, null);
+    t.throws(() => passport.signWithSession(randomBytes(32)));
+
+    const session = await passport.unlock({
+        durationSeconds: 60,
+        maxSignatures: 2,
+    });
+    t.is(session.remainingSignatures, 2);
+    t.true(
+        verifySignature(
+            await passport.getPublicKey(),
+            session.statement,
+            session.endorsement
+        )
+    );
+    t.true(
+        session.statement
+            .toString()
+            .includes(`key: ${session.publicKey.toString('hex')}\n`)
+    );
+
+    for (let i = 0; i < 2; i++) {
+        const challenge = randomBytes(32);
+        const signature = passport.signWithSession(challenge);
+        t.true(verifySignature(session.publicKey, challenge, signature));
+    }
+
+    t.is(passport.session, null);
+    t.throws(() => passport.signWithSession(randomBytes(32)));
+
+    // Statements endorsing a key chosen by someone else are never signed
+    const attackerKey = randomBytes(270).toString('hex');
+    const forged = Buffer.from(
+        session.statement
+            .toString()
+            .replace(/key: [0-9a-f]+/, `key: ${attackerKey}`)
+    );
+    await t.throwsAsync(() => passport.sign(forged), {
+        message: /only signed to unlock a session/,
+    });
+    const [refused, signed] = await passport.signMany([
+        forged,
+        randomBytes(32),
+    ]);
+    t.is(refused.outcome, 'Error');
+    t.regex(refused.error!, /only signed to unlock a session/);
+    t.is(signed.outcome, 'Success');
+    await passport.deleteAccount();
+});
+
+nativeTest('sessions expire and can be locked', async (t) => {
+    const passport = new Passport('software-expiry', PassportBackend.Software);
+    await passport.createAccount();
+
+    await passport.unlock({ durationSeconds: 60, maxSignatures: 10 });
+    t.notThrows(() => passport.signWithSession(randomBytes(32)));
+    passport.lock();
+    t.is(passport.session, null);
+    t.throws(() => passport.signWithSession(randomBytes(32)));
+
+    await passport.unlock({ durationSeconds: 1, maxSignatures: 10 });
+    t.not(passport.session, null);
+    await new Promise((resolve) => setTimeout(resolve, 1100));
+    t.is(passport.session, null);
+    t.throws(() => passport.signWithSession(randomBytes(32)));
+
+    await t.throwsAsync(() =>
+        passport.unlock({ durationSeconds: 0, maxSignatures: 10 })
+    );
+    await passport.deleteAccount();
+});
+
+nativeTest('protect and unprotect data with a software key', async (t) => {
+    const passport = new Passport('software-vault', PassportBackend.Software);
+    await t.throwsAsync(() => passport.protect(randomBytes(32)));
+    await passport.createAccount();
+
+    const vaultKey = randomBytes(32);
+    const envelope = await passport.protect(vaultKey);
+    t.is(envelope.subarray(0, 4).toString(), 'PPVT');
+    t.false(envelope.includes(vaultKey));
+    t.true(vaultKey.equals(await passport.unprotect(envelope)));
+
+    // Every envelope uses a new salt and nonce
+    const other = await passport.protect(vaultKey);
+    t.false(envelope.equals(other));
+    t.true(vaultKey.equals(await passport.unprotect(other)));
+    const empty = await passport.protect(Buffer.alloc(0));
+    t.is((await passport.unprotect(empty)).length, 0);
+
+    // The signature of the vault challenge is never handed out
+    const challenge = Buffer.from(
+        'passport-desktop vault v1\naccount: software-vault\n'
+    );
+    await t.throwsAsync(() => passport.sign(challenge), {
+        message: /only signed to derive the vault key/,
+    });
+
+    // Modified envelopes are rejected
+    const fields = Passport.decodeEnvelope(envelope);
+    const ciphertextOffset = envelope.length - fields.ciphertext.length;
+    for (const offset of [ciphertextOffset - 40, ciphertextOffset - 1]) {
+        const modified = Buffer.from(envelope);
+        modified[offset] ^= 0x01;
+        await t.throwsAsync(() => passport.unprotect(modified), {
+            message: /modified/,
+        });
+    }
+
+    const ciphertext = Buffer.from(fields.ciphertext);
+    ciphertext[ciphertext.length - 1] ^= 0x01;
+    const modified = Passport.encodeEnvelope({ ...fields, ciphertext });
+    await t.throwsAsync(() => passport.unprotect(modified), {
+        message: /modified/,
+    });
+
+    const version = Buffer.from(envelope);
+    version[4] = 3;
+    await t.throwsAsync(() => passport.unprotect(version), {
+        message: /version 3 is not supported/,
+    });
+    await t.throwsAsync(() => passport.unprotect(randomBytes(64)), {
+        message: /not protected/,
+    });
+
+    // Other accounts and replaced keys cannot decrypt the data
+    const stranger = new Passport(
+        'software-vault-other',
+        PassportBackend.Software
+    );
+    await stranger.createAccount();
+    await t.throwsAsync(() => stranger.unprotect(envelope), {
+        message: /account 'software-vault'/,
+    });
+    const renamed = Passport.encodeEnvelope({
+        ...fields,
+        accountId: 'software-vault-other',
+    });
+    await t.throwsAsync(() => stranger.unprotect(renamed), {
+        message: /another key/,
+    });
+
+    await passport.createAccount(KeyCreationOption.ReplaceExisting);
+    await t.throwsAsync(() => passport.unprotect(envelope), {
+        message: /another key/,
+    });
+
+    await passport.deleteAccount();
+    await stranger.deleteAccount();
+});
+
+nativeTest('decode and encode protected envelopes', async (t) => {
+    const passport = new Passport(
+        'software-envelope',
+        PassportBackend.Software
+    );
+    await passport.createAccount();
+
+    const envelope = await passport.protect(Buffer.from('secret'));
+    const fields = Passport.decodeEnvelope(envelope);
+    t.is(fields.version, 2);
+    t.is(fields.accountId, 'software-envelope');
+    t.is(fields.salt.length, 32);
+    t.is(fields.nonce.length, 12);
+    t.is(fields.ciphertext.length, 'secret'.length + 16);
+
+    const publicKey = await passport.getPublicKey(
+        PublicKeyEncoding.Pkcs1RsaPublicKey
+    );
+    t.is(
+        fields.keyFingerprint,
+        createHash('sha256').update(publicKey).digest('hex')
+    );
+    t.true(Passport.encodeEnvelope(fields).equals(envelope));
+
+    // Version 1 envelopes have no account ID and key fingerprint
+    const v1 = Passport.encodeEnvelope({
+        version: 1,
+        salt: fields.salt,
+        nonce: fields.nonce,
+        ciphertext: fields.ciphertext,
+    });
+    t.is(v1.length, 4 + 1 + 32 + 12 + fields.ciphertext.length);
+    const decoded = Passport.decodeEnvelope(v1);
+    t.is(decoded.version, 1);
+    t.is(decoded.accountId, undefined);
+    t.is(decoded.keyFingerprint, undefined);
+    t.true(decoded.ciphertext.equals(fields.ciphertext));
+
+    const invalid = [
+        { ...fields, version: 1 },
+        { ...fields, version: 3 },
+        { ...fields, keyFingerprint: undefined },
+        { ...fields, keyFingerprint: 'abc' },
+        { ...fields, salt: Buffer.alloc(31) },
+        { ...fields, nonce: Buffer.alloc(16) },
+        { ...fields, accountId: 'a'.repeat(0x10000) },
+    ];
+    for (const envelope of invalid) {
+        t.throws(() => Passport.encodeEnvelope(envelope));
+    }
+
+    await passport.deleteAccount();
+});
+
+nativeTest('rewrap protected data when replacing the key', async (t) => {
+    const passport = new Passport('software-rewrap', PassportBackend.Software);
+    await passport.createAccount();
+
+    const secrets = [randomBytes(32), Buffer.from('another secret')];
+    const envelopes = await Promise.all(
+        secrets.map((secret) => passport.protect(secret))
+    );
+    const oldKey = await passport.getPublicKey(
+        PublicKeyEncoding.Pkcs1RsaPublicKey
+    );
+
+    const rewrapped = await passport.rewrap(envelopes);
+    t.is(rewrapped.length, 2);
+    t.false(
+        oldKey.equals(
+            await passport.getPublicKey(PublicKeyEncoding.Pkcs1RsaPublicKey)
+        )
+    );
+    for (let i = 0; i < secrets.length; i++) {
+        t.true(secrets[i].equals(await passport.unprotect(rewrapped[i])));
+        await t.throwsAsync(() => passport.unprotect(envelopes[i]), {
+            message: /another key/,
+        });
+    }
+
+    // The key is kept if any envelope cannot be decrypted
+    const newKey = await passport.getPublicKey(
+        PublicKeyEncoding.Pkcs1RsaPublicKey
+    );
+    await t.throwsAsync(() => passport.rewrap([rewrapped[0], envelopes[1]]), {
+        message: /another key/,
+    });
+    t.true(
+        newKey.equals(
+            await passport.getPublicKey(PublicKeyEncoding.Pkcs1RsaPublicKey)
+        )
+    );
+    await t.throwsAsync(() => passport.rewrap([]), {
+        message: /No envelopes/,
+    });
+    t.true(
+        newKey.equals(
+            await passport.getPublicKey(PublicKeyEncoding.Pkcs1RsaPublicKey)
+        )
+    );
+
+    await passport.deleteAccount();
+    await t.throwsAsync(() => passport.rewrap(rewrapped));
+});
+
+nativeTest('fuzz the protected envelope parser', async (t) => {
+    const passport = new Passport('software-fuzz', PassportBackend.Software);
+    await passport.createAccount();
+    const envelope = await passport.protect(randomBytes(32));
+
+    // A simple generator with a fixed seed, so failures can be reproduced
+    let seed = Number(process.env.PASSPORT_FUZZ_SEED ?? 0x5eed) >>> 0;
+    const random = (max: number) => {
+        seed = (Math.imul(seed, 1664525) + 1013904223) >>> 0;
+        return seed % max;
+    };
+
+    const inputs: Buffer[] = [];
+    for (let i = 0; i < 2000; i++) {
+        const input = Buffer.from(envelope);
+        switch (random(4)) {
+            case 0:
+                inputs.push(
+                    Buffer.from(
+                        Array.from({ length: random(200) }, () => random(256))
+                    )
+                );
+                break;
+            case 1:
+                inputs.push(input.subarray(0, random(input.length)));
+                break;
+            case 2:
+                for (let j = random(8); j >= 0; j--) {
+                    input[random(input.length)] = random(256);
+                }
+                inputs.push(input);
+                break;
+            default:
+                // Keep the magic and version, so the header is parsed
+                input.writeUInt16LE(random(0x10000), 5);
+                inputs.push(input.subarray(0, random(input.length + 1)));
+        }
+    }
+
+    for (const input of inputs) {
+        let fields;
+        try {
+            fields = Passport.decodeEnvelope(input);
+        } catch (e) {
+            t.true(e instanceof Error);
+            continue;
+        }
+
+        // Everything that decodes must encode to the same bytes
+        t.true(Passport.encodeEnvelope(fields).equals(input));
+    }
+
+    // Mutated envelopes must never decrypt
+    for (const input of inputs.slice(0, 50)) {
+        if (!input.equals(envelope)) {
+            await t.throwsAsync(() => passport.unprotect(input));
+        }
+    }
+
+    await passport.deleteAccount();
+});
+
+tpmTest('sign and verify with a TPM key', async (t) => {
+    const keyDir = join(mkdtempSync(join(tmpdir(), 'passport-tpm-')), 'keys');
+    process.env.PASSPORT_TPM_KEY_DIR = keyDir;
+    const bus = await startBus('system');
+    const startPolkit = (parameters: object) =>
+        MockService.start(
+            bus,
+            join('__test__', 'mocks', 'polkitd.py'),
+            'org.freedesktop.PolicyKit1',
+            parameters
+        );
+    let polkitd = startPolkit({
+        allowed: ['io.github.markusjx.passport-desktop.verify'],
+    });
+    Passport.setTpmConsentProvider(ConsentProvider.Polkit);
+    t.teardown(async () => {
+        Passport.setTpmConsentProvider();
+        await polkitd.stop();
+        bus.stop();
+    });
+    t.true(Passport.available());
+    t.true(Passport.available(PassportBackend.Tpm));
+    t.false(Passport.available(PassportBackend.WindowsHello));
+
+    const passport = new Passport('tpm-sign');
+    t.false(passport.accountExists);
+    await passport.createAccount(KeyCreationOption.FailIfExists);
+
+    t.true(passport.accountExists);
+    t.true(Passport.accountWithIdExists('tpm-sign', PassportBackend.Tpm));
+    await t.throwsAsync(() =>
+        passport.createAccount(KeyCreationOption.FailIfExists)
+    );
+
+    // Only the user can access the key directory
+    t.is(statSync(keyDir).mode & 0o777, 0o700);
+    t.deepEqual(
+        readdirSync(keyDir).filter((name) => name.endsWith('.tmp')),
+        []
+    );
+
+    // Only one of several concurrent instances creates the key
+    const racing = ['a', 'b', 'c'].map(
+        () => new Passport('tpm-race', PassportBackend.Tpm)
+    );
+    const created = await Promise.allSettled(
+        racing.map((p) => p.createAccount(KeyCreationOption.FailIfExists))
+    );
+    t.deepEqual(created.map((r) => r.status).sort(), [
+        'fulfilled',
+        'rejected',
+        'rejected',
+    ]);
+    await new Passport('tpm-race', PassportBackend.Tpm).deleteAccount();
+
+    const challenge = randomBytes(32);
+    const signature = await passport.sign(challenge);
+    const key = await passport.getPublicKey();
+    t.true(verifySignature(key, challenge, signature));
+
+    const spki = createPublicKey({
+        key: await passport.getPublicKey(
+            PublicKeyEncoding.X509SubjectPublicKeyInfo
+        ),
+        format: 'der',
+        type: 'spki',
+    });
+    t.true(verifySignature(spki, challenge, signature));
+
+    // The key blob is loaded again by other instances
+    const other = new Passport('tpm-sign', PassportBackend.Tpm);
+    t.true(other.accountExists);
+    t.true(signature.equals(await other.sign(challenge)));
+
+    await passport.createAccount(KeyCreationOption.ReplaceExisting);
+    t.false(key.equals(await passport.getPublicKey()));
+
+    // The user is asked before signing
+    await polkitd.stop();
+    polkitd = startPolkit({ dismissed: true });
+    await t.throwsAsync(() => passport.sign(challenge), {
+        message: /canceled/,
+    });
+    const results = await passport.signMany([challenge, challenge]);
+    t.deepEqual(
+        results.map((result) => result.outcome),
+        ['UserCanceled', 'UserCanceled']
+    );
+
+    await passport.deleteAccount();
+    t.false(Passport.accountWithIdExists('tpm-sign', PassportBackend.Tpm));
+    await t.throwsAsync(() => other.sign(challenge));
+    await t.throwsAsync(() => passport.sign(challenge));
+});
+
+pkcs11Test('sign and verify with a PKCS#11 token', async (t) => {
+    t.throws(() =>
+        Passport.configurePkcs11({
+            modulePath: pkcs11Module!,
+            tokenLabel: 'passport-does-not-exist',
+        })
+    );
+
+    const options = {
+        modulePath: pkcs11Module!,
+        tokenLabel: process.env.PASSPORT_TEST_PKCS11_TOKEN ?? 'passport',
+        pin: process.env.PASSPORT_TEST_PKCS11_PIN ?? '1234',
+    };
+    Passport.configurePkcs11(options);
+    t.true(Passport.available(PassportBackend.Pkcs11));
+
+    const passport = new Passport('pkcs11-sign', PassportBackend.Pkcs11);
+    t.false(passport.accountExists);
+    await passport.createAccount(KeyCreationOption.FailIfExists);
+
+    t.true(Passport.accountWithIdExists('pkcs11-sign', PassportBackend.Pkcs11));
+    await t.throwsAsync(() =>
+        passport.createAccount(KeyCreationOption.FailIfExists)
+    );
+
+    const challenge = randomBytes(32);
+    const signature = await passport.sign(challenge);
+    const key = await passport.getPublicKey();
+    t.true(verifySignature(key, challenge, signature));
+
+    const spki = createPublicKey({
+        key: await passport.getPublicKey(
+            PublicKeyEncoding.X509SubjectPublicKeyInfo
+        ),
+        format: 'der',
+        type: 'spki',
+    });
+    t.true(verifySignature(spki, challenge, signature));
+
+    // Configuring the module again keeps it loaded and opens a new session
+    Passport.configurePkcs11(options);
+    t.true(Passport.accountWithIdExists('pkcs11-sign', PassportBackend.Pkcs11));
+    t.true(verifySignature(key, challenge, await passport.sign(challenge)));
+
+    const other = new Passport('pkcs11-sign-other', PassportBackend.Pkcs11);
+    await other.createAccount();
+    Passport.configurePkcs11(options);
+    t.true(verifySignature(key, challenge, await passport.sign(challenge)));
+    t.true(
+        verifySignature(
+            await other.getPublicKey(),
+            challenge,
+            await other.sign(challenge)
+        )
+    );
+    await other.deleteAccount();
+
+    await passport.createAccount(KeyCreationOption.ReplaceExisting);
+    t.false(key.equals(await passport.getPublicKey()));
+
+    await passport.deleteAccount();
+    t.false(
+        Passport.accountWithIdExists('pkcs11-sign', PassportBackend.Pkcs11)
+    );
+    await t.throwsAsync(() => passport.sign(challenge));
+});
+
+dbusMockTest('verify the user using fprintd', async (t) => {
+    const bus = await startBus('system');
+    t.teardown(() => bus.stop());
+
+    const verify = () =>
+        Passport.requestVerification(
+            'Scan your finger',
+            ConsentProvider.Fprintd
+        );
+    const cases: [object, VerificationResult][] = [
+        [{}, VerificationResult.Verified],
+        [
+            { results: ['verify-retry-scan', 'verify-match'] },
+            VerificationResult.Verified,
+        ],
+        [
+            { results: ['verify-no-match', 'verify-match'] },
+            VerificationResult.Verified,
+        ],
+        [{ results: ['verify-no-match'] }, VerificationResult.RetriesExhausted],
+        [
+            { results: ['verify-disconnected'] },
+            VerificationResult.DeviceNotPresent,
+        ],
+        [{ enrolled: false }, VerificationResult.NotConfiguredForUser],
+        [{ device: false }, VerificationResult.DeviceNotPresent],
+        [{ busy: true }, VerificationResult.DeviceBusy],
+    ];
+
+    for (const [parameters, expected] of cases) {
+        const fprintd = MockService.start(
+            bus,
+            join('__test__', 'mocks', 'fprintd.py'),
+            'net.reactivated.Fprint',
+            parameters
+        );
+
+        try {
+            t.is(await verify(), expected, JSON.stringify(parameters));
+        } finally {
+            await fprintd.stop();
+        }
+    }
+
+    // fprintd is not running at all
+    t.is(await verify(), VerificationResult.DeviceNotPresent);
+});
+
+dbusMockTest('verify the user using polkit', async (t) => {
+    const bus = await startBus('system');
+    t.teardown(() => {
+        Passport.setPolkitAction();
+        bus.stop();
+    });
+
+    const verify = () =>
+        Passport.requestVerification(
+            'Unlock the test vault',
+            ConsentProvider.Polkit
+        );
+    const defaultAction = 'io.github.markusjx.passport-desktop.verify';
+    const action = 'io.github.markusjx.passport-desktop.test';
+    const cases: [string | undefined, object, VerificationResult][] = [
+        [undefined, { allowed: [defaultAction] }, VerificationResult.Verified],
+        [action, { allowed: [action] }, VerificationResult.Verified],
+        [
+            action,
+            { allowed: [defaultAction] },
+            VerificationResult.DisabledByPolicy,
+        ],
+        [action, { dismissed: true }, VerificationResult.Canceled],
+        [action, { challenge: true }, VerificationResult.DeviceNotPresent],
+    ];
+
+    for (const [actionId, parameters, expected] of cases) {
+        Passport.setPolkitAction(actionId);
+        const polkitd = MockService.start(
+            bus,
+            join('__test__', 'mocks', 'polkitd.py'),
+            'org.freedesktop.PolicyKit1',
+            parameters
+        );
+
+        try {
+            t.is(await verify(), expected, JSON.stringify(parameters));
+        } finally {
+            await polkitd.stop();
+        }
+    }
+
+    // polkit is not running at all
+    t.is(await verify(), VerificationResult.DeviceNotPresent);
+    t.throws(() => Passport.setPolkitAction('Not a valid action'));
+});
+
+function keyFiles(directory: string): string[] {
+    return readdirSync(directory).filter((file) => file.endsWith('.key'));
+}
+
+function keyFile(directory: string, accountId: string): string {
+    const name = createHash('sha256').update(accountId).digest('hex');
+    return join(directory, `${name}.key`);
+}
+
+function useKeyDirectory(t: ExecutionContext): string {
+    const directory = mkdtempSync(join(tmpdir(), 'passport-keys-'));
+    Passport.configureKeyStore({ directory, useSecretService: false });
+    t.teardown(() => {
+        Passport.configureKeyStore({});
+        Passport.setKeyStorePassphrase(null);
+    });
+
+    return directory;
+}
+
+nativeSerialTest('store local keys in encrypted files', async (t) => {
+    const directory = useKeyDirectory(t);
+    const passport = new Passport('local-file', PassportBackend.Local);
+    t.false(passport.accountExists);
+    await t.throwsAsync(() => passport.createAccount(), {
+        message: /passphrase/,
+    });
+
+    Passport.setKeyStorePassphrase(() => 'correct horse battery staple');
+    await passport.createAccount(KeyCreationOption.FailIfExists);
+    t.true(Passport.accountWithIdExists('local-file', PassportBackend.Local));
+    await t.throwsAsync(() =>
+        passport.createAccount(KeyCreationOption.FailIfExists)
+    );
+
+    const challenge = randomBytes(32);
+    const signature = await passport.sign(challenge);
+    const key = await passport.getPublicKey();
+    t.true(verifySignature(key, challenge, signature));
+
+    // The key is stored in a versioned, encrypted file
+    t.deepEqual(keyFiles(directory), [
+        keyFile(directory, 'local-file').slice(directory.length + 1),
+    ]);
+    const file = readFileSync(keyFile(directory, 'local-file'));
+    t.is(file.subarray(0, 4).toString(), 'PPKF');
+    t.is(file[4], 1);
+
+    // Other instances load the key using an asynchronous callback
+    Passport.setKeyStorePassphrase(async () => 'correct horse battery staple');
+    const other = new Passport('local-file', PassportBackend.Local);
+    t.true(other.accountExists);
+    t.true(signature.equals(await other.sign(challenge)));
+
+    Passport.setKeyStorePassphrase(() => 'wrong passphrase');
+    await t.throwsAsync(() => other.sign(challenge), {
+        message: /passphrase is wrong/,
+    });
+    Passport.setKeyStorePassphrase(() => Promise.reject(new Error('denied')));
+    await t.throwsAsync(() => other.sign(challenge), { message: /denied/ });
+
+    // Only one of several concurrent instances creates the key
+    Passport.setKeyStorePassphrase(() => 'correct horse battery staple');
+    const racing = ['a', 'b', 'c'].map(
+        () => new Passport('local-race', PassportBackend.Local)
+    );
+    const results = await Promise.allSettled(
+        racing.map((p) => p.createAccount(KeyCreationOption.FailIfExists))
+    );
+    t.deepEqual(results.map((r) => r.status).sort(), [
+        'fulfilled',
+        'rejected',
+        'rejected',
+    ]);
+    await new Passport('local-race', PassportBackend.Local).deleteAccount();
+
+    await passport.deleteAccount();
+    t.false(Passport.accountWithIdExists('local-file', PassportBackend.Local));
+    t.deepEqual(keyFiles(directory), []);
+});
+
+nativeSerialTest('detect modified key files', async (t) => {
+    const directory = useKeyDirectory(t);
+    Passport.setKeyStorePassphrase(() => 'correct horse battery staple');
+
+    const passport = new Passport('local-tamper', PassportBackend.Local);
+    const other = new Passport('local-tamper-other', PassportBackend.Local);
+    await passport.createAccount();
+    await other.createAccount();
+
+    const path = keyFile(directory, 'local-tamper');
+    const original = readFileSync(path);
+    const challenge = randomBytes(32);
+    const signWith = async (data: Buffer) => {
+        writeFileSync(path, data);
+        try {
+            return await passport.sign(challenge);
+        } finally {
+            writeFileSync(path, original);
+        }
+    };
+
+    const modified = /modified/;
+    const cases: [string, number, RegExp][] = [
+        ['magic', 0, /not a key file/],
+        ['version', 4, /unsupported version/],
+        ['memory cost', 5, modified],
+        ['time cost', 9, modified],
+        ['parallelism', 13, modified],
+        ['salt', 17, modified],
+        ['nonce', 33, modified],
+        ['ciphertext', 45, modified],
+        ['tag', original.length - 1, modified],
+    ];
+
+    for (const [field, offset, message] of cases) {
+        const data = Buffer.from(original);
+        data[offset] ^= 0x04;
+        await t.throwsAsync(() => signWith(data), { message }, field);
+    }
+
+    const tooLarge = Buffer.from(original);
+    tooLarge.writeUInt32LE(0xffffffff, 5);
+    await t.throwsAsync(() => signWith(tooLarge), { message: /too large/ });
+
+    await t.throwsAsync(() => signWith(original.subarray(0, 20)), {
+        message: /truncated/,
+    });
+    await t.throwsAsync(() => signWith(original.subarray(0, 60)), {
+        message: modified,
+    });
+
+    // Key files cannot be swapped between accounts
+    const swapped = readFileSync(keyFile(directory, 'local-tamper-other'));
+    await t.throwsAsync(() => signWith(swapped), { message: modified });
+
+    // The original file is still valid
+    const signature = await passport.sign(challenge);
+    t.true(
+        verifySignature(await passport.getPublicKey(), challenge, signature)
+    );
+
+    await passport.deleteAccount();
+    await other.deleteAccount();
+});
+
+dbusMockTest('store local keys in the Secret Service', async (t) => {
+    const bus = await startBus('session');
+    const directory = mkdtempSync(join(tmpdir(), 'passport-keys-'));
+    Passport.configureKeyStore({ appName: 'passport-test', directory });
+    Passport.setKeyStorePassphrase(() => {
+        t.fail('The encrypted file store must not be used');
+        return '';
+    });
+    t.teardown(() => {
+        Passport.configureKeyStore({});
+        Passport.setKeyStorePassphrase(null);
+        bus.stop();
+    });
+
+    const startSecretService = (parameters: object = {}) =>
+        MockService.start(
+            bus,
+            join('__test__', 'mocks', 'secret_service.py'),
+            'org.freedesktop.secrets',
+            parameters
+        );
+
+    let secrets = startSecretService();
+    try {
+        const passport = new Passport('local-secret', PassportBackend.Local);
+        t.false(passport.accountExists);
+        await passport.createAccount(KeyCreationOption.FailIfExists);
+
+        const challenge = randomBytes(32);
+        const signature = await passport.sign(challenge);
+        t.true(
+            verifySignature(await passport.getPublicKey(), challenge, signature)
+        );
+
+        // The key is stored in an item of the application's collection
+        const gdbus = (path: string, method: string, ...args: string[]) =>
+            execFileSync('gdbus', [
+                'call',
+                '--session',
+                '--dest',
+                'org.freedesktop.secrets',
+                '--object-path',
+                path,
+                '--method',
+                method,
+                ...args,
+            ]).toString();
+        const items = gdbus(
+            '/org/freedesktop/secrets',
+            'org.freedesktop.Secret.Service.SearchItems',
+            "{'application': 'passport-test', 'account-id': 'local-secret'}"
+        );
+        t.regex(items, /\/org\/freedesktop\/secrets\/collection\/\d+\/item/);
+        t.deepEqual(keyFiles(directory), []);
+
+        const other = new Passport('local-secret', PassportBackend.Local);
+        t.true(other.accountExists);
+        t.true(signature.equals(await other.sign(challenge)));
+
+        await passport.deleteAccount();
+        t.false(
+            Passport.accountWithIdExists('local-secret', PassportBackend.Local)
+        );
+
+        // Items of other collections are ignored, even with the same attributes
+        gdbus(
+            '/org/freedesktop/secrets',
+            'org.freedesktop.Secret.Service.CreateCollection',
+            "{'org.freedesktop.Secret.Collection.Label': <'other'>}",
+            "''"
+        );
+        const collections = gdbus(
+            '/org/freedesktop/secrets',
+            'org.freedesktop.DBus.Properties.Get',
+            'org.freedesktop.Secret.Service',
+            'Collections'
+        ).match(/\/org\/freedesktop\/secrets\/collection\/\d+/g);
+        gdbus(
+            collections![collections!.length - 1],
+            'org.freedesktop.Secret.Collection.CreateItem',
+            "{'org.freedesktop.Secret.Item.Attributes': " +
+                "<{'application': 'passport-test', " +
+                "'account-id': 'local-secret'}>}",
+            "(objectpath '/org/freedesktop/secrets/session/plain', " +
+                "@ay [], @ay [1], 'text/plain')",
+            'true'
+        );
+        t.false(
+            Passport.accountWithIdExists('local-secret', PassportBackend.Local)
+        );
+    } finally {
+        await secrets.stop();
+    }
+
+    // Locked collections are unlocked using a prompt
+    secrets = startSecretService({ locked: true });
+    try {
+        const passport = new Passport('local-locked', PassportBackend.Local);
+        await passport.createAccount();
+        await passport.sign(randomBytes(32));
+        await passport.deleteAccount();
+    } finally {
+        await secrets.stop();
+    }
+
+    secrets = startSecretService({ dismiss: true });
+    try {
+        const passport = new Passport('local-dismissed', PassportBackend.Local);
+        await t.throwsAsync(() => passport.createAccount(), {
+            message: 'The user canceled the operation',
+        });
+    } finally {
+        await secrets.stop();
+    }
+});
+
+function sshString(value: Buffer | string): Buffer {
+    const length = Buffer.alloc(4);
+    length.writeUInt32BE(Buffer.byteLength(value));
+    return Buffer.concat([length, Buffer.from(value)]);
+}
+
+function readSshStrings(data: Buffer): Buffer[] {
+    const strings = [];
+    for (let offset = 0; offset < data.length; ) {
+        const length = data.readUInt32BE(offset);
+        strings.push(data.subarray(offset + 4, offset + 4 + length));
+        offset += 4 + length;
+    }
+
+    return strings;
+}
+
+function agentPath(name: string): string {
+    return process.platform === 'win32'
+        ? `\\\\.\\pipe\\passport-${name}-${process.pid}`
+        : join(mkdtempSync(join(tmpdir(), 'passport-agent-')), 'agent.sock');
+}
+
+function agentRequest(path: string, request: Buffer): Promise<Buffer> {
+    return new Promise((resolve, reject) => {
+        const socket = connect(path);
+        let response = Buffer.alloc(0);
+        socket.on('error', reject);
+        socket.on('data', (data) => {
+            response = Buffer.concat([response, data]);
+            if (
+                response.length >= 4 &&
+                response.length >= 4 + response.readUInt32BE(0)
+            ) {
+                socket.end();
+                resolve(response.subarray(4));
+            }
+        });
+
+        socket.write(sshString(request));
+    });
+}
+
+nativeTest('serve passport keys using the SSH agent protocol', async (t) => {
+    const passport = new Passport('software-ssh', PassportBackend.Software);
+    await passport.createAccount();
+    const agent = SshAgent.listen(agentPath('protocol'), [
+        { accountId: 'software-ssh', backend: PassportBackend.Software },
+        {
+            accountId: 'software-ssh-missing',
+            backend: PassportBackend.Software,
+        },
+    ]);
+
+    // Accounts without a key are not offered
+    const publicKeys = await agent.publicKeys();
+    t.is(publicKeys.length, 1);
+    t.regex(publicKeys[0], /^ssh-rsa AAAAB3NzaC1yc2E[\w+/=]+ software-ssh$/);
+    const blob = Buffer.from(publicKeys[0].split(' ')[1], 'base64');
+
+    const identities = await agentRequest(agent.path, Buffer.from([11]));
+    t.is(identities[0], 12);
+    t.is(identities.readUInt32BE(1), 1);
+    const [key, comment] = readSshStrings(identities.subarray(5));
+    t.true(key.equals(blob));
+    t.is(comment.toString(), 'software-ssh');
+
+    const publicKey = createPublicKey({
+        key: await passport.getPublicKey(PublicKeyEncoding.Pkcs1RsaPublicKey),
+        format: 'der',
+        type: 'pkcs1',
+    });
+    // Public key authentication requests as defined in RFC 4252
+    const userauth = (algorithm: string, key = blob) =>
+        Buffer.concat([
+            sshString(randomBytes(32)),
+            Buffer.from([50]),
+            sshString('user'),
+            sshString('ssh-connection'),
+            sshString('publickey'),
+            Buffer.from([1]),
+            sshString(algorithm),
+            sshString(key),
+        ]);
+    const sshsig = Buffer.concat([
+        Buffer.from('SSHSIG'),
+        sshString('file'),
+        sshString(''),
+        sshString('sha512'),
+        sshString(createHash('sha512').update('data').digest()),
+    ]);
+    const signRequest = (data: Buffer, flags: number) => {
+        const flagBuffer = Buffer.alloc(4);
+        flagBuffer.writeUInt32BE(flags);
+        return Buffer.concat([
+            Buffer.from([13]),
+            sshString(blob),
+            sshString(data),
+            flagBuffer,
+        ]);
+    };
+
+    for (const [flags, name, hash, data] of [
+        [2, 'rsa-sha2-256', 'SHA256', userauth('rsa-sha2-256')],
+        [4, 'rsa-sha2-512', 'SHA512', userauth('rsa-sha2-512')],
+        [6, 'rsa-sha2-512', 'SHA512', userauth('rsa-sha2-512')],
+        [2, 'rsa-sha2-256', 'SHA256', sshsig],
+    ] as const) {
+        const response = await agentRequest(
+            agent.path,
+            signRequest(data, flags)
+        );
+
+        t.is(response[0], 14);
+        const [signature] = readSshStrings(response.subarray(1));
+        const [algorithm, value] = readSshStrings(signature);
+        t.is(algorithm.toString(), name);
+
+        const verify = createVerify(hash);
+        verify.end(data);
+        t.true(verify.verify(publicKey, value));
+    }
+
+    // SHA-1, unknown keys, data which is neither an authentication
+    // request nor an SSH signature and other requests fail
+    const data = userauth('rsa-sha2-256');
+    const failures = [
+        signRequest(data, 0),
+        Buffer.concat([
+            Buffer.from([13]),
+            sshString(randomBytes(32)),
+            sshString(data),
+            Buffer.from([0, 0, 0, 2]),
+        ]),
+        signRequest(randomBytes(64), 2),
+        signRequest(userauth('rsa-sha2-512'), 2),
+        signRequest(userauth('rsa-sha2-256', randomBytes(32)), 2),
+        signRequest(
+            Buffer.from('passport-desktop vault v1\naccount: software-ssh\n'),
+            2
+        ),
+        Buffer.concat([Buffer.from([13]), sshString(blob)]),
+        Buffer.from([17]),
+        Buffer.from([19]),
+    ];
+    for (const request of failures) {
+        t.deepEqual(await agentRequest(agent.path, request), Buffer.from([5]));
+    }
+
+    // Only the user may connect, and the socket is not replaced
+    if (process.platform !== 'win32') {
+        t.is(statSync(agent.path).mode & 0o777, 0o600);
+        t.throws(() => SshAgent.listen(agent.path, []));
+    }
+
+    // Connected clients are disconnected once the agent is closed
+    const client = connect(agent.path);
+    client.on('error', () => {});
+    await new Promise((resolve) => client.once('connect', resolve));
+    const disconnected = new Promise((resolve) =>
+        client.once('close', resolve)
+    );
+
+    agent.close();
+    agent.close();
+    await disconnected;
+    await t.throwsAsync(() => agentRequest(agent.path, Buffer.from([11])));
+    await passport.deleteAccount();
+});
+
+// Stock OpenSSH clients can only be used with the Unix socket
+const sshClientTest =
+    nativeAvailable &&
+    process.platform !== 'win32' &&
+    onPath('ssh-add') &&
+    onPath('ssh-keygen')
+        ? test
+        : test.skip;
+
+sshClientTest('use the SSH agent with ssh-add and ssh-keygen', async (t) => {
+    const passport = new Passport(
+        'software-ssh-client',
+        PassportBackend.Software
+    );
+    await passport.createAccount();
+    const agent = SshAgent.listen(agentPath('client'), [
+        {
+            accountId: 'software-ssh-client',
+            backend: PassportBackend.Software,
+            comment: 'passport',
+        },
+    ]);
+    const options = { env: { ...process.env, SSH_AUTH_SOCK: agent.path } };
+
+    const { stdout } = await promisify(execFile)('ssh-add', ['-L'], options);
+    t.deepEqual(stdout.trim().split('\n'), await agent.publicKeys());
+
+    // Sign using the key held by the agent and check the signature
+    const directory = mkdtempSync(join(tmpdir(), 'passport-ssh-'));
+    const keyFile = join(directory, 'id_rsa.pub');
+    const dataFile = join(directory, 'data');
+    writeFileSync(keyFile, stdout);
+    writeFileSync(dataFile, 'signed using passport');
+    await promisify(execFile)(
+        'ssh-keygen',
+        ['-Y', 'sign', '-f', keyFile, '-n', 'file', dataFile],
+        options
+    );
+    const check = promisify(execFile)(
+        'ssh-keygen',
+        ['-Y', 'check-novalidate', '-n', 'file', '-s', dataFile + '.sig'],
+        options
+    );
+    check.child.stdin?.end(readFileSync(dataFile));
+    t.regex((await check).stdout, /Good "file" signature/);
+
+    agent.close();
+    await passport.deleteAccount();
+});
+
+nativeTest('sign data in the SSH signature format', async (t) => {
+    const passport = new Passport('software-sshsig', PassportBackend.Software);
+    await passport.createAccount();
+
+    const openSsh = (
+        await passport.getPublicKey(PublicKeyEncoding.OpenSsh)
+    ).toString();
+    t.regex(openSsh, /^ssh-rsa AAAAB3NzaC1yc2E[\w+/=]+$/);
+
+    const data = Buffer.from('signed using passport');
+    const armored = await passport.signSshsig('file', data);
+    const lines = armored.trim().split('\n');
+    t.is(lines[0], '-----BEGIN SSH SIGNATURE-----');
+    t.is(lines[lines.length - 1], '-----END SSH SIGNATURE-----');
+    t.true(lines.slice(1, -1).every((line) => line.length <= 70));
+
+    const blob = Buffer.from(lines.slice(1, -1).join(''), 'base64');
+    t.is(blob.subarray(0, 6).toString(), 'SSHSIG');
+    t.is(blob.readUInt32BE(6), 1);
+    const [publicKey, namespace, reserved, hash, signature] = readSshStrings(
+        blob.subarray(10)
+    );
+    t.true(publicKey.equals(Buffer.from(openSsh.split(' ')[1], 'base64')));
+    t.is(namespace.toString(), 'file');
+    t.is(reserved.length, 0);
+    t.is(hash.toString(), 'sha512');
+
+    const [algorithm, value] = readSshStrings(signature);
+    t.is(algorithm.toString(), 'rsa-sha2-256');
+    const signed = Buffer.concat([
+        Buffer.from('SSHSIG'),
+        sshString('file'),
+        sshString(''),
+        sshString('sha512'),
+        sshString(createHash('sha512').update(data).digest()),
+    ]);
+    t.true(
+        verifySignature(
+            await passport.getPublicKey(PublicKeyEncoding.Pkcs1RsaPublicKey),
+            signed,
+            value
+        )
+    );
+
+    await t.throwsAsync(() => passport.signSshsig('', data), {
+        message: /namespace/,
+    });
+    await passport.deleteAccount();
+    await t.throwsAsync(() => passport.signSshsig('file', data));
+});
+
+sshClientTest('verify SSH signatures using ssh-keygen', async (t) => {
+    const passport = new Passport(
+        'software-sshsig-client',
+        PassportBackend.Software
+    );
+    await passport.createAccount();
+
+    const directory = mkdtempSync(join(tmpdir(), 'passport-sshsig-'));
+    const allowedSigners = join(directory, 'allowed_signers');
+    const signatureFile = join(directory, 'data.sig');
+    const publicKey = await passport.getPublicKey(PublicKeyEncoding.OpenSsh);
+    writeFileSync(allowedSigners, `passport@example.com ${publicKey}\n`);
+
+    const data = Buffer.from('tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904');
+    writeFileSync(signatureFile, await passport.signSshsig('git', data));
+    const verify = (namespace: string) => {
+        const result = promisify(execFile)('ssh-keygen', [
+            '-Y',
+            'verify',
+            '-f',
+            allowedSigners,
+            '-I',
+            'passport@example.com',
+            '-n',
+            namespace,
+            '-s',
+            signatureFile,
+        ]);
+        result.child.stdin?.end(data);
+        return result;
+    };
+
+    t.regex((await verify('git')).stdout, /Good "git" signature/);
+    await t.throwsAsync(() => verify('file'));
+    await passport.deleteAccount();
+});
+
+/** Split DER encoded data into its elements, returning each with its header */
+function derElements(data: Buffer): Buffer[] {
+    const elements = [];
+    for (let offset = 0; offset < data.length; ) {
+        let length = data[offset + 1];
+        let header = 2;
+        if (length & 0x80) {
+            header += length & 0x7f;
+            length = data.readUIntBE(offset + 2, length & 0x7f);
+        }
+
+        elements.push(data.subarray(offset, offset + header + length));
+        offset += header + length;
+    }
+
+    return elements;
+}
+
+/** Get the contents of a DER encoded element */
+function derContents(element: Buffer): Buffer {
+    const header = element[1] & 0x80 ? 2 + (element[1] & 0x7f) : 2;
+    return element.subarray(header);
+}
+
+nativeTest('create a certificate signing request', async (t) => {
+    const passport = new Passport('software-csr', PassportBackend.Software);
+    const keyUsage = {
+        oid: '2.5.29.15',
+        critical: true,
+        value: Buffer.from('03020780', 'hex'),
+    };
+    await t.throwsAsync(() => passport.createCsr('CN=device-1'));
+    await passport.createAccount();
+
+    const { der, pem } = await passport.createCsr(
+        'CN=device-1,O=Example',
+        ['DNS:device-1.example.com', 'IP:10.0.0.1', 'email:it@example.com'],
+        [keyUsage]
+    );
+    const lines = pem.trim().split('\n');
+    t.is(lines[0], '-----BEGIN CERTIFICATE REQUEST-----');
+    t.is(lines[lines.length - 1], '-----END CERTIFICATE REQUEST-----');
+    t.true(Buffer.from(lines.slice(1, -1).join(''), 'base64').equals(der));
+
+    // The request info contains the SPKI of the account key
+    const [request] = derElements(der);
+    const [info, algorithm, signature] = derElements(derContents(request));
+    const publicKey = await passport.getPublicKey(
+        PublicKeyEncoding.X509SubjectPublicKeyInfo
+    );
+    t.true(derElements(derContents(info))[2].equals(publicKey));
+    t.true(info.includes(Buffer.from('device-1.example.com')));
+    t.true(info.includes(Buffer.from([10, 0, 0, 1])));
+    t.true(info.includes(keyUsage.value));
+
+    // sha256WithRSAEncryption
+    const sha256WithRsa = '300d06092a864886f70d01010b0500';
+    t.true(algorithm.equals(Buffer.from(sha256WithRsa, 'hex')));
+    t.true(
+        verifySignature(
+            await passport.getPublicKey(PublicKeyEncoding.Pkcs1RsaPublicKey),
+            info,
+            derContents(signature).subarray(1)
+        )
+    );
+
+    // The inputs are checked before signing
+    const invalid: [string, string[]?, typeof keyUsage[]?][] = [
+        ['CN'],
+        ['CN=device-1', ['device-1.example.com']],
+        ['CN=device-1', ['IP:not-an-address']],
+        ['CN=device-1', [], [{ ...keyUsage, oid: 'key-usage' }]],
+        ['CN=device-1', [], [keyUsage, keyUsage]],
+        [
+            'CN=device-1',
+            ['DNS:device-1.example.com'],
+            [{ ...keyUsage, oid: '2.5.29.17' }],
+        ],
+    ];
+    for (const [subject, names, extensions] of invalid) {
+        await t.throwsAsync(() =>
+            passport.createCsr(subject, names, extensions)
+        );
+    }
+
+    await passport.deleteAccount();
+});
+
+// The OpenSSL command line tool is usually only installed on unix
+const opensslTest =
+    nativeAvailable && process.platform !== 'win32' && onPath('openssl')
+        ? test
+        : test.skip;
+
+opensslTest('verify certificate signing requests using openssl', async (t) => {
+    const passport = new Passport(
+        'software-csr-openssl',
+        PassportBackend.Software
+    );
+    await passport.createAccount();
+
+    const { pem } = await passport.createCsr('CN=device-1,O=Example', [
+        'DNS:device-1.example.com',
+    ]);
+    const openssl = promisify(execFile)('openssl', [
+        'req',
+        '-verify',
+        '-noout',
+        '-text',
+    ]);
+    openssl.child.stdin?.end(pem);
+
+    const { stdout, stderr } = await openssl;
+    t.regex(stdout + stderr, /verify OK/i);
+    // RFC 4514 lists the most significant name last
+    t.regex(stdout, /Subject: O ?= ?Example, CN ?= ?device-1/);
+    t.regex(stdout, /DNS:device-1\.example\.com/);
+    await passport.deleteAccount();
+});
+
+nativeTest('create self-signed certificates', async (t) => {
+    const passport = new Passport('software-cert', PassportBackend.Software);
+    const other = new Passport('software-cert-other', PassportBackend.Software);
+    await passport.createAccount();
+    await other.createAccount();
+
+    const { der, pem } = await passport.createSelfSignedCertificate(
+        'CN=device-1,O=Example',
+        30,
+        ['DNS:localhost', 'IP:127.0.0.1']
+    );
+    const certificate = new X509Certificate(pem);
+    t.true(certificate.raw.equals(der));
+    t.is(certificate.subject, 'O=Example\nCN=device-1');
+    t.is(certificate.issuer, certificate.subject);
+    t.is(certificate.checkHost('localhost'), 'localhost');
+    t.is(certificate.checkIP('127.0.0.1'), '127.0.0.1');
+    t.true(certificate.verify(certificate.publicKey));
+    t.false(certificate.ca);
+
+    const validity =
+        Date.parse(certificate.validTo) - Date.parse(certificate.validFrom);
+    t.is(validity, 30 * 24 * 60 * 60 * 1000);
+    t.true(Math.abs(Date.parse(certificate.validFrom) - Date.now()) < 60000);
+
+    // The certificate contains the key of the account
+    const publicKey = await passport.getPublicKey(
+        PublicKeyEncoding.X509SubjectPublicKeyInfo
+    );
+    t.true(
+        certificate.publicKey
+            .export({ format: 'der', type: 'spki' })
+            .equals(publicKey)
+    );
+    t.true(await passport.matchesCertificate(der));
+    t.true(await passport.matchesCertificate(Buffer.from(pem)));
+    t.false(await other.matchesCertificate(der));
+    await t.throwsAsync(() => passport.matchesCertificate(randomBytes(64)), {
+        message: /certificate is invalid/,
+    });
+
+    // Every certificate has a new serial number
+    const second = await passport.createSelfSignedCertificate('CN=device-1', 1);
+    const serialNumber = new X509Certificate(second.der).serialNumber;
+    t.not(serialNumber, certificate.serialNumber);
+    await t.throwsAsync(() =>
+        passport.createSelfSignedCertificate('CN=device-1', 0)
+    );
+
+    // The validity can't be encoded beyond the year 9999
+    const lastDay = Date.UTC(9999, 11, 31);
+    const maxDays = Math.floor((lastDay - Date.now()) / 86400000);
+    const last = await passport.createSelfSignedCertificate(
+        'CN=device-1',
+        maxDays
+    );
+    const validTo = new Date(new X509Certificate(last.der).validTo);
+    t.is(validTo.getUTCFullYear(), 9999);
+    for (const days of [maxDays + 2, 3000000, 4294967295]) {
+        await t.throwsAsync(
+            () => passport.createSelfSignedCertificate('CN=device-1', days),
+            { message: /9999-12-31/ }
+        );
+    }
+
+    await passport.deleteAccount();
+    await other.deleteAccount();
+    await t.throwsAsync(() => other.matchesCertificate(der));
+});
+
+nativeTest('sign content as detached CMS signature', async (t) => {
+    const passport = new Passport('software-cms', PassportBackend.Software);
+    const other = new Passport('software-cms-other', PassportBackend.Software);
+    await passport.createAccount();
+    await other.createAccount();
+
+    const certificate = await passport.createSelfSignedCertificate(
+        'CN=signer,O=Example',
+        30
+    );
+    const content = Buffer.from('The document to sign');
+    const { der, pem } = await passport.signCms(content, certificate.der);
+    t.regex(pem, /^-----BEGIN CMS-----\n/);
+
+    for (const signature of [der, Buffer.from(pem)]) {
+        const result = Passport.verifyCms(signature, content);
+        t.true(result.valid);
+        t.is(result.signer, 'CN=signer,O=Example');
+        t.true(result.certificate?.equals(certificate.der));
+        t.true(
+            Math.abs(Date.parse(result.signingTime!) - Date.now()) < 60000
+        );
+        t.is(result.error, undefined);
+    }
+
+    // Changed content, garbage and unknown signers are invalid
+    const tampered = Passport.verifyCms(der, Buffer.from('Another document'));
+    t.false(tampered.valid);
+    t.regex(tampered.error!, /does not match the message digest/);
+    t.is(tampered.signer, undefined);
+    t.false(Passport.verifyCms(randomBytes(64), content).valid);
+
+    const otherCertificate = await other.createSelfSignedCertificate(
+        'CN=signer,O=Example',
+        30
+    );
+    const forged = await other.signCms(content, otherCertificate.der);
+    t.true(Passport.verifyCms(forged.der, content).valid);
+    t.false(Passport.verifyCms(forged.der, content, certificate.der).valid);
+    t.false(Passport.verifyCms(der, content, otherCertificate.der).valid);
+
+    // The certificate must belong to the account
+    await t.throwsAsync(() => passport.signCms(content, otherCertificate.der), {
+        message: /not issued for the key of this account/,
+    });
+
+    await passport.deleteAccount();
+    await other.deleteAccount();
+});
+
+opensslTest('verify CMS signatures using openssl', async (t) => {
+    const passport = new Passport(
+        'software-cms-openssl',
+        PassportBackend.Software
+    );
+    await passport.createAccount();
+
+    const certificate = await passport.createSelfSignedCertificate(
+        'CN=signer',
+        1
+    );
+    const content = randomBytes(1024);
+    const { pem } = await passport.signCms(content, certificate.pem);
+
+    const dir = mkdtempSync(join(tmpdir(), 'passport-cms-'));
+    const files = {
+        content: join(dir, 'content.bin'),
+        signature: join(dir, 'signature.pem'),
+        certificate: join(dir, 'certificate.pem'),
+    };
+    writeFileSync(files.content, content);
+    writeFileSync(files.signature, pem);
+    writeFileSync(files.certificate, certificate.pem);
+
+    const verify = (data: string) =>
+        promisify(execFile)('openssl', [
+            'cms',
+            '-verify',
+            '-binary',
+            '-inform',
+            'PEM',
+            '-in',
+            files.signature,
+            '-content',
+            data,
+            '-CAfile',
+            files.certificate,
+            '-purpose',
+            'any',
+            '-out',
+            join(dir, 'verified.bin'),
+        ]);
+
+    const { stderr } = await verify(files.content);
+    t.regex(stderr, /Verification successful/);
+    t.true(readFileSync(join(dir, 'verified.bin')).equals(content));
+
+    writeFileSync(join(dir, 'tampered.bin'), randomBytes(1024));
+    await t.throwsAsync(() => verify(join(dir, 'tampered.bin')));
+    await passport.deleteAccount();
+});
+
+nativeTest('create OpenPGP keys and signatures', async (t) => {
+    const passport = new Passport('software-openpgp', PassportBackend.Software);
+    await passport.createAccount();
+
+    const userId = 'Release Bot <release@example.com>';
+    const key = await passport.createOpenpgpKey(userId, 1700000000);
+    t.is(key.creationTime, 1700000000);
+    t.regex(key.fingerprint, /^[0-9A-F]{40}$/);
+    t.is(key.keyId, key.fingerprint.slice(24));
+    t.regex(key.publicKey, /^-----BEGIN PGP PUBLIC KEY BLOCK-----\n\n/);
+    t.regex(key.publicKey, /\n=[A-Za-z0-9+/]{4}\n-----END PGP PUBLIC KEY/);
+
+    // The fingerprint only depends on the key and its creation time
+    const again = await passport.createOpenpgpKey(
+        'Another <another@example.com>',
+        1700000000
+    );
+    t.is(again.fingerprint, key.fingerprint);
+    const later = await passport.createOpenpgpKey(userId);
+    t.not(later.fingerprint, key.fingerprint);
+    t.true(Math.abs(later.creationTime - Date.now() / 1000) < 60);
+    await t.throwsAsync(() => passport.createOpenpgpKey(''));
+
+    const signature = await passport.signOpenpgp(
+        Buffer.from('release.tar.gz'),
+        key.creationTime
+    );
+    t.regex(signature, /^-----BEGIN PGP SIGNATURE-----\n\n/);
+    t.regex(signature, /-----END PGP SIGNATURE-----\n$/);
+
+    await passport.deleteAccount();
+    await t.throwsAsync(() => passport.signOpenpgp(Buffer.from('data'), 0));
+});
+
+// GnuPG is usually only installed on unix
+const gpgTest =
+    nativeAvailable && process.platform !== 'win32' && onPath('gpg')
+        ? test
+        : test.skip;
+
+gpgTest('verify OpenPGP signatures using gpg', async (t) => {
+    const passport = new Passport(
+        'software-openpgp-gpg',
+        PassportBackend.Software
+    );
+    await passport.createAccount();
+
+    const key = await passport.createOpenpgpKey(
+        'Release Bot <release@example.com>'
+    );
+    const data = randomBytes(1024);
+    const signature = await passport.signOpenpgp(data, key.creationTime);
+
+    const dir = mkdtempSync(join(tmpdir(), 'passport-gpg-'));
+    const files = {
+        key: join(dir, 'key.asc'),
+        data: join(dir, 'data.bin'),
+        signature: join(dir, 'data.bin.asc'),
+    };
+    writeFileSync(files.key, key.publicKey);
+    writeFileSync(files.data, data);
+    writeFileSync(files.signature, signature);
+
+    const gpg = (...args: string[]) =>
+        promisify(execFile)('gpg', ['--batch', '--homedir', dir, ...args]);
+    await gpg('--import', files.key);
+    const { stdout } = await gpg('--with-colons', '--list-keys');
+    t.regex(stdout, new RegExp(`fpr:+${key.fingerprint}:`));
+
+    const { stderr } = await gpg('--verify', files.signature, files.data);
+    t.regex(stderr, /Good signature from "Release Bot <release@example\.com>"/);
+
+    writeFileSync(files.data, randomBytes(1024));
+    await t.throwsAsync(() => gpg('--verify', files.signature, files.data));
+    await passport.deleteAccount();
+});
+
+nativeTest('sign data using the minisign-like format', async (t) => {
+    const passport = new Passport(
+        'software-minisign',
+        PassportBackend.Software
+    );
+    const other = new Passport(
+        'software-minisign-other',
+        PassportBackend.Software
+    );
+    await passport.createAccount();
+    await other.createAccount();
+
+    const publicKey = await passport.exportMinisignPublicKey();
+    const pkcs1 = await passport.getPublicKey(
+        PublicKeyEncoding.Pkcs1RsaPublicKey
+    );
+    const keyId = createHash('sha256').update(pkcs1).digest('hex');
+    t.is(
+        publicKey.split('\n')[0],
+        `untrusted comment: passport public key ${keyId.toUpperCase()}`
+    );
+
+    const data = randomBytes(256);
+    const signature = await passport.signMinisign(data, 'file:config.tar');
+    const lines = signature.split('\n');
+    t.is(lines.length, 4);
+    t.is(lines[2], 'trusted comment: file:config.tar');
+    t.is(Buffer.from(lines[1], 'base64').subarray(0, 2).toString(), 'RS');
+
+    const result = Passport.verifyMinisign(publicKey, signature, data);
+    t.true(result.valid);
+    t.is(result.keyId, keyId.toUpperCase());
+    t.is(result.trustedComment, 'file:config.tar');
+    t.is(result.error, undefined);
+
+    // The trusted comment defaults to the signing time
+    const timestamped = await passport.signMinisign(data);
+    const { trustedComment } = Passport.verifyMinisign(
+        publicKey,
+        timestamped,
+        data
+    );
+    const timestamp = Number(trustedComment?.replace('timestamp:', ''));
+    t.true(Math.abs(timestamp - Date.now() / 1000) < 60);
+
+    // Changed data, comments and keys are rejected
+    const tampered = Passport.verifyMinisign(
+        publicKey,
+        signature,
+        randomBytes(256)
+    );
+    t.false(tampered.valid);
+    t.regex(tampered.error!, /does not match the data/);
+    const comment = signature.replace('file:config.tar', 'file:other.tar');
+    t.false(Passport.verifyMinisign(publicKey, comment, data).valid);
+    const otherKey = await other.exportMinisignPublicKey();
+    t.regex(
+        Passport.verifyMinisign(otherKey, signature, data).error!,
+        /created by another key/
+    );
+    t.false(Passport.verifyMinisign(publicKey, 'garbage', data).valid);
+    await t.throwsAsync(() => passport.signMinisign(data, 'two\nlines'));
+
+    await passport.deleteAccount();
+    await other.deleteAccount();
+});
+
+nativeTest('sign HTTP requests using message signatures', async (t) => {
+    const passport = new Passport('software-http', PassportBackend.Software);
+    await passport.createAccount();
+
+    const request = {
+        method: 'POST',
+        targetUri: 'https://API.example.com:443/items?id=1#top',
+        headers: {
+            'Content-Type': 'application/json',
+            'Content-Digest':
+                'sha-256=:X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=:',
+        },
+    };
+    const result = await passport.signHttpRequest(request, {
+        components: [
+            '@method',
+            '@authority',
+            '@path',
+            '@query',
+            'content-type',
+        ],
+        created: 1700000000,
+        validitySeconds: 300,
+        keyId: 'client-1',
+        nonce: 'abc',
+    });
+
+    const params =
+        '("@method" "@authority" "@path" "@query" "content-type");' +
+        'created=1700000000;expires=1700000300;keyid="client-1";' +
+        'nonce="abc";alg="rsa-v1_5-sha256"';
+    t.is(
+        result.signatureBase,
+        [
+            '"@method": POST',
+            '"@authority": api.example.com',
+            '"@path": /items',
+            '"@query": ?id=1',
+            '"content-type": application/json',
+            `"@signature-params": ${params}`,
+        ].join('\n')
+    );
+    t.is(result.signatureInput, `sig1=${params}`);
+    t.regex(result.signature, /^sig1=:[A-Za-z0-9+/]+=*:$/);
+
+    // The signature is a plain RSASSA-PKCS1-v1_5 signature over the base
+    const publicKey = await passport.getPublicKey(
+        PublicKeyEncoding.X509SubjectPublicKeyInfo
+    );
+    const key = createPublicKey({
+        key: publicKey,
+        format: 'der',
+        type: 'spki',
+    });
+    const signature = Buffer.from(result.signature.slice(6, -1), 'base64');
+    t.true(verifySignature(key, Buffer.from(result.signatureBase), signature));
+
+    // The expired signature is rejected by the verifier
+    const expired = Passport.verifyHttpSignature(
+        request,
+        result.signatureInput,
+        result.signature,
+        publicKey
+    );
+    t.false(expired.valid);
+    t.regex(expired.error!, /expired/);
+
+    const now = await passport.signHttpRequest(request, {
+        components: ['@method', '@target-uri', 'content-digest'],
+        label: 'client',
+    });
+    t.regex(
+        now.signatureInput,
+        /^client=\(.*\);created=\d+;keyid="[0-9a-f]{64}";/
+    );
+    const pem = key.export({ format: 'pem', type: 'pkcs1' });
+    for (const encoded of [publicKey, Buffer.from(pem)]) {
+        const verified = Passport.verifyHttpSignature(
+            request,
+            now.signatureInput,
+            now.signature,
+            encoded
+        );
+        t.true(verified.valid);
+        t.is(verified.label, 'client');
+        t.deepEqual(verified.components, [
+            '@method',
+            '@target-uri',
+            'content-digest',
+        ]);
+        t.true(Math.abs(verified.created! - Date.now() / 1000) < 60);
+    }
+
+    // Signatures of other requests and multiple signatures
+    const changed = { ...request, method: 'PUT' };
+    t.false(
+        Passport.verifyHttpSignature(
+            changed,
+            now.signatureInput,
+            now.signature,
+            publicKey
+        ).valid
+    );
+    const inputs = `${result.signatureInput}, ${now.signatureInput}`;
+    const signatures = `${result.signature}, ${now.signature}`;
+    const multiple = Passport.verifyHttpSignature(
+        request,
+        inputs,
+        signatures,
+        publicKey
+    );
+    t.regex(multiple.error!, /multiple signatures/);
+    t.true(
+        Passport.verifyHttpSignature(
+            request,
+            inputs,
+            signatures,
+            publicKey,
+            'client'
+        ).valid
+    );
+    t.false(
+        Passport.verifyHttpSignature(request, 'sig1=(', 'x', publicKey).valid
+    );
+
+    // Invalid components and labels are rejected when signing
+    for (const options of [
+        { components: ['@unknown'] },
+        { components: ['Content-Type'] },
+        { components: ['@method', '@method'] },
+        { components: ['x-missing'] },
+        { components: ['@method'], label: 'Sig' },
+    ]) {
+        await t.throwsAsync(() => passport.signHttpRequest(request, options));
+    }
+
+    await passport.deleteAccount();
+});
+
+nativeTest('create and verify DPoP proofs', async (t) => {
+    const passport = new Passport('software-dpop', PassportBackend.Software);
+    const other = new Passport('software-dpop-other', PassportBackend.Software);
+    await passport.createAccount();
+    await other.createAccount();
+
+    const uri = 'https://API.example.com/items';
+    const proof = await passport.createDpopProof(
+        'GET',
+        `${uri}?page=2#top`,
+        'access-token',
+        'server-nonce'
+    );
+    const [header, claims, signature] = proof.split('.');
+    const decode = (part: string) =>
+        JSON.parse(Buffer.from(part, 'base64url').toString());
+
+    const { typ, alg, jwk } = decode(header);
+    t.is(typ, 'dpop+jwt');
+    t.is(alg, 'RS256');
+    const publicKey = await passport.getPublicKey(
+        PublicKeyEncoding.X509SubjectPublicKeyInfo
+    );
+    const key = createPublicKey({ key: jwk, format: 'jwk' });
+    t.true(key.export({ format: 'der', type: 'spki' }).equals(publicKey));
+    t.true(
+        verifySignature(
+            key,
+            Buffer.from(`${header}.${claims}`),
+            Buffer.from(signature, 'base64url')
+        )
+    );
+
+    const payload = decode(claims);
+    t.is(payload.htm, 'GET');
+    t.is(payload.htu, 'https://api.example.com/items');
+    t.is(payload.nonce, 'server-nonce');
+    t.is(
+        payload.ath,
+        createHash('sha256').update('access-token').digest('base64url')
+    );
+    t.true(Math.abs(payload.iat - Date.now() / 1000) < 60);
+    t.truthy(payload.jti);
+
+    // The thumbprint is computed as defined in RFC 7638
+    const thumbprint = createHash('sha256')
+        .update(JSON.stringify({ e: jwk.e, kty: 'RSA', n: jwk.n }))
+        .digest('base64url');
+    const result = Passport.verifyDpopProof(proof, 'GET', uri, {
+        accessToken: 'access-token',
+        nonce: 'server-nonce',
+        jkt: thumbprint,
+    });
+    t.true(result.valid);
+    t.is(result.jkt, thumbprint);
+    t.is(result.jti, payload.jti);
+    t.is(result.iat, payload.iat);
+    t.is(result.error, undefined);
+    t.true(Passport.verifyDpopProof(proof, 'GET', uri).valid);
+
+    // Every proof has a new ID
+    const second = await passport.createDpopProof('GET', uri);
+    t.not(Passport.verifyDpopProof(second, 'GET', uri).jti, payload.jti);
+
+    const otherProof = await other.createDpopProof('GET', uri);
+    const invalid: [string, string, string, any, RegExp][] = [
+        [proof, 'POST', uri, {}, /method/],
+        [proof, 'GET', `${uri}/1`, {}, /URI/],
+        [proof, 'GET', uri, { accessToken: 'other' }, /another access token/],
+        [second, 'GET', uri, { accessToken: 'access-token' }, /not bound/],
+        [proof, 'GET', uri, { nonce: 'other' }, /nonce/],
+        [otherProof, 'GET', uri, { jkt: thumbprint }, /key/],
+        [`${header}.${claims}.${signature.slice(2)}`, 'GET', uri, {}, /./],
+        ['garbage', 'GET', uri, {}, /three parts/],
+    ];
+    for (const [token, htm, htu, options, error] of invalid) {
+        const verification = Passport.verifyDpopProof(token, htm, htu, options);
+        t.false(verification.valid);
+        t.regex(verification.error!, error);
+    }
+
+    // Proofs are only accepted for maxAgeSeconds around their iat
+    const verifyAt = (token: string, time: number) =>
+        Passport.verifyDpopProof(token, 'GET', uri, { time });
+    t.true(verifyAt(proof, payload.iat + 300).valid);
+    t.true(verifyAt(proof, payload.iat - 300).valid);
+    t.regex(verifyAt(proof, payload.iat + 301).error!, /not issued recently/);
+    t.regex(verifyAt(proof, payload.iat - 301).error!, /not issued recently/);
+
+    // Anyone can sign a proof using their own key, including any iat,
+    // which is written as is, since it may not fit a javascript number
+    const attacker = generateKeyPairSync('rsa', { modulusLength: 2048 });
+    const forge = (iat: string) => {
+        const encode = (json: string) =>
+            Buffer.from(json).toString('base64url');
+        const input = [
+            encode(
+                JSON.stringify({
+                    typ: 'dpop+jwt',
+                    alg: 'RS256',
+                    jwk: attacker.publicKey.export({ format: 'jwk' }),
+                })
+            ),
+            encode(`{"jti":"a","htm":"GET","htu":"${uri}","iat":${iat}}`),
+        ].join('.');
+        const forged = sign('sha256', Buffer.from(input), attacker.privateKey);
+
+        return `${input}.${forged.toString('base64url')}`;
+    };
+    t.true(verifyAt(forge(`${payload.iat}`), payload.iat).valid);
+    for (const time of [0, payload.iat, Number.MAX_SAFE_INTEGER]) {
+        t.regex(
+            verifyAt(forge('18446744073709551615'), time).error!,
+            /not issued recently/
+        );
+    }
+
+    // Times after 2106 can be checked, negative times are rejected
+    t.true(verifyAt(forge('5000000000'), 5000000000).valid);
+    t.throws(() => verifyAt(proof, -1), { message: /must not be negative/ });
+
+    await passport.deleteAccount();
+    await other.deleteAccount();
+});
+
+nativeTest('store device-bound refresh tokens', async (t) => {
+    const passport = new Passport('software-refresh', PassportBackend.Software);
+    const other = new Passport(
+        'software-refresh-other',
+        PassportBackend.Software
+    );
+    await passport.createAccount();
+    await other.createAccount();
+
+    const path = join(mkdtempSync(join(tmpdir(), 'passport-')), 'token.bin');
+    await passport.storeRefreshToken(path, 'refresh-token-1');
+    t.false(readFileSync(path).includes('refresh-token-1'));
+    if (process.platform !== 'win32') {
+        t.is(statSync(path).mode & 0o777, 0o600);
+    }
+
+    const options = {
+        clientId: 'desktop-app',
+        audience: 'https://auth.example.com/token',
+    };
+    const grant = await passport.createRefreshTokenGrant(path, options);
+    t.is(grant.refreshToken, 'refresh-token-1');
+    t.is(
+        grant.clientAssertionType,
+        'urn:ietf:params:oauth:client-assertion-type:jwt-bearer'
+    );
+
+    const [header, claims, signature] = grant.clientAssertion.split('.');
+    const decode = (part: string) =>
+        JSON.parse(Buffer.from(part, 'base64url').toString());
+    const publicKey = await passport.getPublicKey(
+        PublicKeyEncoding.Pkcs1RsaPublicKey
+    );
+    t.true(
+        verifySignature(
+            publicKey,
+            Buffer.from(`${header}.${claims}`),
+            Buffer.from(signature, 'base64url')
+        )
+    );
+
+    // The key ID is the JWK thumbprint of the account key
+    const { alg, typ, kid } = decode(header);
+    t.is(alg, 'RS256');
+    t.is(typ, 'JWT');
+    const jwk = createPublicKey({
+        key: publicKey,
+        format: 'der',
+        type: 'pkcs1',
+    }).export({ format: 'jwk' });
+    const thumbprint = createHash('sha256')
+        .update(JSON.stringify({ e: jwk.e, kty: 'RSA', n: jwk.n }))
+        .digest('base64url');
+    t.is(kid, thumbprint);
+
+    const { iss, sub, aud, jti, iat, exp } = decode(claims);
+    t.is(iss, 'desktop-app');
+    t.is(sub, 'desktop-app');
+    t.is(aud, 'https://auth.example.com/token');
+    t.truthy(jti);
+    t.true(Math.abs(iat - Date.now() / 1000) < 60);
+    t.is(exp, iat + 60);
+
+    const assertion = await passport.createClientAssertion({
+        ...options,
+        lifetimeSeconds: 300,
+        keyId: 'client-key',
+    });
+    t.is(decode(assertion.split('.')[0]).kid, 'client-key');
+    const lifetime = decode(assertion.split('.')[1]);
+    t.is(lifetime.exp - lifetime.iat, 300);
+    t.not(decode(assertion.split('.')[1]).jti, jti);
+
+    // Rotated tokens replace the stored token
+    // The permissions of a stale temporary file are not kept
+    writeFileSync(`${path}.tmp`, 'stale', { mode: 0o644 });
+    await passport.storeRefreshToken(path, 'refresh-token-2');
+    t.false(existsSync(`${path}.tmp`));
+    if (process.platform !== 'win32') {
+        t.is(statSync(path).mode & 0o777, 0o600);
+    }
+    const rotated = await passport.createRefreshTokenGrant(path, options);
+    t.is(rotated.refreshToken, 'refresh-token-2');
+
+    // The file is useless without the account key
+    await t.throwsAsync(() => other.createRefreshTokenGrant(path, options), {
+        message: /protected by the account 'software-refresh'/,
+    });
+    await t.throwsAsync(
+        () => passport.createRefreshTokenGrant(`${path}.missing`, options),
+        { message: /could not be read/ }
+    );
+    await t.throwsAsync(() =>
+        passport.createClientAssertion({ ...options, clientId: '' })
+    );
+
+    await passport.deleteAccount();
+    await other.deleteAccount();
+});
+
+nativeTest('generate one-time passwords from stored secrets', async (t) => {
+    const passport = new Passport('software-otp', PassportBackend.Software);
+    const other = new Passport('software-otp-other', PassportBackend.Software);
+    await passport.createAccount();
+    await other.createAccount();
+
+    const path = join(mkdtempSync(join(tmpdir(), 'passport-')), 'otp.json');
+    const store = passport.openOtpStore(path);
+    t.deepEqual(store.labels(), []);
+
+    // The secret "12345678901234567890" of RFC 4226 and RFC 6238
+    const secret = 'GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ';
+    await store.add('totp', { secret, digits: 8 });
+    await store.add('hotp', { secret: secret.toLowerCase(), counter: 0 });
+    await store.add('sha512', {
+        secret: secret.repeat(3) + 'GEZDGNA=',
+        algorithm: OtpAlgorithm.Sha512,
+        digits: 8,
+    });
+    t.deepEqual(store.labels(), ['hotp', 'sha512', 'totp']);
+    t.false(readFileSync(path).includes(secret));
+
+    t.is(await store.generateCode('totp', 59), '94287082');
+    t.is(await store.generateCode('totp', 1111111109), '07081804');
+    t.is(await store.generateCode('totp', 2000000000), '69279037');
+    t.is(await store.generateCode('totp', 20000000000), '65353130');
+    await t.throwsAsync(() => store.generateCode('totp', -1), {
+        message: /must not be negative/,
+    });
+    t.regex(await store.generateCode('totp'), /^\d{8}$/);
+    t.is(await store.generateCode('sha512', 59), '90693936');
+
+    // Every HOTP code increments the counter
+    t.is(await store.generateCode('hotp'), '755224');
+    t.is(await store.generateCode('hotp'), '287082');
+    t.is(await passport.openOtpStore(path).generateCode('hotp'), '359152');
+
+    // The secrets are useless without the account key
+    await t.throwsAsync(() => other.openOtpStore(path).generateCode('totp'), {
+        message: /protected by the account 'software-otp'/,
+    });
+    await t.throwsAsync(() => store.generateCode('missing'), {
+        message: /no secret labeled 'missing'/,
+    });
+    await t.throwsAsync(() => store.add('invalid', { secret: 'not base32!' }), {
+        message: /not a valid base32 string/,
+    });
+    await t.throwsAsync(() => store.add('invalid', { secret, digits: 4 }));
+
+    store.remove('totp');
+    t.deepEqual(store.labels(), ['hotp', 'sha512']);
+    t.throws(() => store.remove('totp'));
+
+    await passport.deleteAccount();
+    await other.deleteAccount();
+    t.throws(() => passport.openOtpStore(path));
+    await t.throwsAsync(() => store.generateCode('sha512'), {
+        message: /account does not exist/,
+    });
+});
+
+// Claims passed as string are signed verbatim, so they
+// may contain numbers javascript can't represent
+function signEntitlement(
+    privateKey: KeyObject,
+    claims: object | string
+): string {
+    const encode = (value: object | string) =>
+        Buffer.from(
+            typeof value === 'string' ? value : JSON.stringify(value)
+        ).toString('base64url');
+    const input = `${encode({ alg: 'RS256', typ: 'JWT' })}.${encode(claims)}`;
+    const signature = sign('sha256', Buffer.from(input), privateKey);
+    return `${input}.${signature.toString('base64url')}`;
+}
+
+nativeTest('countersign and verify entitlement tokens', async (t) => {
+    const passport = new Passport(
+        'software-entitlement',
+        PassportBackend.Software
+    );
+    await passport.createAccount();
+
+    const server = generateKeyPairSync('rsa', { modulusLength: 2048 });
+    const other = generateKeyPairSync('rsa', { modulusLength: 2048 });
+    const serverKey = Buffer.from(
+        server.publicKey.export({ format: 'pem', type: 'spki' }) as string
+    );
+    const otherKey = other.publicKey.export({ format: 'der', type: 'pkcs1' });
+    const claims = { sub: 'user', exp: 2000, features: ['pro'] };
+    const token = signEntitlement(server.privateKey, claims);
+
+    // Tokens are verified using the pinned key of the server
+    const verified = Passport.verifyEntitlement(token, serverKey, {
+        time: 1000,
+    });
+    t.true(verified.valid, verified.error ?? undefined);
+    t.deepEqual(JSON.parse(verified.claims!), claims);
+    t.is(verified.expiresAt, 2000);
+    t.is(verified.deviceJkt, undefined);
+    t.regex(
+        Passport.verifyEntitlement(token, otherKey, { time: 1000 }).error!,
+        /does not match the pinned key/
+    );
+    t.regex(
+        Passport.verifyEntitlement(token, serverKey, { time: 2060 }).error!,
+        /has expired/
+    );
+    t.false(Passport.verifyEntitlement(token, serverKey).valid);
+    t.regex(
+        Passport.verifyEntitlement(token, serverKey, {
+            time: 1000,
+            requireCountersignature: true,
+        }).error!,
+        /not countersigned/
+    );
+
+    // The countersignature is a second signature of the JWS
+    const report = await passport.countersignEntitlement(token, serverKey);
+    const { payload, signatures } = JSON.parse(report);
+    t.is(payload, token.split('.')[1]);
+    t.is(signatures.length, 2);
+    const publicKey = await passport.getPublicKey(
+        PublicKeyEncoding.Pkcs1RsaPublicKey
+    );
+    t.true(
+        verifySignature(
+            publicKey,
+            Buffer.from(`${signatures[1].protected}.${payload}`),
+            Buffer.from(signatures[1].signature, 'base64url')
+        )
+    );
+
+    const jwk = createPublicKey({
+        key: publicKey,
+        format: 'der',
+        type: 'pkcs1',
+    }).export({ format: 'jwk' });
+    const jkt = createHash('sha256')
+        .update(JSON.stringify({ e: jwk.e, kty: jwk.kty, n: jwk.n }))
+        .digest('base64url');
+    const countersigned = Passport.verifyEntitlement(report, serverKey, {
+        time: 1000,
+        deviceKey: publicKey,
+    });
+    t.true(countersigned.valid, countersigned.error ?? undefined);
+    t.is(countersigned.deviceJkt, jkt);
+    t.regex(
+        Passport.verifyEntitlement(report, serverKey, {
+            time: 1000,
+            deviceKey: otherKey,
+        }).error!,
+        /another device/
+    );
+
+    // The countersignature is bound to the server's signature
+    const forged = signEntitlement(other.privateKey, claims);
+    const moved = JSON.stringify({
+        payload,
+        signatures: [
+            { ...signatures[0], signature: forged.split('.')[2] },
+            signatures[1],
+        ],
+    });
+    t.regex(
+        Passport.verifyEntitlement(moved, otherKey, { time: 1000 }).error!,
+        /belongs to another signature/
+    );
+
+    await t.throwsAsync(
+        () => passport.countersignEntitlement(report, serverKey),
+        { message: /already countersigned/ }
+    );
+    await t.throwsAsync(
+        () => passport.countersignEntitlement(token, otherKey),
+        { message: /does not match the pinned key/ }
+    );
+    t.regex(
+        Passport.verifyEntitlement('', serverKey).error!,
+        /compact or JSON serialization/
+    );
+    t.false(Passport.verifyEntitlement(token, Buffer.alloc(0)).valid);
+
+    await passport.deleteAccount();
+});
+
+// Both signatures are verified without any key backend,
+// so this runs wherever the native module loads
+nativeTest('verify entitlement tokens countersigned elsewhere', (t) => {
+    const server = generateKeyPairSync('rsa', { modulusLength: 2048 });
+    const device = generateKeyPairSync('rsa', { modulusLength: 2048 });
+    const serverKey = server.publicKey.export({ format: 'der', type: 'spki' });
+    const deviceKey = device.publicKey.export({ format: 'der', type: 'spki' });
+    const jwk = device.publicKey.export({ format: 'jwk' });
+
+    const countersign = (token: string) => {
+        const [protectedHeader, payload, signature] = token.split('.');
+        const header = Buffer.from(
+            JSON.stringify({
+                alg: 'RS256',
+                jwk: { kty: jwk.kty, n: jwk.n, e: jwk.e },
+                countersigns: createHash('sha256')
+                    .update(Buffer.from(signature, 'base64url'))
+                    .digest('base64url'),
+            })
+        ).toString('base64url');
+        const countersignature = sign(
+            'sha256',
+            Buffer.from(`${header}.${payload}`),
+            device.privateKey
+        );
+        return {
+            payload,
+            signatures: [
+                { protected: protectedHeader, signature },
+                {
+                    protected: header,
+                    signature: countersignature.toString('base64url'),
+                },
+            ],
+        };
+    };
+
+    const report = countersign(
+        signEntitlement(server.privateKey, { sub: 'user', exp: 2000 })
+    );
+    const verified = Passport.verifyEntitlement(
+        JSON.stringify(report),
+        serverKey,
+        { time: 1000, deviceKey }
+    );
+    t.true(verified.valid, verified.error ?? undefined);
+    t.is(verified.expiresAt, 2000);
+
+    // A countersignature moved to another server signature is rejected
+    const other = signEntitlement(server.privateKey, { sub: 'other' });
+    const moved = JSON.stringify({
+        ...report,
+        signatures: [
+            { ...report.signatures[0], signature: other.split('.')[2] },
+            report.signatures[1],
+        ],
+    });
+    t.regex(
+        Passport.verifyEntitlement(moved, serverKey, { time: 1000 }).error!,
+        /does not match the pinned key/
+    );
+    const forged = countersign(other);
+    t.regex(
+        Passport.verifyEntitlement(
+            JSON.stringify({
+                payload: report.payload,
+                signatures: [report.signatures[0], forged.signatures[1]],
+            }),
+            serverKey,
+            { time: 1000 }
+        ).error!,
+        /belongs to another signature/
+    );
+
+    // Timestamps beyond the range of the options don't overflow
+    const maxExp = signEntitlement(
+        server.privateKey,
+        '{"sub":"user","exp":18446744073709551615}'
+    );
+    t.regex(
+        Passport.verifyEntitlement(maxExp, serverKey, {
+            time: Number.MAX_SAFE_INTEGER,
+            leewaySeconds: 4294967295,
+        }).error!,
+        /'exp' claim is out of range/
+    );
+    const maxNbf = signEntitlement(
+        server.privateKey,
+        '{"sub":"user","nbf":18446744073709551615}'
+    );
+    t.regex(
+        Passport.verifyEntitlement(maxNbf, serverKey, {
+            time: Number.MAX_SAFE_INTEGER,
+            leewaySeconds: 4294967295,
+        }).error!,
+        /not valid yet/
+    );
+
+    // Times after 2106 can be checked, negative times are rejected
+    const late = signEntitlement(server.privateKey, {
+        sub: 'user',
+        exp: 5000000000,
+    });
+    t.true(
+        Passport.verifyEntitlement(late, serverKey, { time: 4300000000 }).valid
+    );
+    t.regex(
+        Passport.verifyEntitlement(late, serverKey, { time: 5000000060 })
+            .error!,
+        /has expired/
+    );
+    t.throws(() => Passport.verifyEntitlement(late, serverKey, { time: -1 }), {
+        message: /must not be negative/,
+    });
+});
+
+unixTest('check Passport exceptions on unix', (t) => {
+    t.throws(() => new Passport('test'), MODULE_NOT_FOUND);
+    t.throws(() => Passport.accountWithIdExists('test'), MODULE_NOT_FOUND);
+    t.throws(() => Passport.requestVerification('test'), MODULE_NOT_FOUND);
+    t.throws(() => Passport.setPolkitAction(), MODULE_NOT_FOUND);
+    t.throws(() => Passport.setTpmConsentProvider(), MODULE_NOT_FOUND);
+    t.throws(() => Passport.configureKeyStore({}), MODULE_NOT_FOUND);
+    t.throws(() => Passport.setKeyStorePassphrase(null), MODULE_NOT_FOUND);
+    t.throws(() => Passport.decodeEnvelope(Buffer.alloc(0)), MODULE_NOT_FOUND);
+    t.throws(
+        () => Passport.verifyCms(Buffer.alloc(0), Buffer.alloc(0)),
+        MODULE_NOT_FOUND
+    );
+    t.throws(
+        () => Passport.verifyMinisign('', '', Buffer.alloc(0)),
+        MODULE_NOT_FOUND
+    );
+    t.throws(
+        () =>
+            Passport.verifyHttpSignature(
+                { method: 'GET', targetUri: 'https://example.com' },
+                '',
+                '',
+                Buffer.alloc(0)
+            ),
+        MODULE_NOT_FOUND
+    );
+    t.throws(
+        () => Passport.verifyDpopProof('', 'GET', 'https://example.com'),
+        MODULE_NOT_FOUND
+    );
+    t.throws(
+        () => Passport.verifyEntitlement('', Buffer.alloc(0)),
+        MODULE_NOT_FOUND
+    );
+    t.throws(() => SshAgent.listen('agent.sock', []), MODULE_NOT_FOUND);
+    t.notThrows(() => Passport.available());
+    t.false(Passport.available());
+});
+
+unixTest('check AuditSink exceptions on unix', (t) => {
+    t.throws(() => AuditSink.file('audit.jsonl'), MODULE_NOT_FOUND);
+    t.throws(() => AuditSink.verifyFile('audit.jsonl'), MODULE_NOT_FOUND);
+    t.throws(() => Passport.setAuditSink(null as any), MODULE_NOT_FOUND);
+});
+
+unixTest('check Logger exceptions on unix', (t) => {
+    t.throws(() => Logger.logToFile('log.jsonl'), MODULE_NOT_FOUND);
+    t.throws(() => Logger.disable(), MODULE_NOT_FOUND);
+    t.throws(() => LogLevel.Debug, MODULE_NOT_FOUND);
+});
+
+unixTest('check DigestAlgorithm exceptions on unix', (t) => {
+    t.throws(() => DigestAlgorithm.Sha256, MODULE_NOT_FOUND);
+    t.throws(() => DigestAlgorithm.Sha384, MODULE_NOT_FOUND);
+    t.throws(() => DigestAlgorithm.Sha512, MODULE_NOT_FOUND);
+});
+
+unixTest('check OtpAlgorithm exceptions on unix', (t) => {
+    t.throws(() => OtpAlgorithm.Sha1, MODULE_NOT_FOUND);
+    t.throws(() => OtpAlgorithm.Sha256, MODULE_NOT_FOUND);
+    t.throws(() => OtpAlgorithm.Sha512, MODULE_NOT_FOUND);
+});
+
+unixTest('check PassportBackend exceptions on unix', (t) => {
+    t.throws(() => PassportBackend.WindowsHello, MODULE_NOT_FOUND);
+    t.throws(() => PassportBackend.Software, MODULE_NOT_FOUND);
+    t.throws(() => PassportBackend.Tpm, MODULE_NOT_FOUND);
+    t.throws(() => PassportBackend.Pkcs11, MODULE_NOT_FOUND);
+    t.throws(() => PassportBackend.Local, MODULE_NOT_FOUND);
+});
+
+unixTest('check ConsentProvider exceptions on unix', (t) => {
+    t.throws(() => ConsentProvider.WindowsHello, MODULE_NOT_FOUND);
+    t.throws(() => ConsentProvider.Fprintd, MODULE_NOT_FOUND);
+    t.throws(() => ConsentProvider.Polkit, MODULE_NOT_FOUND);
 });
 
 unixTest('check KeyCreationOption exceptions on unix', (t) => {
@@ -104,6 +2760,7 @@ unixTest('check PublicKeyEncoding exceptions on unix', (t) => {
     t.throws(() => PublicKeyEncoding.BCryptEccFullPublicKey, MODULE_NOT_FOUND);
     t.throws(() => PublicKeyEncoding.BCryptPublicKey, MODULE_NOT_FOUND);
     t.throws(() => PublicKeyEncoding.Capi1PublicKey, MODULE_NOT_FOUND);
+    t.throws(() => PublicKeyEncoding.OpenSsh, MODULE_NOT_FOUND);
 });
 
 unixTest('check VerificationResult exceptions on unix', (t) => {
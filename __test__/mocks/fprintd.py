@@ -0,0 +1,68 @@
+'''fprintd mock template
+
+This creates a fprintd manager with a single fingerprint reader.
+The results reported by the reader are passed in the "results"
+parameter and consumed one by one for every scan. The reader
+reports "verify-no-match" once all results were consumed.
+
+Parameters:
+    results: the VerifyStatus results to report, defaults to a match
+    enrolled: whether the user has enrolled prints, defaults to True
+    device: whether a reader is present, defaults to True
+    busy: whether the reader was claimed by another user
+'''
+
+import dbus
+
+from dbusmock import mockobject
+
+BUS_NAME = 'net.reactivated.Fprint'
+MAIN_OBJ = '/net/reactivated/Fprint/Manager'
+MAIN_IFACE = 'net.reactivated.Fprint.Manager'
+SYSTEM_BUS = True
+
+DEVICE_PATH = '/net/reactivated/Fprint/Device/0'
+DEVICE_IFACE = 'net.reactivated.Fprint.Device'
+ERROR_PREFIX = 'net.reactivated.Fprint.Error.'
+
+
+def error(name, message):
+    return f'raise dbus.exceptions.DBusException({message!r}, name={ERROR_PREFIX + name!r})'
+
+
+def load(mock, parameters):
+    if not parameters.get('device', True):
+        mock.AddMethod(MAIN_IFACE, 'GetDefaultDevice', '', 'o',
+                       error('NoSuchDevice', 'No devices available'))
+        return
+
+    mock.AddMethod(MAIN_IFACE, 'GetDefaultDevice', '', 'o',
+                   f'ret = dbus.ObjectPath({DEVICE_PATH!r})')
+
+    mock.AddObject(DEVICE_PATH, DEVICE_IFACE, {}, [])
+    device = mockobject.objects[DEVICE_PATH]
+    device.results = list(parameters.get('results', ['verify-match']))
+
+    if parameters.get('busy', False):
+        claim = error('AlreadyInUse', 'Device was already claimed')
+    else:
+        claim = ''
+
+    if parameters.get('enrolled', True):
+        verify_start = f'''
+while True:
+    result = self.results.pop(0) if self.results else "verify-no-match"
+    done = result != "verify-retry-scan"
+    self.EmitSignal({DEVICE_IFACE!r}, "VerifyStatus", "sb", [result, done])
+    if done:
+        break
+'''
+    else:
+        verify_start = error('NoEnrolledPrints', 'No enrolled prints')
+
+    device.AddMethods(DEVICE_IFACE, [
+        ('Claim', 's', '', claim),
+        ('Release', '', '', ''),
+        ('VerifyStart', 's', '', verify_start),
+        ('VerifyStop', '', '', ''),
+    ])
@@ -0,0 +1,43 @@
+'''polkitd mock template
+
+This creates a polkit authority answering CheckAuthorization
+calls from unix processes, like polkit does after the user
+interacted with the authentication agent.
+
+Parameters:
+    allowed: the action IDs the user is authorized for
+    dismissed: whether the user dismissed the authentication dialog
+    challenge: whether there is no authentication agent to ask the user
+'''
+
+import dbus
+
+BUS_NAME = 'org.freedesktop.PolicyKit1'
+MAIN_OBJ = '/org/freedesktop/PolicyKit1/Authority'
+MAIN_IFACE = 'org.freedesktop.PolicyKit1.Authority'
+SYSTEM_BUS = True
+
+ERROR_PREFIX = 'org.freedesktop.PolicyKit1.Error.'
+
+
+def load(mock, parameters):
+    mock.allowed = list(parameters.get('allowed', []))
+    mock.dismissed = parameters.get('dismissed', False)
+    mock.challenge = parameters.get('challenge', False)
+
+    mock.AddMethod(MAIN_IFACE, 'CheckAuthorization', '(sa{sv})sa{ss}us', '(bba{ss})', f'''
+subject, action_id, details, flags, _ = args
+if subject[0] != "unix-process" or "pid" not in subject[1] or "start-time" not in subject[1]:
+    raise dbus.exceptions.DBusException("Invalid subject", name="{ERROR_PREFIX}Failed")
+if not flags & 1:
+    raise dbus.exceptions.DBusException("User interaction is required", name="{ERROR_PREFIX}Failed")
+if "message" not in details:
+    raise dbus.exceptions.DBusException("The message is missing", name="{ERROR_PREFIX}Failed")
+
+if self.dismissed:
+    ret = (False, False, {{"polkit.dismissed": "true"}})
+elif action_id in self.allowed:
+    ret = (True, False, {{}})
+else:
+    ret = (False, self.challenge, {{}})
+''')
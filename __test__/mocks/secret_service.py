@@ -0,0 +1,190 @@
+'''Secret Service mock template
+
+This creates a Secret Service keeping collections and items in memory.
+Only the "plain" algorithm is supported for transferring secrets.
+
+Parameters:
+    locked: whether new collections and their items are locked until
+            they are unlocked using a prompt, defaults to False
+    dismiss: whether the user dismisses all prompts, defaults to False
+'''
+
+import dbus
+
+from dbusmock import mockobject
+
+BUS_NAME = 'org.freedesktop.secrets'
+MAIN_OBJ = '/org/freedesktop/secrets'
+MAIN_IFACE = 'org.freedesktop.Secret.Service'
+SYSTEM_BUS = False
+
+COLLECTION_IFACE = 'org.freedesktop.Secret.Collection'
+ITEM_IFACE = 'org.freedesktop.Secret.Item'
+SESSION_IFACE = 'org.freedesktop.Secret.Session'
+PROMPT_IFACE = 'org.freedesktop.Secret.Prompt'
+ERROR_PREFIX = 'org.freedesktop.Secret.Error.'
+
+SESSION_PATH = '/org/freedesktop/secrets/session/plain'
+NO_PROMPT = dbus.ObjectPath('/')
+
+
+def call(method):
+    '''Forward a mocked method to a function of this template.'''
+    return f'ret = objects[{MAIN_OBJ!r}].template.{method}(self, *args)'
+
+
+class Template:
+    def __init__(self, mock, parameters):
+        self.mock = mock
+        self.locked = parameters.get('locked', False)
+        self.dismiss = parameters.get('dismiss', False)
+        self.counter = 0
+
+    def next_path(self, prefix):
+        self.counter += 1
+        return dbus.ObjectPath(f'{prefix}/{self.counter}')
+
+    def prompt(self, result, on_complete=None):
+        '''Create a prompt completing with the given result.'''
+        path = self.next_path('/org/freedesktop/secrets/prompt')
+        self.mock.AddObject(path, PROMPT_IFACE, {}, [
+            ('Prompt', 's', '', f'objects[{MAIN_OBJ!r}].template.complete(self)'),
+            ('Dismiss', '', '', ''),
+        ])
+
+        prompt = mockobject.objects[path]
+        prompt.result = result
+        prompt.on_complete = on_complete
+        return path
+
+    def complete(self, prompt):
+        if self.dismiss:
+            prompt.EmitSignal(PROMPT_IFACE, 'Completed', 'bv',
+                              [True, dbus.String('', variant_level=1)])
+            return
+
+        if prompt.on_complete:
+            prompt.on_complete()
+        prompt.EmitSignal(PROMPT_IFACE, 'Completed', 'bv', [False, prompt.result])
+
+    def open_session(self, _service, algorithm, _input):
+        if algorithm != 'plain':
+            raise dbus.exceptions.DBusException(
+                'Only plain sessions are supported',
+                name='org.freedesktop.DBus.Error.NotSupported')
+
+        if SESSION_PATH not in mockobject.objects:
+            self.mock.AddObject(SESSION_PATH, SESSION_IFACE, {}, [('Close', '', '', '')])
+        return (dbus.String('', variant_level=1), dbus.ObjectPath(SESSION_PATH))
+
+    def create_collection(self, _service, properties, _alias):
+        path = self.next_path('/org/freedesktop/secrets/collection')
+        label = properties.get('org.freedesktop.Secret.Collection.Label', '')
+        self.mock.AddObject(path, COLLECTION_IFACE, {
+            'Label': dbus.String(label),
+            'Locked': dbus.Boolean(self.locked),
+            'Items': dbus.Array([], signature='o'),
+        }, [
+            ('CreateItem', 'a{sv}(oayays)b', 'oo', call('create_item')),
+            ('SearchItems', 'a{ss}', 'ao', call('search_collection')),
+            ('Delete', '', 'o', ''),
+        ])
+
+        collections = self.mock.Get(MAIN_IFACE, 'Collections')
+        self.mock.Set(MAIN_IFACE, 'Collections', dbus.Array(list(collections) + [path], signature='o'))
+
+        # Creating a collection always requires a prompt
+        return (NO_PROMPT, self.prompt(dbus.ObjectPath(path, variant_level=1)))
+
+    def create_item(self, collection, properties, secret, replace):
+        if collection.Get(COLLECTION_IFACE, 'Locked'):
+            raise dbus.exceptions.DBusException('The collection is locked', name=ERROR_PREFIX + 'IsLocked')
+
+        attributes = dict(properties.get('org.freedesktop.Secret.Item.Attributes', {}))
+        for path in collection.Get(COLLECTION_IFACE, 'Items'):
+            item = mockobject.objects[path]
+            if replace and dict(item.Get(ITEM_IFACE, 'Attributes')) == attributes:
+                item.secret = bytes(secret[2])
+                return (path, NO_PROMPT)
+
+        path = self.next_path(collection.path + '/item')
+        self.mock.AddObject(path, ITEM_IFACE, {
+            'Label': dbus.String(properties.get('org.freedesktop.Secret.Item.Label', '')),
+            'Attributes': dbus.Dictionary(attributes, signature='ss'),
+            'Locked': dbus.Boolean(False),
+        }, [
+            ('GetSecret', 'o', '(oayays)', call('get_secret')),
+            ('Delete', '', 'o', call('delete_item')),
+        ])
+
+        item = mockobject.objects[path]
+        item.secret = bytes(secret[2])
+        item.content_type = str(secret[3])
+        item.collection = collection
+        items = collection.Get(COLLECTION_IFACE, 'Items')
+        collection.Set(COLLECTION_IFACE, 'Items', dbus.Array(list(items) + [path], signature='o'))
+        return (path, NO_PROMPT)
+
+    def items(self):
+        for path in self.mock.Get(MAIN_IFACE, 'Collections'):
+            for item in mockobject.objects[path].Get(COLLECTION_IFACE, 'Items'):
+                yield mockobject.objects[item]
+
+    @staticmethod
+    def matches(item, attributes):
+        return all(item.Get(ITEM_IFACE, 'Attributes').get(k) == v for k, v in attributes.items())
+
+    def search_items(self, _service, attributes):
+        unlocked, locked = [], []
+        for item in self.items():
+            if self.matches(item, attributes):
+                is_locked = item.collection.Get(COLLECTION_IFACE, 'Locked')
+                (locked if is_locked else unlocked).append(dbus.ObjectPath(item.path))
+
+        return (dbus.Array(unlocked, signature='o'), dbus.Array(locked, signature='o'))
+
+    def search_collection(self, collection, attributes):
+        items = [mockobject.objects[p] for p in collection.Get(COLLECTION_IFACE, 'Items')]
+        return dbus.Array([dbus.ObjectPath(i.path) for i in items if self.matches(i, attributes)], signature='o')
+
+    def unlock(self, _service, objects):
+        collections = set()
+        for path in objects:
+            obj = mockobject.objects[path]
+            collections.add(obj.collection if hasattr(obj, 'collection') else obj)
+
+        def on_complete():
+            for collection in collections:
+                collection.Set(COLLECTION_IFACE, 'Locked', dbus.Boolean(False))
+
+        if not any(c.Get(COLLECTION_IFACE, 'Locked') for c in collections):
+            return (dbus.Array(objects, signature='o'), NO_PROMPT)
+
+        result = dbus.Array(objects, signature='o', variant_level=1)
+        return (dbus.Array([], signature='o'), self.prompt(result, on_complete))
+
+    def get_secret(self, item, session):
+        if item.collection.Get(COLLECTION_IFACE, 'Locked'):
+            raise dbus.exceptions.DBusException('The item is locked', name=ERROR_PREFIX + 'IsLocked')
+        if session != SESSION_PATH:
+            raise dbus.exceptions.DBusException('The session does not exist', name=ERROR_PREFIX + 'NoSession')
+
+        return (dbus.ObjectPath(session), dbus.ByteArray(b''), dbus.ByteArray(item.secret), item.content_type)
+
+    def delete_item(self, item):
+        collection = item.collection
+        items = [p for p in collection.Get(COLLECTION_IFACE, 'Items') if p != item.path]
+        collection.Set(COLLECTION_IFACE, 'Items', dbus.Array(items, signature='o'))
+        self.mock.RemoveObject(item.path)
+        return NO_PROMPT
+
+
+def load(mock, parameters):
+    mock.template = Template(mock, parameters)
+    mock.AddProperties(MAIN_IFACE, {'Collections': dbus.Array([], signature='o')})
+    mock.AddMethods(MAIN_IFACE, [
+        ('OpenSession', 'sv', 'vo', call('open_session')),
+        ('CreateCollection', 'a{sv}s', 'oo', call('create_collection')),
+        ('SearchItems', 'a{ss}', 'aoao', call('search_items')),
+        ('Unlock', 'ao', 'aoo', call('unlock')),
+    ])
@@ -1,8 +1,8 @@
 extern crate napi_build;
 
 fn main() {
-    #[cfg(not(windows))]
-    panic!("This crate does not support non-Windows platforms");
+    #[cfg(not(any(windows, target_os = "linux")))]
+    panic!("This crate only supports Windows and Linux");
 
     napi_build::setup();
 }
@@ -0,0 +1,150 @@
+use crate::backend::{sign_each, KeyBackend};
+use crate::node::key_creation_option::KeyCreationOption;
+use crate::node::public_key_encoding::PublicKeyEncoding;
+use crate::ssh::openssh_public_key;
+use crate::util::errors::OperationError;
+use crate::util::mappers::MapNapiError;
+use crate::util::traits::IntoWinBuffer;
+use crate::win::passport::{create_passport_key, get_passport_account, get_passport_account_sync};
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use tracing::Instrument;
+use windows::core::HSTRING;
+use windows::Security::Credentials::{KeyCredential, KeyCredentialManager, KeyCredentialStatus};
+
+/// The backend storing keys using Windows Hello.
+#[derive(Default)]
+pub struct HelloBackend;
+
+impl HelloBackend {
+    async fn open(account_id: &str) -> Result<KeyCredential, OperationError> {
+        let credential = get_passport_account(&account_id.to_string())
+            .await
+            .map_napi_error()?;
+
+        let status = credential.Status().map_napi_error()?;
+        if status != KeyCredentialStatus::Success {
+            return Err(OperationError::from_credential_status(status));
+        }
+
+        Ok(credential.Credential().map_napi_error()?)
+    }
+
+    fn sign_with(credential: &KeyCredential, data: &[u8]) -> Result<Vec<u8>, OperationError> {
+        let data = data.to_vec().into_win_buffer().map_napi_error()?;
+        let res = tracing::debug_span!("RequestSignAsync")
+            .in_scope(|| credential.RequestSignAsync(&data)?.get())
+            .map_napi_error()?;
+
+        let status = res.Status().map_napi_error()?;
+        if status != KeyCredentialStatus::Success {
+            return Err(OperationError::from_credential_status(status));
+        }
+
+        Ok(Vec::from_win_buffer(res.Result().map_napi_error()?).map_napi_error()?)
+    }
+}
+
+impl KeyBackend for HelloBackend {
+    fn available(&self) -> napi::Result<bool> {
+        tracing::debug_span!("IsSupportedAsync")
+            .in_scope(|| KeyCredentialManager::IsSupportedAsync()?.get())
+            .map_napi_error()
+    }
+
+    fn account_exists(&self, account_id: &str) -> napi::Result<bool> {
+        let status = get_passport_account_sync(&account_id.to_string())
+            .map_napi_error()?
+            .Status()
+            .map_napi_error()?;
+
+        match status {
+            KeyCredentialStatus::Success => Ok(true),
+            KeyCredentialStatus::NotFound => Ok(false),
+            s => Err(OperationError::from_credential_status(s).into()),
+        }
+    }
+
+    fn create_key<'a>(
+        &'a self,
+        account_id: &'a str,
+        option: KeyCreationOption,
+    ) -> BoxFuture<'a, Result<(), OperationError>> {
+        async move {
+            let status = create_passport_key(&account_id.to_string(), Some(option.into()))
+                .await
+                .map_napi_error()?
+                .Status()
+                .map_napi_error()?;
+
+            match status {
+                KeyCredentialStatus::Success => Ok(()),
+                s => Err(OperationError::from_credential_status(s)),
+            }
+        }
+        .boxed()
+    }
+
+    fn delete_key<'a>(&'a self, account_id: &'a str) -> BoxFuture<'a, Result<(), OperationError>> {
+        async move {
+            KeyCredentialManager::DeleteAsync(&HSTRING::from(account_id))
+                .map_napi_error()?
+                .instrument(tracing::debug_span!("DeleteAsync"))
+                .await
+                .map_napi_error()?;
+
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn sign<'a>(
+        &'a self,
+        account_id: &'a str,
+        data: &'a [u8],
+    ) -> BoxFuture<'a, Result<Vec<u8>, OperationError>> {
+        async move {
+            let credential = Self::open(account_id).await?;
+            Self::sign_with(&credential, data)
+        }
+        .boxed()
+    }
+
+    fn sign_many<'a>(
+        &'a self,
+        account_id: &'a str,
+        data: &'a [Vec<u8>],
+    ) -> BoxFuture<'a, Vec<Result<Vec<u8>, OperationError>>> {
+        async move {
+            // Windows Hello asks for consent on every signature,
+            // but the credential only needs to be opened once
+            let credential = match Self::open(account_id).await {
+                Ok(credential) => credential,
+                Err(e) => return data.iter().map(|_| Err(e.skipped())).collect(),
+            };
+
+            sign_each(data, |item| async { Self::sign_with(&credential, item) }).await
+        }
+        .boxed()
+    }
+
+    fn public_key<'a>(
+        &'a self,
+        account_id: &'a str,
+        encoding: PublicKeyEncoding,
+    ) -> BoxFuture<'a, Result<Vec<u8>, OperationError>> {
+        async move {
+            let credential = Self::open(account_id).await?;
+            let res = credential
+                .RetrievePublicKeyWithBlobType(encoding.into())
+                .map_napi_error()?;
+            let key = Vec::from_win_buffer(res).map_napi_error()?;
+
+            match encoding {
+                PublicKeyEncoding::OpenSsh => Ok(openssh_public_key(&key)?.into_bytes()),
+                _ => Ok(key),
+            }
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,119 @@
+use crate::backend::software::SoftwareKey;
+use crate::backend::{block_on, KeyBackend};
+use crate::keystore;
+use crate::node::digest_algorithm::DigestAlgorithm;
+use crate::node::key_creation_option::KeyCreationOption;
+use crate::node::public_key_encoding::PublicKeyEncoding;
+use crate::util::errors::OperationError;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+
+/// A backend generating keys in software and keeping them in
+/// the key store of the machine, so they are persisted across
+/// processes without being stored in plain files.
+#[derive(Default)]
+pub struct LocalBackend;
+
+impl LocalBackend {
+    async fn load(account_id: &str) -> Result<SoftwareKey, OperationError> {
+        let der = keystore::open().await?.load(account_id).await?;
+        Ok(SoftwareKey::from_pkcs8_der(&der)?)
+    }
+}
+
+impl KeyBackend for LocalBackend {
+    fn available(&self) -> napi::Result<bool> {
+        Ok(true)
+    }
+
+    fn account_exists(&self, account_id: &str) -> napi::Result<bool> {
+        // The key stores can only be queried asynchronously,
+        // so block the calling javascript thread until they answer
+        let account_id = account_id.to_string();
+        block_on(async move { keystore::open().await?.contains(&account_id).await })?
+    }
+
+    fn create_key<'a>(
+        &'a self,
+        account_id: &'a str,
+        option: KeyCreationOption,
+    ) -> BoxFuture<'a, Result<(), OperationError>> {
+        async move {
+            let store = keystore::open().await?;
+            let key = SoftwareKey::generate_async().await?;
+            let der = key.to_pkcs8_der()?;
+            match option {
+                KeyCreationOption::ReplaceExisting => store.store(account_id, &der).await,
+                KeyCreationOption::FailIfExists => store.store_new(account_id, &der).await,
+            }
+        }
+        .boxed()
+    }
+
+    fn delete_key<'a>(&'a self, account_id: &'a str) -> BoxFuture<'a, Result<(), OperationError>> {
+        async move { keystore::open().await?.delete(account_id).await }.boxed()
+    }
+
+    fn can_move_keys(&self) -> bool {
+        true
+    }
+
+    fn move_key<'a>(
+        &'a self,
+        from: &'a str,
+        to: &'a str,
+    ) -> BoxFuture<'a, Result<(), OperationError>> {
+        async move {
+            // The stores bind keys to their account, so the key is stored again
+            let store = keystore::open().await?;
+            let key = store.load(from).await?;
+            store.store(to, &key).await?;
+            store.delete(from).await
+        }
+        .boxed()
+    }
+
+    fn sign<'a>(
+        &'a self,
+        account_id: &'a str,
+        data: &'a [u8],
+    ) -> BoxFuture<'a, Result<Vec<u8>, OperationError>> {
+        async move { Ok(Self::load(account_id).await?.sign(data)?) }.boxed()
+    }
+
+    fn sign_with_digest<'a>(
+        &'a self,
+        account_id: &'a str,
+        data: &'a [u8],
+        algorithm: DigestAlgorithm,
+    ) -> BoxFuture<'a, Result<Vec<u8>, OperationError>> {
+        async move {
+            let key = Self::load(account_id).await?;
+            Ok(key.sign_with_digest(data, algorithm)?)
+        }
+        .boxed()
+    }
+
+    fn sign_many<'a>(
+        &'a self,
+        account_id: &'a str,
+        data: &'a [Vec<u8>],
+    ) -> BoxFuture<'a, Vec<Result<Vec<u8>, OperationError>>> {
+        async move {
+            // Only load the key once, as it may require a passphrase
+            match Self::load(account_id).await {
+                Ok(key) => data.iter().map(|item| Ok(key.sign(item)?)).collect(),
+                Err(e) => data.iter().map(|_| Err(e.skipped())).collect(),
+            }
+        }
+        .boxed()
+    }
+
+    fn public_key<'a>(
+        &'a self,
+        account_id: &'a str,
+        encoding: PublicKeyEncoding,
+    ) -> BoxFuture<'a, Result<Vec<u8>, OperationError>> {
+        async move { Ok(Self::load(account_id).await?.public_key(encoding)?) }.boxed()
+    }
+}
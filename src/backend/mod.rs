@@ -0,0 +1,189 @@
+use crate::node::digest_algorithm::DigestAlgorithm;
+use crate::node::key_creation_option::KeyCreationOption;
+use crate::node::passport_backend::PassportBackend;
+use crate::node::public_key_encoding::PublicKeyEncoding;
+use crate::util::audit::{self, hash_hex};
+use crate::util::errors::OperationError;
+use crate::util::mappers::MapNapiError;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+
+#[cfg(windows)]
+pub(crate) mod hello;
+pub(crate) mod local;
+pub(crate) mod pkcs11;
+pub(crate) mod software;
+#[cfg(target_os = "linux")]
+pub(crate) mod tpm;
+pub(crate) mod unsupported;
+
+/// A store for passport keys. Every account ID maps to at most
+/// one RSA key pair, whose private key never leaves the backend.
+/// Signatures are RSASSA-PKCS1-v1_5 signatures using SHA-256.
+pub trait KeyBackend: Send + Sync {
+    /// Whether the backend can be used on this machine.
+    fn available(&self) -> napi::Result<bool>;
+
+    /// Whether a key exists for the given account.
+    fn account_exists(&self, account_id: &str) -> napi::Result<bool>;
+
+    /// Create a new key for the given account.
+    fn create_key<'a>(
+        &'a self,
+        account_id: &'a str,
+        option: KeyCreationOption,
+    ) -> BoxFuture<'a, Result<(), OperationError>>;
+
+    /// Delete the key of the given account.
+    fn delete_key<'a>(&'a self, account_id: &'a str) -> BoxFuture<'a, Result<(), OperationError>>;
+
+    /// Whether keys can be moved to another account using [`KeyBackend::move_key`].
+    fn can_move_keys(&self) -> bool {
+        false
+    }
+
+    /// Move the key of an account to another account, replacing
+    /// the key of the other account once the key was moved.
+    /// Windows Hello keys are bound to their name, so backends
+    /// fail unless they override this.
+    fn move_key<'a>(
+        &'a self,
+        _from: &'a str,
+        _to: &'a str,
+    ) -> BoxFuture<'a, Result<(), OperationError>> {
+        async move { Err(napi::Error::from_reason("The backend cannot move keys").into()) }.boxed()
+    }
+
+    /// Sign data using the key of the given account.
+    fn sign<'a>(
+        &'a self,
+        account_id: &'a str,
+        data: &'a [u8],
+    ) -> BoxFuture<'a, Result<Vec<u8>, OperationError>>;
+
+    /// Sign data using RSASSA-PKCS1-v1_5 with the given digest algorithm.
+    /// Windows Hello keys can only sign using SHA-256, so backends
+    /// fail for other algorithms unless they override this.
+    fn sign_with_digest<'a>(
+        &'a self,
+        account_id: &'a str,
+        data: &'a [u8],
+        algorithm: DigestAlgorithm,
+    ) -> BoxFuture<'a, Result<Vec<u8>, OperationError>> {
+        match algorithm {
+            DigestAlgorithm::Sha256 => self.sign(account_id, data),
+            _ => async move {
+                Err(napi::Error::from_reason(format!(
+                    "The backend cannot sign using {}",
+                    algorithm.name()
+                ))
+                .into())
+            }
+            .boxed(),
+        }
+    }
+
+    /// Sign multiple items using the key of the given account,
+    /// returning a result for every item. Backends asking the
+    /// user for consent should override this if they can
+    /// sign all items with a single verification.
+    fn sign_many<'a>(
+        &'a self,
+        account_id: &'a str,
+        data: &'a [Vec<u8>],
+    ) -> BoxFuture<'a, Vec<Result<Vec<u8>, OperationError>>> {
+        sign_each(data, move |item| self.sign(account_id, item)).boxed()
+    }
+
+    /// Export the public key of the given account.
+    fn public_key<'a>(
+        &'a self,
+        account_id: &'a str,
+        encoding: PublicKeyEncoding,
+    ) -> BoxFuture<'a, Result<Vec<u8>, OperationError>>;
+}
+
+/// Get the shared instance of the PKCS#11 backend.
+pub fn pkcs11_backend() -> Arc<pkcs11::Pkcs11Backend> {
+    static PKCS11: OnceLock<Arc<pkcs11::Pkcs11Backend>> = OnceLock::new();
+    PKCS11.get_or_init(Default::default).clone()
+}
+
+/// Get the shared instance of a backend.
+pub fn get_backend(backend: PassportBackend) -> Arc<dyn KeyBackend> {
+    #[cfg(windows)]
+    static HELLO: OnceLock<Arc<hello::HelloBackend>> = OnceLock::new();
+    static SOFTWARE: OnceLock<Arc<software::SoftwareBackend>> = OnceLock::new();
+    static LOCAL: OnceLock<Arc<local::LocalBackend>> = OnceLock::new();
+    #[cfg(target_os = "linux")]
+    static TPM: OnceLock<Arc<tpm::TpmBackend>> = OnceLock::new();
+
+    match backend {
+        #[cfg(windows)]
+        PassportBackend::WindowsHello => HELLO.get_or_init(Default::default).clone(),
+        PassportBackend::Software => SOFTWARE.get_or_init(Default::default).clone(),
+        PassportBackend::Pkcs11 => pkcs11_backend(),
+        PassportBackend::Local => LOCAL.get_or_init(Default::default).clone(),
+        #[cfg(target_os = "linux")]
+        PassportBackend::Tpm => TPM.get_or_init(Default::default).clone(),
+        #[allow(unreachable_patterns)]
+        backend => Arc::new(unsupported::UnsupportedBackend::new(backend)),
+    }
+}
+
+/// Block the calling javascript thread until a future completed, for
+/// backends which can only be queried asynchronously. The future runs on
+/// the napi runtime, which only runs futures without an output, so the
+/// output is passed back using a channel.
+pub fn block_on<T, F>(future: F) -> napi::Result<T>
+where
+    T: Send + 'static,
+    F: Future<Output = T> + Send + 'static,
+{
+    let (sender, receiver) = std::sync::mpsc::channel();
+    napi::bindgen_prelude::block_on(async move {
+        let _ = sender.send(future.await);
+    });
+
+    receiver.recv().map_napi_error()
+}
+
+/// Get the fingerprint of an account's public key for
+/// recording it in the audit log, if the log is enabled.
+pub async fn key_fingerprint(backend: &dyn KeyBackend, account_id: &str) -> Option<String> {
+    if !audit::enabled() {
+        return None;
+    }
+
+    backend
+        .public_key(account_id, PublicKeyEncoding::Pkcs1RsaPublicKey)
+        .await
+        .ok()
+        .map(|key| hash_hex(&key))
+}
+
+/// Sign items one after another. Once the user declined to sign
+/// an item, the remaining items are skipped instead of asking
+/// the user again for every single item.
+pub async fn sign_each<'a, F, Fut>(
+    data: &'a [Vec<u8>],
+    mut sign: F,
+) -> Vec<Result<Vec<u8>, OperationError>>
+where
+    F: FnMut(&'a [u8]) -> Fut,
+    Fut: Future<Output = Result<Vec<u8>, OperationError>>,
+{
+    let mut results: Vec<Result<Vec<u8>, OperationError>> = Vec::with_capacity(data.len());
+    for item in data {
+        let result = match results.last() {
+            Some(Err(e)) if e.declined_by_user() => Err(e.skipped()),
+            _ => sign(item).await,
+        };
+
+        results.push(result);
+    }
+
+    results
+}
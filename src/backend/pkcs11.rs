@@ -0,0 +1,358 @@
+use crate::backend::software::encode_public_key;
+use crate::backend::{block_on, KeyBackend};
+use crate::node::key_creation_option::KeyCreationOption;
+use crate::node::pkcs11_options::Pkcs11Options;
+use crate::node::public_key_encoding::PublicKeyEncoding;
+use crate::util::audit::hash_hex;
+use crate::util::errors::OperationError;
+use crate::util::mappers::MapNapiError;
+use cryptoki::context::{CInitializeArgs, Pkcs11};
+use cryptoki::error::{Error, RvError};
+use cryptoki::mechanism::Mechanism;
+use cryptoki::object::{Attribute, AttributeType, ObjectClass, ObjectHandle};
+use cryptoki::session::{Session, UserType};
+use cryptoki::types::AuthPin;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use rsa::{BigUint, RsaPublicKey};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+
+/// The size of generated keys in bits, matching the keys created by Windows Hello.
+const KEY_SIZE: usize = 2048;
+/// The public exponent of generated keys, 65537.
+const PUBLIC_EXPONENT: [u8; 3] = [0x01, 0x00, 0x01];
+
+/// Get the context of a PKCS#11 module, loading and initializing
+/// it on first use. A module can only be initialized once per
+/// process and finalizing it closes all of its sessions, so the
+/// contexts are kept for the lifetime of the process and shared
+/// by all tokens opened using the module.
+fn module(module_path: &str) -> napi::Result<Pkcs11> {
+    static MODULES: OnceLock<Mutex<HashMap<String, Pkcs11>>> = OnceLock::new();
+    let mut modules = MODULES.get_or_init(Default::default).lock().unwrap();
+    if let Some(pkcs11) = modules.get(module_path) {
+        return Ok(pkcs11.clone());
+    }
+
+    let pkcs11 = Pkcs11::new(module_path).map_napi_error()?;
+    pkcs11
+        .initialize(CInitializeArgs::OsThreads)
+        .map_napi_error()?;
+    modules.insert(module_path.to_string(), pkcs11.clone());
+
+    Ok(pkcs11)
+}
+
+/// An open session with a token. The session is kept open for
+/// the lifetime of the token, so the user stays logged in.
+struct Token {
+    session: Mutex<Session>,
+}
+
+impl Token {
+    fn open(options: &Pkcs11Options) -> napi::Result<Self> {
+        let pkcs11 = module(&options.module_path)?;
+
+        let mut slot = None;
+        for candidate in pkcs11.get_slots_with_token().map_napi_error()? {
+            let info = pkcs11.get_token_info(candidate).map_napi_error()?;
+            let matches = match &options.token_label {
+                Some(label) => info.label() == label,
+                None => true,
+            };
+
+            if matches {
+                slot = Some(candidate);
+                break;
+            }
+        }
+
+        let slot = slot.ok_or_else(|| {
+            napi::Error::from_reason(match &options.token_label {
+                Some(label) => format!("No PKCS#11 token with the label '{}' was found", label),
+                None => "No PKCS#11 token was found".to_string(),
+            })
+        })?;
+
+        let session = pkcs11.open_rw_session(slot).map_napi_error()?;
+        if let Some(pin) = &options.pin {
+            // The login state is shared by all sessions of the
+            // application, so the user may still be logged in
+            // using the session of the previously opened token
+            match session.login(UserType::User, Some(&AuthPin::new(pin.clone()))) {
+                Ok(()) | Err(Error::Pkcs11(RvError::UserAlreadyLoggedIn)) => {}
+                Err(e) => return Err(e).map_napi_error(),
+            }
+        }
+
+        Ok(Self {
+            session: Mutex::new(session),
+        })
+    }
+
+    /// Find the objects of a class belonging to an account.
+    fn find(
+        session: &Session,
+        account_id: &str,
+        class: ObjectClass,
+    ) -> napi::Result<Vec<ObjectHandle>> {
+        session
+            .find_objects(&[
+                Attribute::Class(class),
+                Attribute::Label(account_id.as_bytes().to_vec()),
+            ])
+            .map_napi_error()
+    }
+
+    fn find_one(
+        session: &Session,
+        account_id: &str,
+        class: ObjectClass,
+    ) -> Result<ObjectHandle, OperationError> {
+        Self::find(session, account_id, class)?
+            .into_iter()
+            .next()
+            .ok_or_else(OperationError::not_found)
+    }
+
+    /// Find the private and public keys belonging to an account.
+    fn find_keys(session: &Session, account_id: &str) -> napi::Result<Vec<ObjectHandle>> {
+        let mut objects = Self::find(session, account_id, ObjectClass::PRIVATE_KEY)?;
+        objects.extend(Self::find(session, account_id, ObjectClass::PUBLIC_KEY)?);
+
+        Ok(objects)
+    }
+
+    fn destroy(session: &Session, objects: Vec<ObjectHandle>) -> napi::Result<()> {
+        for object in objects {
+            session.destroy_object(object).map_napi_error()?;
+        }
+
+        Ok(())
+    }
+
+    /// The ID linking the public and private key of an account.
+    fn key_id(account_id: &str) -> napi::Result<Vec<u8>> {
+        hex::decode(hash_hex(account_id.as_bytes())).map_napi_error()
+    }
+
+    fn generate(session: &Session, account_id: &str) -> napi::Result<()> {
+        let label = account_id.as_bytes().to_vec();
+        let id = Self::key_id(account_id)?;
+
+        let public_template = [
+            Attribute::Token(true),
+            Attribute::Private(false),
+            Attribute::Label(label.clone()),
+            Attribute::Id(id.clone()),
+            Attribute::ModulusBits(KEY_SIZE.try_into().map_napi_error()?),
+            Attribute::PublicExponent(PUBLIC_EXPONENT.to_vec()),
+            Attribute::Verify(true),
+        ];
+        let private_template = [
+            Attribute::Token(true),
+            Attribute::Private(true),
+            Attribute::Sensitive(true),
+            Attribute::Extractable(false),
+            Attribute::Label(label),
+            Attribute::Id(id),
+            Attribute::Sign(true),
+        ];
+
+        session
+            .generate_key_pair(
+                &Mechanism::RsaPkcsKeyPairGen,
+                &public_template,
+                &private_template,
+            )
+            .map(|_| ())
+            .map_napi_error()
+    }
+
+    fn public_key(session: &Session, account_id: &str) -> Result<RsaPublicKey, OperationError> {
+        let object = Self::find_one(session, account_id, ObjectClass::PUBLIC_KEY)?;
+        let attributes = session
+            .get_attributes(
+                object,
+                &[AttributeType::Modulus, AttributeType::PublicExponent],
+            )
+            .map_napi_error()?;
+
+        let (mut modulus, mut exponent) = (None, None);
+        for attribute in attributes {
+            match attribute {
+                Attribute::Modulus(n) => modulus = Some(BigUint::from_bytes_be(&n)),
+                Attribute::PublicExponent(e) => exponent = Some(BigUint::from_bytes_be(&e)),
+                _ => {}
+            }
+        }
+
+        match (modulus, exponent) {
+            (Some(n), Some(e)) => Ok(RsaPublicKey::new(n, e).map_napi_error()?),
+            _ => Err(napi::Error::from_reason("The key is not an RSA public key").into()),
+        }
+    }
+}
+
+/// The backend storing keys on a PKCS#11 token. Every account
+/// maps to a key pair with the account ID as its label.
+/// The backend must be configured using [`Pkcs11Backend::configure`]
+/// before it can be used.
+#[derive(Default)]
+pub struct Pkcs11Backend {
+    token: RwLock<Option<Arc<Token>>>,
+}
+
+impl Pkcs11Backend {
+    /// Open the token all operations are performed on,
+    /// replacing the previously configured token.
+    pub fn configure(&self, options: &Pkcs11Options) -> napi::Result<()> {
+        let _span = tracing::info_span!("C_Login", module = options.module_path).entered();
+        let token = Token::open(options)?;
+        *self.token.write().unwrap() = Some(Arc::new(token));
+
+        Ok(())
+    }
+
+    fn token(&self) -> napi::Result<Arc<Token>> {
+        self.token.read().unwrap().clone().ok_or_else(|| {
+            napi::Error::from_reason(
+                "The PKCS#11 backend is not configured, call Passport.configurePkcs11 first",
+            )
+        })
+    }
+
+    /// Run an operation using the token's session on the blocking thread pool.
+    async fn with_session<T, F>(&self, f: F) -> Result<T, OperationError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Session) -> Result<T, OperationError> + Send + 'static,
+    {
+        Self::run(self.token()?, f).await
+    }
+
+    async fn run<T, F>(token: Arc<Token>, f: F) -> Result<T, OperationError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Session) -> Result<T, OperationError> + Send + 'static,
+    {
+        tokio::task::spawn_blocking(move || f(&token.session.lock().unwrap()))
+            .await
+            .map_napi_error()?
+    }
+}
+
+impl KeyBackend for Pkcs11Backend {
+    fn available(&self) -> napi::Result<bool> {
+        Ok(self.token.read().unwrap().is_some())
+    }
+
+    fn account_exists(&self, account_id: &str) -> napi::Result<bool> {
+        // Block the calling javascript thread until the token answered,
+        // but keep the token I/O on the blocking thread pool
+        let account_id = account_id.to_string();
+        block_on(Self::run(self.token()?, move |session| {
+            let objects = Token::find(session, &account_id, ObjectClass::PRIVATE_KEY)?;
+            Ok(!objects.is_empty())
+        }))?
+        .map_err(napi::Error::from)
+    }
+
+    fn create_key<'a>(
+        &'a self,
+        account_id: &'a str,
+        option: KeyCreationOption,
+    ) -> BoxFuture<'a, Result<(), OperationError>> {
+        let account_id = account_id.to_string();
+        self.with_session(move |session| {
+            let exists = !Token::find(session, &account_id, ObjectClass::PRIVATE_KEY)?.is_empty();
+            if exists && matches!(option, KeyCreationOption::FailIfExists) {
+                return Err(OperationError::already_exists());
+            }
+
+            // Only delete the old key once the new key was generated,
+            // so the old key is kept if the generation fails
+            let old = Token::find_keys(session, &account_id)?;
+            let _span = tracing::debug_span!("C_GenerateKeyPair").entered();
+            Token::generate(session, &account_id)?;
+            Ok(Token::destroy(session, old)?)
+        })
+        .boxed()
+    }
+
+    fn delete_key<'a>(&'a self, account_id: &'a str) -> BoxFuture<'a, Result<(), OperationError>> {
+        let account_id = account_id.to_string();
+        self.with_session(move |session| {
+            let _span = tracing::debug_span!("C_DestroyObject").entered();
+            match Token::find_keys(session, &account_id)? {
+                objects if objects.is_empty() => Err(OperationError::not_found()),
+                objects => Ok(Token::destroy(session, objects)?),
+            }
+        })
+        .boxed()
+    }
+
+    fn can_move_keys(&self) -> bool {
+        true
+    }
+
+    fn move_key<'a>(
+        &'a self,
+        from: &'a str,
+        to: &'a str,
+    ) -> BoxFuture<'a, Result<(), OperationError>> {
+        let (from, to) = (from.to_string(), to.to_string());
+        self.with_session(move |session| {
+            let objects = match Token::find_keys(session, &from)? {
+                objects if objects.is_empty() => return Err(OperationError::not_found()),
+                objects => objects,
+            };
+
+            let old = Token::find_keys(session, &to)?;
+            let template = [
+                Attribute::Label(to.as_bytes().to_vec()),
+                Attribute::Id(Token::key_id(&to)?),
+            ];
+            for object in objects {
+                session
+                    .update_attributes(object, &template)
+                    .map_napi_error()?;
+            }
+
+            Ok(Token::destroy(session, old)?)
+        })
+        .boxed()
+    }
+
+    fn sign<'a>(
+        &'a self,
+        account_id: &'a str,
+        data: &'a [u8],
+    ) -> BoxFuture<'a, Result<Vec<u8>, OperationError>> {
+        let account_id = account_id.to_string();
+        let data = data.to_vec();
+        self.with_session(move |session| {
+            let _span = tracing::debug_span!("C_Sign").entered();
+            let key = Token::find_one(session, &account_id, ObjectClass::PRIVATE_KEY)?;
+
+            Ok(session
+                .sign(&Mechanism::Sha256RsaPkcs, key, &data)
+                .map_napi_error()?)
+        })
+        .boxed()
+    }
+
+    fn public_key<'a>(
+        &'a self,
+        account_id: &'a str,
+        encoding: PublicKeyEncoding,
+    ) -> BoxFuture<'a, Result<Vec<u8>, OperationError>> {
+        let account_id = account_id.to_string();
+        self.with_session(move |session| {
+            let key = Token::public_key(session, &account_id)?;
+            Ok(encode_public_key(&key, encoding)?)
+        })
+        .boxed()
+    }
+}
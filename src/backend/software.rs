@@ -0,0 +1,273 @@
+use crate::backend::KeyBackend;
+use crate::node::digest_algorithm::DigestAlgorithm;
+use crate::node::key_creation_option::KeyCreationOption;
+use crate::node::public_key_encoding::PublicKeyEncoding;
+use crate::ssh::{authorized_key, rsa_public_key_blob};
+use crate::util::errors::OperationError;
+use crate::util::mappers::MapNapiError;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use rsa::pkcs1::EncodeRsaPublicKey;
+use rsa::pkcs1v15::SigningKey;
+use rsa::pkcs8::{DecodePrivateKey, EncodePrivateKey, EncodePublicKey};
+use rsa::sha2::{Sha256, Sha384, Sha512};
+use rsa::signature::{SignatureEncoding, Signer};
+use rsa::traits::PublicKeyParts;
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use zeroize::Zeroizing;
+
+/// The size of generated keys in bits, matching the keys created by Windows Hello.
+const KEY_SIZE: usize = 2048;
+/// `BCRYPT_RSAPUBLIC_MAGIC`, used by both the BCrypt and CAPI blob formats.
+const RSA_PUBLIC_MAGIC: u32 = 0x3141_5352;
+/// `CALG_RSA_KEYX`, the algorithm BCrypt writes to legacy public key blobs.
+const CALG_RSA_KEYX: u32 = 0x0000_a400;
+
+/// An RSA key pair generated in software.
+pub struct SoftwareKey {
+    signing_key: SigningKey<Sha256>,
+    public_key: RsaPublicKey,
+}
+
+impl SoftwareKey {
+    /// Generate a new key pair. This takes a while,
+    /// so it should not be called on the main thread.
+    pub fn generate() -> napi::Result<Self> {
+        RsaPrivateKey::new(&mut rand::thread_rng(), KEY_SIZE)
+            .map(Self::from)
+            .map_napi_error()
+    }
+
+    /// Generate a new key pair on the blocking thread pool.
+    pub async fn generate_async() -> napi::Result<Self> {
+        tokio::task::spawn_blocking(Self::generate)
+            .await
+            .map_napi_error()?
+    }
+
+    /// Load a key pair from a PKCS#8 encoded private key.
+    pub fn from_pkcs8_der(der: &[u8]) -> napi::Result<Self> {
+        RsaPrivateKey::from_pkcs8_der(der)
+            .map(Self::from)
+            .map_napi_error()
+    }
+
+    /// Encode the private key as PKCS#8 document.
+    pub fn to_pkcs8_der(&self) -> napi::Result<Zeroizing<Vec<u8>>> {
+        let der = self.signing_key.as_ref().to_pkcs8_der().map_napi_error()?;
+        Ok(Zeroizing::new(der.as_bytes().to_vec()))
+    }
+
+    /// Sign data using RSASSA-PKCS1-v1_5 with SHA-256.
+    pub fn sign(&self, data: &[u8]) -> napi::Result<Vec<u8>> {
+        self.signing_key
+            .try_sign(data)
+            .map(|s| s.to_vec())
+            .map_napi_error()
+    }
+
+    /// Sign data using RSASSA-PKCS1-v1_5 with the given digest algorithm.
+    pub fn sign_with_digest(
+        &self,
+        data: &[u8],
+        algorithm: DigestAlgorithm,
+    ) -> napi::Result<Vec<u8>> {
+        let key = self.signing_key.as_ref().clone();
+        let signature = match algorithm {
+            DigestAlgorithm::Sha256 => return self.sign(data),
+            DigestAlgorithm::Sha384 => SigningKey::<Sha384>::new(key).try_sign(data),
+            DigestAlgorithm::Sha512 => SigningKey::<Sha512>::new(key).try_sign(data),
+        };
+
+        signature.map(|s| s.to_vec()).map_napi_error()
+    }
+
+    /// Export the public key in the given encoding.
+    pub fn public_key(&self, encoding: PublicKeyEncoding) -> napi::Result<Vec<u8>> {
+        encode_public_key(&self.public_key, encoding)
+    }
+}
+
+impl From<RsaPrivateKey> for SoftwareKey {
+    fn from(key: RsaPrivateKey) -> Self {
+        Self {
+            public_key: key.to_public_key(),
+            signing_key: SigningKey::new(key),
+        }
+    }
+}
+
+/// Encode an RSA public key the same way Windows
+/// encodes the public keys of Windows Hello keys.
+pub fn encode_public_key(key: &RsaPublicKey, encoding: PublicKeyEncoding) -> napi::Result<Vec<u8>> {
+    let exponent = key.e().to_bytes_be();
+    let modulus = key.n().to_bytes_be();
+    let bits = (key.size() * 8) as u32;
+
+    match encoding {
+        PublicKeyEncoding::Pkcs1RsaPublicKey => key
+            .to_pkcs1_der()
+            .map(|der| der.into_vec())
+            .map_napi_error(),
+        PublicKeyEncoding::X509SubjectPublicKeyInfo => key
+            .to_public_key_der()
+            .map(|der| der.into_vec())
+            .map_napi_error(),
+        PublicKeyEncoding::BCryptPublicKey => {
+            // BCRYPT_RSAKEY_BLOB followed by the big-endian exponent and modulus
+            let mut blob = vec![];
+            for value in [
+                RSA_PUBLIC_MAGIC,
+                bits,
+                exponent.len() as u32,
+                modulus.len() as u32,
+                0,
+                0,
+            ] {
+                blob.extend_from_slice(&value.to_le_bytes());
+            }
+
+            blob.extend_from_slice(&exponent);
+            blob.extend_from_slice(&modulus);
+            Ok(blob)
+        }
+        PublicKeyEncoding::Capi1PublicKey => {
+            // PUBLICKEYSTRUC and RSAPUBKEY followed by the little-endian modulus
+            let mut padded_exponent = [0u8; 4];
+            if exponent.len() > padded_exponent.len() {
+                return Err(napi::Error::from_reason(
+                    "The public exponent is too large for a CAPI public key blob",
+                ));
+            }
+            padded_exponent[4 - exponent.len()..].copy_from_slice(&exponent);
+
+            let mut blob = vec![0x06, 0x02, 0x00, 0x00];
+            blob.extend_from_slice(&CALG_RSA_KEYX.to_le_bytes());
+            blob.extend_from_slice(&RSA_PUBLIC_MAGIC.to_le_bytes());
+            blob.extend_from_slice(&bits.to_le_bytes());
+            blob.extend_from_slice(&u32::from_be_bytes(padded_exponent).to_le_bytes());
+            blob.extend(modulus.iter().rev());
+            Ok(blob)
+        }
+        PublicKeyEncoding::OpenSsh => {
+            Ok(authorized_key(&rsa_public_key_blob(key), None).into_bytes())
+        }
+        PublicKeyEncoding::BCryptEccFullPublicKey => Err(napi::Error::from_reason(
+            "The key is an RSA key and cannot be exported as an ECC key",
+        )),
+    }
+}
+
+/// A backend keeping software-generated keys in memory.
+/// The keys are shared between all Passport instances,
+/// but are lost once the process exits.
+#[derive(Default)]
+pub struct SoftwareBackend {
+    keys: RwLock<HashMap<String, Arc<SoftwareKey>>>,
+}
+
+impl SoftwareBackend {
+    fn get_key(&self, account_id: &str) -> Result<Arc<SoftwareKey>, OperationError> {
+        self.keys
+            .read()
+            .unwrap()
+            .get(account_id)
+            .cloned()
+            .ok_or_else(OperationError::not_found)
+    }
+}
+
+impl KeyBackend for SoftwareBackend {
+    fn available(&self) -> napi::Result<bool> {
+        Ok(true)
+    }
+
+    fn account_exists(&self, account_id: &str) -> napi::Result<bool> {
+        Ok(self.keys.read().unwrap().contains_key(account_id))
+    }
+
+    fn create_key<'a>(
+        &'a self,
+        account_id: &'a str,
+        option: KeyCreationOption,
+    ) -> BoxFuture<'a, Result<(), OperationError>> {
+        async move {
+            if matches!(option, KeyCreationOption::FailIfExists)
+                && self.account_exists(account_id)?
+            {
+                return Err(OperationError::already_exists());
+            }
+
+            let key = SoftwareKey::generate_async().await?;
+            self.keys
+                .write()
+                .unwrap()
+                .insert(account_id.to_string(), Arc::new(key));
+
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn delete_key<'a>(&'a self, account_id: &'a str) -> BoxFuture<'a, Result<(), OperationError>> {
+        async move {
+            self.keys
+                .write()
+                .unwrap()
+                .remove(account_id)
+                .map(|_| ())
+                .ok_or_else(OperationError::not_found)
+        }
+        .boxed()
+    }
+
+    fn can_move_keys(&self) -> bool {
+        true
+    }
+
+    fn move_key<'a>(
+        &'a self,
+        from: &'a str,
+        to: &'a str,
+    ) -> BoxFuture<'a, Result<(), OperationError>> {
+        async move {
+            let mut keys = self.keys.write().unwrap();
+            let key = keys.remove(from).ok_or_else(OperationError::not_found)?;
+            keys.insert(to.to_string(), key);
+
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn sign<'a>(
+        &'a self,
+        account_id: &'a str,
+        data: &'a [u8],
+    ) -> BoxFuture<'a, Result<Vec<u8>, OperationError>> {
+        async move { Ok(self.get_key(account_id)?.sign(data)?) }.boxed()
+    }
+
+    fn sign_with_digest<'a>(
+        &'a self,
+        account_id: &'a str,
+        data: &'a [u8],
+        algorithm: DigestAlgorithm,
+    ) -> BoxFuture<'a, Result<Vec<u8>, OperationError>> {
+        async move {
+            let key = self.get_key(account_id)?;
+            Ok(key.sign_with_digest(data, algorithm)?)
+        }
+        .boxed()
+    }
+
+    fn public_key<'a>(
+        &'a self,
+        account_id: &'a str,
+        encoding: PublicKeyEncoding,
+    ) -> BoxFuture<'a, Result<Vec<u8>, OperationError>> {
+        async move { Ok(self.get_key(account_id)?.public_key(encoding)?) }.boxed()
+    }
+}
@@ -0,0 +1,464 @@
+use crate::backend::software::encode_public_key;
+use crate::backend::{sign_each, KeyBackend};
+use crate::consent::get_verifier;
+use crate::node::consent_provider::ConsentProvider;
+use crate::node::key_creation_option::KeyCreationOption;
+use crate::node::public_key_encoding::PublicKeyEncoding;
+use crate::node::verification_result::VerificationResult;
+use crate::util::audit::hash_hex;
+use crate::util::errors::OperationError;
+use crate::util::file_lock;
+use crate::util::mappers::MapNapiError;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use rand::RngCore;
+use rsa::{BigUint, RsaPublicKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use std::fs::{self, DirBuilder, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tss_esapi::attributes::ObjectAttributesBuilder;
+use tss_esapi::constants::tss::{TPM2_RH_NULL, TPM2_ST_HASHCHECK};
+use tss_esapi::handles::KeyHandle;
+use tss_esapi::interface_types::algorithm::{
+    HashingAlgorithm, PublicAlgorithm, RsaSchemeAlgorithm,
+};
+use tss_esapi::interface_types::ecc::EccCurve;
+use tss_esapi::interface_types::key_bits::RsaKeyBits;
+use tss_esapi::interface_types::resource_handles::Hierarchy;
+use tss_esapi::structures::{
+    Digest, EccPoint, HashScheme, HashcheckTicket, Private, Public, PublicBuilder,
+    PublicEccParametersBuilder, PublicKeyRsa, PublicRsaParametersBuilder, RsaExponent, RsaScheme,
+    Signature, SignatureScheme, SymmetricDefinitionObject,
+};
+use tss_esapi::traits::{Marshall, UnMarshall};
+use tss_esapi::tss2_esys::TPMT_TK_HASHCHECK;
+use tss_esapi::{Context, TctiNameConf};
+
+/// The environment variable overriding the directory key blobs are stored in.
+const KEY_DIR_VAR: &str = "PASSPORT_TPM_KEY_DIR";
+/// The public exponent used by the TPM if the key's exponent is zero.
+const DEFAULT_EXPONENT: u32 = 65537;
+
+/// The provider asking the user for consent before a key is used,
+/// if another than the default provider was configured. The keys
+/// have no auth value, so without asking, anyone able to read a
+/// key blob could sign using it.
+static CONSENT_PROVIDER: RwLock<Option<ConsentProvider>> = RwLock::new(None);
+
+/// Set the provider asking the user for consent before signing,
+/// or reset it to the default provider.
+pub fn set_consent_provider(provider: Option<ConsentProvider>) {
+    *CONSENT_PROVIDER.write().unwrap() = provider;
+}
+
+/// A signing key created by the TPM. The private part is
+/// encrypted by the primary key and can only be loaded
+/// into the TPM that created it.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyBlob {
+    account_id: String,
+    /// The hex-encoded, marshalled `TPM2B_PUBLIC`.
+    public: String,
+    /// The hex-encoded `TPM2B_PRIVATE`.
+    private: String,
+}
+
+impl KeyBlob {
+    fn public(&self) -> napi::Result<Public> {
+        Public::unmarshall(&hex::decode(&self.public).map_napi_error()?).map_napi_error()
+    }
+
+    fn private(&self) -> napi::Result<Private> {
+        Private::try_from(hex::decode(&self.private).map_napi_error()?).map_napi_error()
+    }
+
+    fn public_key(&self) -> napi::Result<RsaPublicKey> {
+        match self.public()? {
+            Public::Rsa {
+                unique, parameters, ..
+            } => {
+                let exponent = match parameters.exponent().value() {
+                    0 => DEFAULT_EXPONENT,
+                    e => e,
+                };
+
+                RsaPublicKey::new(
+                    BigUint::from_bytes_be(unique.value()),
+                    BigUint::from(exponent),
+                )
+                .map_napi_error()
+            }
+            _ => Err(napi::Error::from_reason("The stored key is not an RSA key")),
+        }
+    }
+}
+
+/// The backend storing keys using the TPM 2.0 of the machine.
+/// Key blobs are stored as JSON files named after the hash of
+/// the account ID, which are written holding a lock on the file
+/// `.lock` in their directory. The primary key is recreated from
+/// the owner hierarchy's seed for every operation, so it never has
+/// to be persisted in the TPM.
+#[derive(Default)]
+pub struct TpmBackend;
+
+impl TpmBackend {
+    fn key_dir() -> PathBuf {
+        if let Some(dir) = std::env::var_os(KEY_DIR_VAR) {
+            return dir.into();
+        }
+
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+            })
+            .unwrap_or_else(std::env::temp_dir)
+            .join("passport-desktop")
+            .join("tpm")
+    }
+
+    fn key_path(account_id: &str) -> PathBuf {
+        Self::key_dir().join(format!("{}.json", hash_hex(account_id.as_bytes())))
+    }
+
+    fn read_key(account_id: &str) -> Result<KeyBlob, OperationError> {
+        let data = match fs::read(Self::key_path(account_id)) {
+            Err(e) if e.kind() == ErrorKind::NotFound => return Err(OperationError::not_found()),
+            result => result.map_napi_error()?,
+        };
+
+        Ok(serde_json::from_slice(&data).map_napi_error()?)
+    }
+
+    /// Run a file operation holding the lock of the key directory,
+    /// which is created only accessible by the user if it is missing.
+    fn locked<T, F>(f: F) -> Result<T, OperationError>
+    where
+        F: FnOnce() -> Result<T, OperationError>,
+    {
+        let dir = Self::key_dir();
+        DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(&dir)
+            .map_napi_error()?;
+
+        let _lock = file_lock::lock(&dir.join(".lock"), true)?;
+        f()
+    }
+
+    /// Write a key blob, which must be done holding the lock. Unless
+    /// `replace` is set, an existing blob is kept and an `AlreadyExists`
+    /// error is returned.
+    fn write_key(blob: &KeyBlob, replace: bool) -> Result<(), OperationError> {
+        let path = Self::key_path(&blob.account_id);
+        if !replace && path.try_exists().map_napi_error()? {
+            return Err(OperationError::already_exists());
+        }
+
+        // Every write uses a new file, so a file left behind
+        // by a crash is never reused with its permissions
+        let mut suffix = [0u8; 8];
+        rand::thread_rng().fill_bytes(&mut suffix);
+        let tmp = path.with_extension(format!("{}.tmp", hex::encode(suffix)));
+
+        let data = serde_json::to_vec(blob).map_napi_error()?;
+        let written = OpenOptions::new()
+            .create_new(true)
+            .write(true)
+            .mode(0o600)
+            .open(&tmp)
+            .and_then(|mut file| file.write_all(&data).and_then(|_| file.sync_all()))
+            .and_then(|_| fs::rename(&tmp, &path));
+        if written.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+
+        Ok(written.map_napi_error()?)
+    }
+
+    fn context() -> napi::Result<Context> {
+        let tcti = TctiNameConf::from_environment_variable()
+            .unwrap_or_else(|_| TctiNameConf::Device(Default::default()));
+
+        tracing::debug!(?tcti, "Connecting to the TPM");
+        Context::new(tcti).map_napi_error()
+    }
+
+    /// Create the primary storage key under the owner hierarchy,
+    /// using the default ECC P-256 storage root key template.
+    fn create_primary(ctx: &mut Context) -> tss_esapi::Result<KeyHandle> {
+        let attributes = ObjectAttributesBuilder::new()
+            .with_fixed_tpm(true)
+            .with_fixed_parent(true)
+            .with_sensitive_data_origin(true)
+            .with_user_with_auth(true)
+            .with_restricted(true)
+            .with_decrypt(true)
+            .build()?;
+
+        let public = PublicBuilder::new()
+            .with_public_algorithm(PublicAlgorithm::Ecc)
+            .with_name_hashing_algorithm(HashingAlgorithm::Sha256)
+            .with_object_attributes(attributes)
+            .with_ecc_parameters(
+                PublicEccParametersBuilder::new_restricted_decryption_key(
+                    SymmetricDefinitionObject::AES_128_CFB,
+                    EccCurve::NistP256,
+                )
+                .build()?,
+            )
+            .with_ecc_unique_identifier(EccPoint::default())
+            .build()?;
+
+        Ok(ctx
+            .create_primary(Hierarchy::Owner, public, None, None, None, None)?
+            .key_handle)
+    }
+
+    /// The template of the signing keys, 2048 bit RSA keys
+    /// signing using RSASSA-PKCS1-v1_5 with SHA-256, like
+    /// the keys created by Windows Hello.
+    fn signing_key_template() -> tss_esapi::Result<Public> {
+        let attributes = ObjectAttributesBuilder::new()
+            .with_fixed_tpm(true)
+            .with_fixed_parent(true)
+            .with_sensitive_data_origin(true)
+            .with_user_with_auth(true)
+            .with_sign_encrypt(true)
+            .build()?;
+
+        PublicBuilder::new()
+            .with_public_algorithm(PublicAlgorithm::Rsa)
+            .with_name_hashing_algorithm(HashingAlgorithm::Sha256)
+            .with_object_attributes(attributes)
+            .with_rsa_parameters(
+                PublicRsaParametersBuilder::new()
+                    .with_scheme(RsaScheme::create(
+                        RsaSchemeAlgorithm::RsaSsa,
+                        Some(HashingAlgorithm::Sha256),
+                    )?)
+                    .with_key_bits(RsaKeyBits::Rsa2048)
+                    .with_exponent(RsaExponent::default())
+                    .with_is_signing_key(true)
+                    .with_restricted(false)
+                    .build()?,
+            )
+            .with_rsa_unique_identifier(PublicKeyRsa::default())
+            .build()
+    }
+
+    fn create_blob(account_id: &str) -> napi::Result<KeyBlob> {
+        let mut ctx = Self::context()?;
+        let (public, private) = ctx
+            .execute_with_nullauth_session(|ctx| {
+                let primary = Self::create_primary(ctx)?;
+                let created = Self::signing_key_template()
+                    .and_then(|template| ctx.create(primary, template, None, None, None, None));
+                ctx.flush_context(primary.into())?;
+
+                let created = created?;
+                Ok::<_, tss_esapi::Error>((created.out_public, created.out_private))
+            })
+            .map_napi_error()?;
+
+        Ok(KeyBlob {
+            account_id: account_id.into(),
+            public: hex::encode(public.marshall().map_napi_error()?),
+            private: hex::encode(private.value()),
+        })
+    }
+
+    fn sign_with_blob(blob: &KeyBlob, data: &[u8]) -> napi::Result<Vec<u8>> {
+        let public = blob.public()?;
+        let private = blob.private()?;
+        let digest = Digest::try_from(Sha256::digest(data).to_vec()).map_napi_error()?;
+        let validation = HashcheckTicket::try_from(TPMT_TK_HASHCHECK {
+            tag: TPM2_ST_HASHCHECK,
+            hierarchy: TPM2_RH_NULL,
+            digest: Default::default(),
+        })
+        .map_napi_error()?;
+
+        let mut ctx = Self::context()?;
+        let signature = ctx
+            .execute_with_nullauth_session(|ctx| {
+                let primary = Self::create_primary(ctx)?;
+                let key = ctx.load(primary, private, public);
+                ctx.flush_context(primary.into())?;
+
+                let key = key?;
+                let signature = ctx.sign(
+                    key,
+                    digest,
+                    SignatureScheme::RsaSsa {
+                        hash_scheme: HashScheme::new(HashingAlgorithm::Sha256),
+                    },
+                    validation,
+                );
+                ctx.flush_context(key.into())?;
+
+                signature
+            })
+            .map_napi_error()?;
+
+        match signature {
+            Signature::RsaSsa(signature) => Ok(signature.signature().value().to_vec()),
+            _ => Err(napi::Error::from_reason(
+                "The TPM returned an unexpected signature type",
+            )),
+        }
+    }
+
+    /// Ask the user for consent to sign using the key of an account.
+    async fn verify_consent(account_id: &str) -> Result<(), OperationError> {
+        let provider = CONSENT_PROVIDER.read().unwrap().unwrap_or_default();
+        let message = format!("Sign using the passport key of '{}'", account_id);
+        match get_verifier(provider).verify(&message).await? {
+            VerificationResult::Verified => Ok(()),
+            result => Err(OperationError::not_verified(result)),
+        }
+    }
+
+    /// Sign data using a key blob on the blocking thread pool.
+    async fn sign_blocking(blob: &KeyBlob, data: &[u8]) -> Result<Vec<u8>, OperationError> {
+        let blob = blob.clone();
+        let data = data.to_vec();
+
+        Self::blocking(move || {
+            let _span = tracing::debug_span!("TPM2_Sign").entered();
+            Ok(Self::sign_with_blob(&blob, &data)?)
+        })
+        .await
+    }
+
+    /// Run a blocking TPM operation on the blocking thread pool.
+    async fn blocking<T, F>(f: F) -> Result<T, OperationError>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T, OperationError> + Send + 'static,
+    {
+        tokio::task::spawn_blocking(f).await.map_napi_error()?
+    }
+}
+
+impl KeyBackend for TpmBackend {
+    fn available(&self) -> napi::Result<bool> {
+        Ok(Self::context().is_ok())
+    }
+
+    fn account_exists(&self, account_id: &str) -> napi::Result<bool> {
+        Ok(Self::key_path(account_id).is_file())
+    }
+
+    fn create_key<'a>(
+        &'a self,
+        account_id: &'a str,
+        option: KeyCreationOption,
+    ) -> BoxFuture<'a, Result<(), OperationError>> {
+        async move {
+            // Fail before creating the key, the check
+            // is repeated once the directory is locked
+            let replace = matches!(option, KeyCreationOption::ReplaceExisting);
+            if !replace && self.account_exists(account_id)? {
+                return Err(OperationError::already_exists());
+            }
+
+            let account_id = account_id.to_string();
+            Self::blocking(move || {
+                let blob = tracing::debug_span!("TPM2_Create")
+                    .in_scope(|| Self::create_blob(&account_id))?;
+                Self::locked(|| Self::write_key(&blob, replace))
+            })
+            .await
+        }
+        .boxed()
+    }
+
+    fn delete_key<'a>(&'a self, account_id: &'a str) -> BoxFuture<'a, Result<(), OperationError>> {
+        let path = Self::key_path(account_id);
+        Self::blocking(move || {
+            // The key can't be used without the blob,
+            // so there is nothing to remove from the TPM
+            Self::locked(|| match fs::remove_file(&path) {
+                Err(e) if e.kind() == ErrorKind::NotFound => Err(OperationError::not_found()),
+                result => Ok(result.map_napi_error()?),
+            })
+        })
+        .boxed()
+    }
+
+    fn can_move_keys(&self) -> bool {
+        true
+    }
+
+    fn move_key<'a>(
+        &'a self,
+        from: &'a str,
+        to: &'a str,
+    ) -> BoxFuture<'a, Result<(), OperationError>> {
+        let (from, to) = (from.to_string(), to.to_string());
+        Self::blocking(move || {
+            Self::locked(|| {
+                let mut blob = Self::read_key(&from)?;
+                blob.account_id = to;
+                Self::write_key(&blob, true)?;
+
+                Ok(fs::remove_file(Self::key_path(&from)).map_napi_error()?)
+            })
+        })
+        .boxed()
+    }
+
+    fn sign<'a>(
+        &'a self,
+        account_id: &'a str,
+        data: &'a [u8],
+    ) -> BoxFuture<'a, Result<Vec<u8>, OperationError>> {
+        async move {
+            let blob = Self::read_key(account_id)?;
+            Self::verify_consent(account_id).await?;
+            Self::sign_blocking(&blob, data).await
+        }
+        .boxed()
+    }
+
+    fn sign_many<'a>(
+        &'a self,
+        account_id: &'a str,
+        data: &'a [Vec<u8>],
+    ) -> BoxFuture<'a, Vec<Result<Vec<u8>, OperationError>>> {
+        async move {
+            // Ask the user once for all items
+            let consent = match Self::read_key(account_id) {
+                Ok(blob) => Self::verify_consent(account_id).await.map(|_| blob),
+                Err(e) => Err(e),
+            };
+            let blob = match consent {
+                Ok(blob) => blob,
+                Err(e) => return data.iter().map(|_| Err(e.skipped())).collect(),
+            };
+
+            sign_each(data, |item| Self::sign_blocking(&blob, item)).await
+        }
+        .boxed()
+    }
+
+    fn public_key<'a>(
+        &'a self,
+        account_id: &'a str,
+        encoding: PublicKeyEncoding,
+    ) -> BoxFuture<'a, Result<Vec<u8>, OperationError>> {
+        async move {
+            let key = Self::read_key(account_id)?.public_key()?;
+            Ok(encode_public_key(&key, encoding)?)
+        }
+        .boxed()
+    }
+}
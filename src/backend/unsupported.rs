@@ -0,0 +1,64 @@
+use crate::backend::KeyBackend;
+use crate::node::key_creation_option::KeyCreationOption;
+use crate::node::passport_backend::PassportBackend;
+use crate::node::public_key_encoding::PublicKeyEncoding;
+use crate::util::errors::OperationError;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+
+/// A backend which is not supported on this platform.
+/// It is never available and all operations fail.
+pub struct UnsupportedBackend {
+    backend: PassportBackend,
+}
+
+impl UnsupportedBackend {
+    pub fn new(backend: PassportBackend) -> Self {
+        Self { backend }
+    }
+
+    fn error(&self) -> napi::Error {
+        napi::Error::from_reason(format!(
+            "The {} backend is not supported on this platform",
+            self.backend.name()
+        ))
+    }
+}
+
+impl KeyBackend for UnsupportedBackend {
+    fn available(&self) -> napi::Result<bool> {
+        Ok(false)
+    }
+
+    fn account_exists(&self, _account_id: &str) -> napi::Result<bool> {
+        Err(self.error())
+    }
+
+    fn create_key<'a>(
+        &'a self,
+        _account_id: &'a str,
+        _option: KeyCreationOption,
+    ) -> BoxFuture<'a, Result<(), OperationError>> {
+        async move { Err(self.error().into()) }.boxed()
+    }
+
+    fn delete_key<'a>(&'a self, _account_id: &'a str) -> BoxFuture<'a, Result<(), OperationError>> {
+        async move { Err(self.error().into()) }.boxed()
+    }
+
+    fn sign<'a>(
+        &'a self,
+        _account_id: &'a str,
+        _data: &'a [u8],
+    ) -> BoxFuture<'a, Result<Vec<u8>, OperationError>> {
+        async move { Err(self.error().into()) }.boxed()
+    }
+
+    fn public_key<'a>(
+        &'a self,
+        _account_id: &'a str,
+        _encoding: PublicKeyEncoding,
+    ) -> BoxFuture<'a, Result<Vec<u8>, OperationError>> {
+        async move { Err(self.error().into()) }.boxed()
+    }
+}
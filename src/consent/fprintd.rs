@@ -0,0 +1,167 @@
+use crate::consent::ConsentVerifier;
+use crate::node::verification_result::VerificationResult;
+use crate::util::mappers::MapNapiError;
+use futures::future::BoxFuture;
+use futures::{FutureExt, StreamExt};
+use zbus::zvariant::OwnedObjectPath;
+use zbus::{dbus_proxy, Connection};
+
+/// How many fingerprint scans are accepted before giving up.
+const MAX_ATTEMPTS: usize = 3;
+
+#[dbus_proxy(
+    interface = "net.reactivated.Fprint.Manager",
+    default_service = "net.reactivated.Fprint",
+    default_path = "/net/reactivated/Fprint/Manager"
+)]
+trait Manager {
+    fn get_default_device(&self) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[dbus_proxy(
+    interface = "net.reactivated.Fprint.Device",
+    default_service = "net.reactivated.Fprint"
+)]
+trait Device {
+    fn claim(&self, username: &str) -> zbus::Result<()>;
+
+    fn release(&self) -> zbus::Result<()>;
+
+    fn verify_start(&self, finger_name: &str) -> zbus::Result<()>;
+
+    fn verify_stop(&self) -> zbus::Result<()>;
+
+    #[dbus_proxy(signal)]
+    fn verify_status(&self, result: String, done: bool) -> zbus::Result<()>;
+}
+
+/// Verifies the user by scanning their fingerprint using `fprintd`.
+pub struct FprintdVerifier;
+
+impl FprintdVerifier {
+    /// Map the errors fprintd and the bus return for expected
+    /// conditions, like a missing reader, to a verification result.
+    fn map_error(error: zbus::Error) -> napi::Result<VerificationResult> {
+        let name = match &error {
+            zbus::Error::MethodError(name, _, _) => name.as_str(),
+            _ => "",
+        };
+
+        let result = match name {
+            "net.reactivated.Fprint.Error.NoSuchDevice"
+            | "org.freedesktop.DBus.Error.ServiceUnknown"
+            | "org.freedesktop.DBus.Error.NameHasNoOwner" => VerificationResult::DeviceNotPresent,
+            "net.reactivated.Fprint.Error.NoEnrolledPrints" => {
+                VerificationResult::NotConfiguredForUser
+            }
+            "net.reactivated.Fprint.Error.AlreadyInUse" => VerificationResult::DeviceBusy,
+            "net.reactivated.Fprint.Error.PermissionDenied" => VerificationResult::DisabledByPolicy,
+            _ => return Err(error).map_napi_error(),
+        };
+
+        tracing::debug!(error = %error, result = result.name(), "fprintd returned an error");
+        Ok(result)
+    }
+
+    async fn verify_device(connection: &Connection) -> napi::Result<VerificationResult> {
+        let manager = ManagerProxy::new(connection).await.map_napi_error()?;
+        let path = match manager.get_default_device().await {
+            Ok(path) => path,
+            Err(e) => return Self::map_error(e),
+        };
+
+        let device = DeviceProxy::builder(connection)
+            .path(path)
+            .map_napi_error()?
+            .build()
+            .await
+            .map_napi_error()?;
+
+        // An empty user name claims the device for the calling user
+        if let Err(e) = device.claim("").await {
+            return Self::map_error(e);
+        }
+
+        let result = Self::verify_claimed(&device).await;
+        if let Err(e) = device.release().await {
+            tracing::warn!(error = %e, "Failed to release the fingerprint reader");
+        }
+
+        result
+    }
+
+    async fn verify_claimed(device: &DeviceProxy<'_>) -> napi::Result<VerificationResult> {
+        // Subscribe before starting, so no status can be missed
+        let mut statuses = device.receive_verify_status().await.map_napi_error()?;
+
+        for attempt in 1..=MAX_ATTEMPTS {
+            if let Err(e) = device.verify_start("any").await {
+                return Self::map_error(e);
+            }
+
+            let result = Self::wait_for_result(&mut statuses).await;
+            if let Err(e) = device.verify_stop().await {
+                tracing::debug!(error = %e, "Failed to stop the verification");
+            }
+
+            match result? {
+                Some(result) => return Ok(result),
+                None => tracing::debug!(attempt, "The fingerprint did not match"),
+            }
+        }
+
+        Ok(VerificationResult::RetriesExhausted)
+    }
+
+    /// Wait for the final status of a verification.
+    /// Returns `None` if the fingerprint did not match
+    /// and the verification may be attempted again.
+    async fn wait_for_result(
+        statuses: &mut VerifyStatusStream<'_>,
+    ) -> napi::Result<Option<VerificationResult>> {
+        while let Some(status) = statuses.next().await {
+            let args = status.args().map_napi_error()?;
+            tracing::debug!(result = args.result, done = args.done, "Verify status");
+
+            match (args.result.as_str(), args.done) {
+                ("verify-match", _) => return Ok(Some(VerificationResult::Verified)),
+                ("verify-no-match", _) => return Ok(None),
+                ("verify-disconnected", _) => {
+                    return Ok(Some(VerificationResult::DeviceNotPresent))
+                }
+                ("verify-unknown-error", _) => {
+                    return Err(napi::Error::from_reason(
+                        "The fingerprint reader reported an unknown error",
+                    ))
+                }
+                // The reader asks for another scan, like verify-retry-scan
+                (_, false) => continue,
+                // The verification ended without a scan, count it as an attempt
+                (_, true) => return Ok(None),
+            }
+        }
+
+        // The signal stream only ends if the connection was closed
+        Ok(Some(VerificationResult::DeviceNotPresent))
+    }
+}
+
+impl ConsentVerifier for FprintdVerifier {
+    fn verify<'a>(&'a self, message: &'a str) -> BoxFuture<'a, napi::Result<VerificationResult>> {
+        async move {
+            // fprintd has no user interface to show the message in
+            tracing::info!(message, "Requesting a fingerprint scan");
+
+            let connection = match Connection::system().await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to connect to the system bus");
+                    return Ok(VerificationResult::DeviceNotPresent);
+                }
+            };
+
+            Self::verify_device(&connection).await
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,26 @@
+use crate::consent::ConsentVerifier;
+use crate::node::verification_result::VerificationResult;
+use crate::util::mappers::MapNapiError;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use tracing::Instrument;
+use windows::core::HSTRING;
+use windows::Security::Credentials::UI::UserConsentVerifier;
+
+/// Verifies the user using the Windows Hello consent dialog.
+pub struct HelloVerifier;
+
+impl ConsentVerifier for HelloVerifier {
+    fn verify<'a>(&'a self, message: &'a str) -> BoxFuture<'a, napi::Result<VerificationResult>> {
+        async move {
+            VerificationResult::try_from(
+                UserConsentVerifier::RequestVerificationAsync(&HSTRING::from(message))
+                    .map_napi_error()?
+                    .instrument(tracing::debug_span!("RequestVerificationAsync"))
+                    .await
+                    .map_napi_error()?,
+            )
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,69 @@
+use crate::node::consent_provider::ConsentProvider;
+use crate::node::verification_result::VerificationResult;
+use futures::future::BoxFuture;
+use std::sync::{Arc, RwLock};
+
+#[cfg(target_os = "linux")]
+pub(crate) mod fprintd;
+#[cfg(windows)]
+pub(crate) mod hello;
+#[cfg(target_os = "linux")]
+pub(crate) mod polkit;
+pub(crate) mod unsupported;
+
+/// The polkit action checked by [`ConsentProvider::Polkit`],
+/// if another than the default action was configured.
+static POLKIT_ACTION_ID: RwLock<Option<String>> = RwLock::new(None);
+
+/// Asks the user to prove their presence, e.g. using a PIN,
+/// a fingerprint or their password.
+pub trait ConsentVerifier: Send + Sync {
+    /// Ask the user to verify their identity. Failing to verify
+    /// the user is not an error, but a [`VerificationResult`]
+    /// other than [`VerificationResult::Verified`].
+    fn verify<'a>(&'a self, message: &'a str) -> BoxFuture<'a, napi::Result<VerificationResult>>;
+}
+
+/// Get the verifier of a consent provider.
+pub fn get_verifier(provider: ConsentProvider) -> Arc<dyn ConsentVerifier> {
+    match provider {
+        #[cfg(windows)]
+        ConsentProvider::WindowsHello => Arc::new(hello::HelloVerifier),
+        #[cfg(target_os = "linux")]
+        ConsentProvider::Fprintd => Arc::new(fprintd::FprintdVerifier),
+        #[cfg(target_os = "linux")]
+        ConsentProvider::Polkit => {
+            let action_id = POLKIT_ACTION_ID
+                .read()
+                .unwrap()
+                .clone()
+                .unwrap_or_else(|| polkit::DEFAULT_ACTION_ID.to_string());
+
+            Arc::new(polkit::PolkitVerifier::new(action_id))
+        }
+        #[allow(unreachable_patterns)]
+        provider => Arc::new(unsupported::UnsupportedVerifier::new(provider)),
+    }
+}
+
+/// Set the polkit action checked by [`ConsentProvider::Polkit`],
+/// or reset it to the default action.
+pub fn set_polkit_action_id(action_id: Option<String>) -> napi::Result<()> {
+    if let Some(action_id) = &action_id {
+        // polkit only accepts lower case letters, digits, dots and dashes
+        let valid = !action_id.is_empty()
+            && action_id
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '.' || c == '-');
+
+        if !valid {
+            return Err(napi::Error::from_reason(format!(
+                "'{}' is not a valid polkit action ID",
+                action_id
+            )));
+        }
+    }
+
+    *POLKIT_ACTION_ID.write().unwrap() = action_id;
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use crate::consent::ConsentVerifier;
+use crate::node::verification_result::VerificationResult;
+use crate::util::mappers::MapNapiError;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use std::collections::HashMap;
+use zbus::zvariant::Value;
+use zbus::{dbus_proxy, Connection};
+
+/// The action checked if no other action was configured.
+/// It is defined in the policy shipped with this package.
+pub const DEFAULT_ACTION_ID: &str = "io.github.markusjx.passport-desktop.verify";
+
+/// Allow polkit to ask the user to authenticate.
+const ALLOW_USER_INTERACTION: u32 = 1;
+
+#[dbus_proxy(
+    interface = "org.freedesktop.PolicyKit1.Authority",
+    default_service = "org.freedesktop.PolicyKit1",
+    default_path = "/org/freedesktop/PolicyKit1/Authority"
+)]
+trait Authority {
+    #[allow(clippy::type_complexity)]
+    fn check_authorization(
+        &self,
+        subject: &(&str, HashMap<&str, Value<'_>>),
+        action_id: &str,
+        details: HashMap<&str, &str>,
+        flags: u32,
+        cancellation_id: &str,
+    ) -> zbus::Result<(bool, bool, HashMap<String, String>)>;
+}
+
+/// Verifies the user by asking polkit whether the user is authorized
+/// to perform an action. Depending on the policy of the action, the
+/// polkit authentication agent asks the user for their password.
+pub struct PolkitVerifier {
+    action_id: String,
+}
+
+impl PolkitVerifier {
+    pub fn new(action_id: String) -> Self {
+        Self { action_id }
+    }
+
+    /// Get the start time of this process, which identifies
+    /// the process together with its PID.
+    fn start_time() -> napi::Result<u64> {
+        let stat = std::fs::read_to_string("/proc/self/stat").map_napi_error()?;
+
+        // The process name in the second field may contain spaces,
+        // the start time is the 22nd field
+        stat.rsplit_once(')')
+            .and_then(|(_, fields)| fields.split_whitespace().nth(19))
+            .and_then(|start_time| start_time.parse().ok())
+            .ok_or_else(|| napi::Error::from_reason("Failed to get the process start time"))
+    }
+
+    /// Map the errors polkit and the bus return for expected
+    /// conditions, like a missing polkit daemon, to a verification result.
+    fn map_error(error: zbus::Error) -> napi::Result<VerificationResult> {
+        let name = match &error {
+            zbus::Error::MethodError(name, _, _) => name.as_str(),
+            _ => "",
+        };
+
+        let result = match name {
+            "org.freedesktop.PolicyKit1.Error.Cancelled" => VerificationResult::Canceled,
+            "org.freedesktop.DBus.Error.ServiceUnknown"
+            | "org.freedesktop.DBus.Error.NameHasNoOwner" => VerificationResult::DeviceNotPresent,
+            _ => return Err(error).map_napi_error(),
+        };
+
+        tracing::debug!(error = %error, result = result.name(), "polkit returned an error");
+        Ok(result)
+    }
+
+    async fn check(
+        &self,
+        connection: &Connection,
+        message: &str,
+    ) -> napi::Result<VerificationResult> {
+        let authority = AuthorityProxy::new(connection).await.map_napi_error()?;
+        let subject = (
+            "unix-process",
+            HashMap::from([
+                ("pid", Value::from(std::process::id())),
+                ("start-time", Value::from(Self::start_time()?)),
+            ]),
+        );
+
+        // The message can be shown using $(message) in the policy
+        let details = HashMap::from([("message", message)]);
+        let (authorized, challenge, details) = match authority
+            .check_authorization(
+                &subject,
+                &self.action_id,
+                details,
+                ALLOW_USER_INTERACTION,
+                "",
+            )
+            .await
+        {
+            Ok(result) => result,
+            Err(e) => return Self::map_error(e),
+        };
+
+        tracing::debug!(authorized, challenge, ?details, "Authorization checked");
+        Ok(if authorized {
+            VerificationResult::Verified
+        } else if details.get("polkit.dismissed").map(String::as_str) == Some("true") {
+            VerificationResult::Canceled
+        } else if challenge {
+            // The user could have been authenticated,
+            // but there is no authentication agent to ask them
+            VerificationResult::DeviceNotPresent
+        } else {
+            VerificationResult::DisabledByPolicy
+        })
+    }
+}
+
+impl ConsentVerifier for PolkitVerifier {
+    fn verify<'a>(&'a self, message: &'a str) -> BoxFuture<'a, napi::Result<VerificationResult>> {
+        async move {
+            let connection = match Connection::system().await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to connect to the system bus");
+                    return Ok(VerificationResult::DeviceNotPresent);
+                }
+            };
+
+            tracing::info!(
+                action_id = self.action_id,
+                "Checking the polkit authorization"
+            );
+            self.check(&connection, message).await
+        }
+        .boxed()
+    }
+}
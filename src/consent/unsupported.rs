@@ -0,0 +1,30 @@
+use crate::consent::ConsentVerifier;
+use crate::node::consent_provider::ConsentProvider;
+use crate::node::verification_result::VerificationResult;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+
+/// A consent provider which is not supported on this platform.
+/// The user can never be verified.
+pub struct UnsupportedVerifier {
+    provider: ConsentProvider,
+}
+
+impl UnsupportedVerifier {
+    pub fn new(provider: ConsentProvider) -> Self {
+        Self { provider }
+    }
+}
+
+impl ConsentVerifier for UnsupportedVerifier {
+    fn verify<'a>(&'a self, _message: &'a str) -> BoxFuture<'a, napi::Result<VerificationResult>> {
+        async move {
+            tracing::warn!(
+                provider = self.provider.name(),
+                "The consent provider is not supported on this platform"
+            );
+            Ok(VerificationResult::DeviceNotPresent)
+        }
+        .boxed()
+    }
+}
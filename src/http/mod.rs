@@ -0,0 +1,396 @@
+use crate::http::structured_fields::{
+    is_key, is_string, parse_dictionary, serialize_bare_item, serialize_inner_list, BareItem, Item,
+    MemberValue, Parameters,
+};
+use rsa::pkcs1::DecodeRsaPublicKey;
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+use rsa::pkcs8::DecodePublicKey;
+use rsa::signature::Verifier;
+use rsa::RsaPublicKey;
+use sha2::Sha256;
+use std::collections::HashMap;
+
+pub(crate) mod structured_fields;
+
+/// The algorithm of passport keys, as registered in RFC 9421.
+pub const ALGORITHM: &str = "rsa-v1_5-sha256";
+/// The name of the component covering the signature parameters.
+const SIGNATURE_PARAMS: &str = "@signature-params";
+
+fn invalid(reason: &str) -> napi::Error {
+    napi::Error::from_reason(format!("The HTTP signature is invalid: {}", reason))
+}
+
+/// The parts of a target URI the derived components are built from.
+struct TargetUri<'a> {
+    scheme: String,
+    authority: String,
+    path: &'a str,
+    query: Option<&'a str>,
+}
+
+impl<'a> TargetUri<'a> {
+    /// Split an absolute URI. The fragment is never sent, so it is dropped.
+    fn parse(uri: &'a str) -> napi::Result<Self> {
+        let error = || napi::Error::from_reason(format!("The target URI '{}' is invalid", uri));
+        let (scheme, rest) = uri.split_once("://").ok_or_else(error)?;
+        if scheme.is_empty()
+            || !scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        {
+            return Err(error());
+        }
+
+        let rest = rest.split('#').next().unwrap();
+        let (authority, path_and_query) = match rest.find(['/', '?']) {
+            Some(index) => rest.split_at(index),
+            None => (rest, ""),
+        };
+        let authority = authority.rsplit('@').next().unwrap();
+        if authority.is_empty() {
+            return Err(error());
+        }
+
+        let (path, query) = match path_and_query.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (path_and_query, None),
+        };
+
+        let scheme = scheme.to_ascii_lowercase();
+        let mut authority = authority.to_ascii_lowercase();
+        let default_port = match scheme.as_str() {
+            "http" => Some(":80"),
+            "https" => Some(":443"),
+            _ => None,
+        };
+        if let Some(port) = default_port.filter(|port| authority.ends_with(port)) {
+            authority.truncate(authority.len() - port.len());
+        }
+
+        Ok(Self {
+            scheme,
+            authority,
+            path,
+            query,
+        })
+    }
+
+    fn path(&self) -> &str {
+        if self.path.is_empty() {
+            "/"
+        } else {
+            self.path
+        }
+    }
+
+    fn query(&self) -> String {
+        format!("?{}", self.query.unwrap_or_default())
+    }
+}
+
+/// A request whose components are covered by a signature.
+pub struct Request<'a> {
+    pub method: &'a str,
+    pub target_uri: &'a str,
+    pub headers: &'a HashMap<String, String>,
+}
+
+impl Request<'_> {
+    /// The value of a derived component or header field.
+    fn component_value(&self, name: &str) -> napi::Result<String> {
+        let uri = || TargetUri::parse(self.target_uri);
+        Ok(match name {
+            "@method" => self.method.to_string(),
+            "@target-uri" => self.target_uri.split('#').next().unwrap().to_string(),
+            "@authority" => uri()?.authority,
+            "@scheme" => uri()?.scheme,
+            "@request-target" => {
+                let uri = uri()?;
+                match uri.query {
+                    Some(query) => format!("{}?{}", uri.path(), query),
+                    None => uri.path().to_string(),
+                }
+            }
+            "@path" => uri()?.path().to_string(),
+            "@query" => uri()?.query(),
+            name if name.starts_with('@') => {
+                return Err(napi::Error::from_reason(format!(
+                    "The component '{}' is not supported",
+                    name
+                )))
+            }
+            name => self
+                .headers
+                .iter()
+                .find(|(header, _)| header.eq_ignore_ascii_case(name))
+                .map(|(_, value)| {
+                    value
+                        .split('\n')
+                        .map(|line| line.trim_matches([' ', '\t', '\r']))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .ok_or_else(|| {
+                    napi::Error::from_reason(format!(
+                        "The component '{}' is not present in the request",
+                        name
+                    ))
+                })?,
+        })
+    }
+}
+
+/// The signature parameters, serialized as the value of the
+/// `@signature-params` component and the `Signature-Input` header.
+pub struct SignatureParams {
+    pub components: Vec<String>,
+    pub parameters: Parameters,
+}
+
+impl SignatureParams {
+    /// Create the parameters of a new signature using [`ALGORITHM`].
+    pub fn new(
+        components: Vec<String>,
+        created: u32,
+        expires: Option<u32>,
+        key_id: String,
+        nonce: Option<String>,
+        tag: Option<String>,
+    ) -> napi::Result<Self> {
+        for (index, component) in components.iter().enumerate() {
+            if component.is_empty()
+                || component.chars().any(|c| c.is_ascii_uppercase())
+                || !is_string(component)
+            {
+                return Err(napi::Error::from_reason(format!(
+                    "The component '{}' must be a lower case name",
+                    component
+                )));
+            }
+            if component == SIGNATURE_PARAMS || components[..index].contains(component) {
+                return Err(napi::Error::from_reason(format!(
+                    "The component '{}' cannot be covered twice",
+                    component
+                )));
+            }
+        }
+
+        let mut parameters = vec![("created".to_string(), BareItem::Integer(created as i64))];
+        if let Some(expires) = expires {
+            if expires <= created {
+                return Err(napi::Error::from_reason(
+                    "The signature must expire after it was created",
+                ));
+            }
+
+            parameters.push(("expires".to_string(), BareItem::Integer(expires as i64)));
+        }
+
+        let strings = [("keyid", Some(key_id)), ("nonce", nonce), ("tag", tag)];
+        for (key, value) in strings {
+            if let Some(value) = value {
+                if !is_string(&value) {
+                    return Err(napi::Error::from_reason(format!(
+                        "The {} must only contain printable ASCII characters",
+                        key
+                    )));
+                }
+
+                parameters.push((key.to_string(), BareItem::String(value)));
+            }
+        }
+
+        parameters.push(("alg".to_string(), BareItem::String(ALGORITHM.to_string())));
+        Ok(Self {
+            components,
+            parameters,
+        })
+    }
+
+    fn serialize(&self) -> String {
+        let items = self
+            .components
+            .iter()
+            .map(|component| Item {
+                value: BareItem::String(component.clone()),
+                parameters: vec![],
+            })
+            .collect::<Vec<_>>();
+
+        serialize_inner_list(&items, &self.parameters)
+    }
+
+    fn parameter(&self, key: &str) -> Option<&BareItem> {
+        self.parameters
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value)
+    }
+
+    pub fn key_id(&self) -> Option<String> {
+        match self.parameter("keyid") {
+            Some(BareItem::String(key_id)) => Some(key_id.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn integer(&self, key: &str) -> Option<i64> {
+        match self.parameter(key) {
+            Some(BareItem::Integer(value)) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+/// Build the signature base of a request, the data the account key signs.
+pub fn signature_base(request: &Request, params: &SignatureParams) -> napi::Result<String> {
+    let mut base = String::new();
+    for component in &params.components {
+        let value = request.component_value(component)?;
+        base.push_str(&format!("\"{}\": {}\n", component, value));
+    }
+
+    base.push_str(&format!("\"{}\": {}", SIGNATURE_PARAMS, params.serialize()));
+    Ok(base)
+}
+
+/// Check whether a label can be used as key of the signature headers.
+pub fn check_label(label: &str) -> napi::Result<()> {
+    if !is_key(label) {
+        return Err(napi::Error::from_reason(format!(
+            "The label '{}' must start with a lower case letter and only contain lower case letters, digits, '_', '-', '.' and '*'",
+            label
+        )));
+    }
+
+    Ok(())
+}
+
+/// The value of the `Signature-Input` header.
+pub fn signature_input_header(label: &str, params: &SignatureParams) -> String {
+    format!("{}={}", label, params.serialize())
+}
+
+/// The value of the `Signature` header.
+pub fn signature_header(label: &str, signature: &[u8]) -> String {
+    format!(
+        "{}={}",
+        label,
+        serialize_bare_item(&BareItem::ByteSequence(signature.to_vec()))
+    )
+}
+
+/// Parse a PEM or DER encoded RSA public key, either
+/// as `SubjectPublicKeyInfo` or as PKCS#1 public key.
+pub fn parse_public_key(public_key: &[u8]) -> napi::Result<RsaPublicKey> {
+    let key = match std::str::from_utf8(public_key) {
+        Ok(pem) if pem.trim_start().starts_with("-----BEGIN") => {
+            RsaPublicKey::from_public_key_pem(pem.trim())
+                .ok()
+                .or_else(|| RsaPublicKey::from_pkcs1_pem(pem.trim()).ok())
+        }
+        _ => RsaPublicKey::from_public_key_der(public_key)
+            .ok()
+            .or_else(|| RsaPublicKey::from_pkcs1_der(public_key).ok()),
+    };
+
+    key.ok_or_else(|| napi::Error::from_reason("The public key is not a valid RSA public key"))
+}
+
+/// A verified signature.
+pub struct Verified {
+    pub label: String,
+    pub params: SignatureParams,
+}
+
+/// Verify the signature of a request using the `Signature-Input` and `Signature`
+/// headers. If no label is passed, the headers must contain a single signature.
+/// Signatures which expired before `now` are rejected.
+pub fn verify(
+    request: &Request,
+    signature_input: &str,
+    signature: &str,
+    public_key: &[u8],
+    label: Option<&str>,
+    now: u64,
+) -> napi::Result<Verified> {
+    let inputs = parse_dictionary(signature_input)?;
+    let label = match (label, inputs.as_slice()) {
+        (Some(label), _) => label.to_string(),
+        (None, [(label, _)]) => label.clone(),
+        (None, []) => return Err(invalid("the request is not signed")),
+        (None, _) => {
+            return Err(invalid(
+                "the request has multiple signatures, pass the label to verify",
+            ))
+        }
+    };
+
+    let (components, parameters) = match inputs.into_iter().find(|(key, _)| *key == label) {
+        Some((_, MemberValue::InnerList(items, parameters))) => (items, parameters),
+        Some(_) => return Err(invalid("the signature input is not a list of components")),
+        None => {
+            return Err(invalid(&format!(
+                "there is no signature labeled '{}'",
+                label
+            )))
+        }
+    };
+
+    let components = components
+        .into_iter()
+        .map(|item| match item {
+            Item {
+                value: BareItem::String(component),
+                parameters,
+            } if parameters.is_empty() => Ok(component),
+            _ => Err(invalid("only components without parameters are supported")),
+        })
+        .collect::<napi::Result<Vec<_>>>()?;
+    let params = SignatureParams {
+        components,
+        parameters,
+    };
+
+    match params.parameter("alg") {
+        None => {}
+        Some(BareItem::String(alg)) if alg == ALGORITHM => {}
+        Some(_) => return Err(invalid(&format!("only {} is supported", ALGORITHM))),
+    }
+    if params
+        .integer("expires")
+        .is_some_and(|expires| expires < now as i64)
+    {
+        return Err(invalid("the signature expired"));
+    }
+
+    let value = match parse_dictionary(signature)?
+        .into_iter()
+        .find(|(key, _)| *key == label)
+    {
+        Some((
+            _,
+            MemberValue::Item(Item {
+                value: BareItem::ByteSequence(value),
+                ..
+            }),
+        )) => value,
+        _ => {
+            return Err(invalid(&format!(
+                "there is no signature labeled '{}'",
+                label
+            )))
+        }
+    };
+
+    let base = signature_base(request, &params)?;
+    let key = parse_public_key(public_key)?;
+    let value =
+        Signature::try_from(value.as_slice()).map_err(|_| invalid("the signature is malformed"))?;
+    VerifyingKey::<Sha256>::new(key)
+        .verify(base.as_bytes(), &value)
+        .map_err(|_| invalid("the signature does not match the request"))?;
+
+    Ok(Verified { label, params })
+}
@@ -0,0 +1,277 @@
+use base64ct::{Base64, Encoding};
+
+/// A bare item of a structured field, as defined in RFC 8941.
+/// Decimals are not used by HTTP message signatures.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BareItem {
+    Integer(i64),
+    String(String),
+    Token(String),
+    ByteSequence(Vec<u8>),
+    Boolean(bool),
+}
+
+/// The parameters of an item or inner list, in their original order.
+pub type Parameters = Vec<(String, BareItem)>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Item {
+    pub value: BareItem,
+    pub parameters: Parameters,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MemberValue {
+    Item(Item),
+    InnerList(Vec<Item>, Parameters),
+}
+
+fn invalid(reason: &str) -> napi::Error {
+    napi::Error::from_reason(format!("The structured field is invalid: {}", reason))
+}
+
+/// Check whether a string is a valid dictionary or parameter key.
+pub fn is_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_lowercase() || c == '*')
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "_-.*".contains(c))
+}
+
+/// Check whether a string only contains characters a string item may contain.
+pub fn is_string(value: &str) -> bool {
+    value.chars().all(|c| (' '..='~').contains(&c))
+}
+
+fn is_tchar(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~:/".contains(c)
+}
+
+pub fn serialize_bare_item(item: &BareItem) -> String {
+    match item {
+        BareItem::Integer(value) => value.to_string(),
+        BareItem::String(value) => {
+            format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+        }
+        BareItem::Token(value) => value.clone(),
+        BareItem::ByteSequence(value) => format!(":{}:", Base64::encode_string(value)),
+        BareItem::Boolean(value) => format!("?{}", *value as u8),
+    }
+}
+
+pub fn serialize_parameters(parameters: &Parameters) -> String {
+    parameters
+        .iter()
+        .map(|(key, value)| match value {
+            BareItem::Boolean(true) => format!(";{}", key),
+            value => format!(";{}={}", key, serialize_bare_item(value)),
+        })
+        .collect()
+}
+
+pub fn serialize_item(item: &Item) -> String {
+    serialize_bare_item(&item.value) + &serialize_parameters(&item.parameters)
+}
+
+pub fn serialize_inner_list(items: &[Item], parameters: &Parameters) -> String {
+    let items = items.iter().map(serialize_item).collect::<Vec<_>>();
+    format!("({}){}", items.join(" "), serialize_parameters(parameters))
+}
+
+/// Parses structured field values, failing on anything
+/// that is not a valid RFC 8941 serialization.
+struct Parser<'a> {
+    input: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.input.chars().next()
+    }
+
+    fn advance(&mut self, length: usize) {
+        self.input = &self.input[length..];
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.advance(c.len_utf8());
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_whitespace(&mut self, tabs: bool) {
+        self.input = self
+            .input
+            .trim_start_matches(|c| c == ' ' || (tabs && c == '\t'));
+    }
+
+    fn take_while<F: Fn(char) -> bool>(&mut self, f: F) -> &'a str {
+        let end = self.input.find(|c| !f(c)).unwrap_or(self.input.len());
+        let value = &self.input[..end];
+        self.advance(end);
+        value
+    }
+
+    fn key(&mut self) -> napi::Result<String> {
+        let key =
+            self.take_while(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "_-.*".contains(c));
+        if !is_key(key) {
+            return Err(invalid("expected a key"));
+        }
+
+        Ok(key.to_string())
+    }
+
+    fn bare_item(&mut self) -> napi::Result<BareItem> {
+        match self.peek() {
+            Some('"') => {
+                self.advance(1);
+                let mut value = String::new();
+                loop {
+                    match self.peek() {
+                        Some('"') => {
+                            self.advance(1);
+                            return Ok(BareItem::String(value));
+                        }
+                        Some('\\') => {
+                            self.advance(1);
+                            match self.peek() {
+                                Some(c @ ('"' | '\\')) => value.push(c),
+                                _ => return Err(invalid("invalid escape in a string")),
+                            }
+                            self.advance(1);
+                        }
+                        Some(c) if (' '..='~').contains(&c) => {
+                            value.push(c);
+                            self.advance(1);
+                        }
+                        _ => return Err(invalid("unterminated string")),
+                    }
+                }
+            }
+            Some(':') => {
+                self.advance(1);
+                let value = self.take_while(|c| c.is_ascii_alphanumeric() || "+/=".contains(c));
+                if !self.eat(':') {
+                    return Err(invalid("unterminated byte sequence"));
+                }
+
+                Base64::decode_vec(value)
+                    .map(BareItem::ByteSequence)
+                    .map_err(|_| invalid("invalid byte sequence"))
+            }
+            Some('?') => {
+                self.advance(1);
+                if self.eat('1') {
+                    Ok(BareItem::Boolean(true))
+                } else if self.eat('0') {
+                    Ok(BareItem::Boolean(false))
+                } else {
+                    Err(invalid("invalid boolean"))
+                }
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let negative = self.eat('-');
+                let digits = self.take_while(|c| c.is_ascii_digit());
+                if digits.is_empty() || digits.len() > 15 || self.peek() == Some('.') {
+                    return Err(invalid("only integers up to 15 digits are supported"));
+                }
+
+                let value = digits.parse::<i64>().unwrap();
+                Ok(BareItem::Integer(if negative { -value } else { value }))
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == '*' => {
+                Ok(BareItem::Token(self.take_while(is_tchar).to_string()))
+            }
+            _ => Err(invalid("expected an item")),
+        }
+    }
+
+    fn parameters(&mut self) -> napi::Result<Parameters> {
+        let mut parameters = Parameters::new();
+        while self.eat(';') {
+            self.skip_whitespace(false);
+            let key = self.key()?;
+            let value = if self.eat('=') {
+                self.bare_item()?
+            } else {
+                BareItem::Boolean(true)
+            };
+
+            match parameters.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, existing)) => *existing = value,
+                None => parameters.push((key, value)),
+            }
+        }
+
+        Ok(parameters)
+    }
+
+    fn item(&mut self) -> napi::Result<Item> {
+        Ok(Item {
+            value: self.bare_item()?,
+            parameters: self.parameters()?,
+        })
+    }
+
+    fn member_value(&mut self) -> napi::Result<MemberValue> {
+        if !self.eat('(') {
+            return self.item().map(MemberValue::Item);
+        }
+
+        let mut items = vec![];
+        loop {
+            self.skip_whitespace(false);
+            if self.eat(')') {
+                return Ok(MemberValue::InnerList(items, self.parameters()?));
+            }
+
+            items.push(self.item()?);
+            if !matches!(self.peek(), Some(' ' | ')')) {
+                return Err(invalid("unterminated inner list"));
+            }
+        }
+    }
+}
+
+/// Parse a dictionary, like the value of the `Signature-Input` header.
+pub fn parse_dictionary(input: &str) -> napi::Result<Vec<(String, MemberValue)>> {
+    let mut parser = Parser { input };
+    let mut members: Vec<(String, MemberValue)> = vec![];
+    parser.skip_whitespace(false);
+    while !parser.input.is_empty() {
+        let key = parser.key()?;
+        let value = if parser.eat('=') {
+            parser.member_value()?
+        } else {
+            MemberValue::Item(Item {
+                value: BareItem::Boolean(true),
+                parameters: parser.parameters()?,
+            })
+        };
+
+        match members.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, existing)) => *existing = value,
+            None => members.push((key, value)),
+        }
+
+        parser.skip_whitespace(true);
+        if parser.input.is_empty() {
+            break;
+        }
+        if !parser.eat(',') {
+            return Err(invalid("expected a comma between members"));
+        }
+
+        parser.skip_whitespace(true);
+        if parser.input.is_empty() {
+            return Err(invalid("trailing comma"));
+        }
+    }
+
+    Ok(members)
+}
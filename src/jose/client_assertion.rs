@@ -0,0 +1,66 @@
+use crate::jose::{base64url, RS256};
+use rand::RngCore;
+use serde::Serialize;
+
+/// The `client_assertion_type` of JWT client assertions, defined in RFC 7523.
+pub const ASSERTION_TYPE: &str = "urn:ietf:params:oauth:client-assertion-type:jwt-bearer";
+
+#[derive(Serialize)]
+pub struct Header {
+    pub alg: &'static str,
+    pub typ: &'static str,
+    pub kid: String,
+}
+
+#[derive(Serialize)]
+pub struct Claims {
+    pub iss: String,
+    pub sub: String,
+    pub aud: String,
+    pub jti: String,
+    pub iat: u64,
+    pub exp: u64,
+}
+
+impl Header {
+    pub fn new(kid: String) -> Self {
+        Self {
+            alg: RS256,
+            typ: "JWT",
+            kid,
+        }
+    }
+}
+
+impl Claims {
+    /// The claims of a `private_key_jwt` client assertion, issued
+    /// by the client for itself and only valid for the audience.
+    pub fn new(client_id: String, audience: String, iat: u64, lifetime: u64) -> napi::Result<Self> {
+        if client_id.is_empty() || audience.is_empty() {
+            return Err(napi::Error::from_reason(
+                "The client ID and audience must not be empty",
+            ));
+        }
+        if lifetime == 0 {
+            return Err(napi::Error::from_reason(
+                "The assertion must be valid for at least one second",
+            ));
+        }
+
+        let exp = iat
+            .checked_add(lifetime)
+            .ok_or_else(|| napi::Error::from_reason("The lifetime of the assertion is too long"))?;
+
+        let mut jti = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut jti);
+
+        Ok(Self {
+            iss: client_id.clone(),
+            sub: client_id,
+            aud: audience,
+            jti: base64url(&jti),
+            iat,
+            exp,
+        })
+    }
+}
@@ -0,0 +1,157 @@
+use crate::jose::{base64url, DecodedToken, RsaJwk, RS256};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// The type of DPoP proofs, defined in RFC 9449.
+const TYPE: &str = "dpop+jwt";
+
+fn invalid(reason: &str) -> napi::Error {
+    napi::Error::from_reason(format!("The DPoP proof is invalid: {}", reason))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Header {
+    pub typ: String,
+    pub alg: String,
+    pub jwk: RsaJwk,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Claims {
+    pub jti: String,
+    pub htm: String,
+    pub htu: String,
+    pub iat: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ath: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+}
+
+/// The hash of an access token bound to a proof.
+pub fn access_token_hash(access_token: &str) -> String {
+    base64url(&Sha256::digest(access_token.as_bytes()))
+}
+
+/// Normalize the target URI of a request for the `htu` claim: the
+/// scheme and host are case insensitive, and the query and fragment
+/// are not part of the claim.
+pub fn normalize_uri(uri: &str) -> napi::Result<String> {
+    let uri = uri.split(['?', '#']).next().unwrap();
+    let (scheme, rest) = uri
+        .split_once("://")
+        .filter(|(scheme, rest)| !scheme.is_empty() && !rest.is_empty())
+        .ok_or_else(|| napi::Error::from_reason(format!("The URI '{}' is invalid", uri)))?;
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+
+    Ok(format!(
+        "{}://{}{}",
+        scheme.to_ascii_lowercase(),
+        authority.to_ascii_lowercase(),
+        path
+    ))
+}
+
+impl Header {
+    pub fn new(jwk: RsaJwk) -> Self {
+        Self {
+            typ: TYPE.to_string(),
+            alg: RS256.to_string(),
+            jwk,
+        }
+    }
+}
+
+impl Claims {
+    pub fn new(
+        htm: String,
+        htu: &str,
+        iat: u64,
+        access_token: Option<&str>,
+        nonce: Option<String>,
+    ) -> napi::Result<Self> {
+        let mut jti = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut jti);
+
+        Ok(Self {
+            jti: base64url(&jti),
+            htm,
+            htu: normalize_uri(htu)?,
+            iat,
+            ath: access_token.map(access_token_hash),
+            nonce,
+        })
+    }
+}
+
+/// What a proof is expected to contain.
+pub struct Expected<'a> {
+    pub htm: &'a str,
+    pub htu: &'a str,
+    pub access_token: Option<&'a str>,
+    pub nonce: Option<&'a str>,
+    pub thumbprint: Option<&'a str>,
+    pub max_age: u64,
+    pub now: u64,
+}
+
+/// A verified proof and the thumbprint of its key.
+pub struct Verified {
+    pub claims: Claims,
+    pub thumbprint: String,
+}
+
+/// Verify a proof as described in section 4.3 of RFC 9449.
+pub fn verify(proof: &str, expected: &Expected) -> napi::Result<Verified> {
+    let token = DecodedToken::<Header, Claims>::decode(proof)?;
+    let (header, claims) = (&token.header, &token.claims);
+    if header.typ != TYPE {
+        return Err(invalid(&format!("the type must be '{}'", TYPE)));
+    }
+    if header.alg != RS256 {
+        return Err(invalid(&format!("only {} is supported", RS256)));
+    }
+
+    token.verify(header.jwk.public_key()?)?;
+    let thumbprint = header.jwk.thumbprint();
+    if expected
+        .thumbprint
+        .is_some_and(|expected| expected != thumbprint)
+    {
+        return Err(invalid(
+            "the key is not the key the access token is bound to",
+        ));
+    }
+
+    if claims.htm != expected.htm {
+        return Err(invalid("the method does not match the request"));
+    }
+    if normalize_uri(&claims.htu)? != normalize_uri(expected.htu)? {
+        return Err(invalid("the URI does not match the request"));
+    }
+    // `iat` is chosen by whoever signed the proof, so the sums must not overflow
+    if claims.iat.saturating_add(expected.max_age) < expected.now
+        || claims.iat > expected.now.saturating_add(expected.max_age)
+    {
+        return Err(invalid("the proof was not issued recently"));
+    }
+    if claims.jti.is_empty() {
+        return Err(invalid("the proof has no ID"));
+    }
+
+    match (expected.access_token, &claims.ath) {
+        (None, _) => {}
+        (Some(access_token), Some(ath)) if access_token_hash(access_token) == *ath => {}
+        (Some(_), Some(_)) => return Err(invalid("the proof is bound to another access token")),
+        (Some(_), None) => return Err(invalid("the proof is not bound to the access token")),
+    }
+    if expected.nonce.is_some() && expected.nonce != claims.nonce.as_deref() {
+        return Err(invalid("the nonce does not match"));
+    }
+
+    Ok(Verified {
+        thumbprint,
+        claims: token.claims,
+    })
+}
@@ -0,0 +1,245 @@
+use crate::jose::{base64url, decode_part, parse_part, verify_rs256, RsaJwk, RS256};
+use crate::util::mappers::MapNapiError;
+use rsa::RsaPublicKey;
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+pub fn invalid(reason: &str) -> napi::Error {
+    napi::Error::from_reason(format!("The entitlement is invalid: {}", reason))
+}
+
+/// The protected header of the server's signature.
+#[derive(Deserialize)]
+struct ServerHeader {
+    alg: String,
+    /// Extensions the verifier must understand, which none are.
+    crit: Option<IgnoredAny>,
+}
+
+/// The protected header of a device's countersignature.
+#[derive(Serialize, Deserialize)]
+pub struct CountersignatureHeader {
+    pub alg: String,
+    pub jwk: RsaJwk,
+    /// The base64url encoded SHA-256 hash of the server's signature,
+    /// binding the countersignature to it. Both signatures cover the
+    /// same payload, as all signatures of a JWS do.
+    pub countersigns: String,
+}
+
+/// A signature of a JWS in the general JSON serialization.
+#[derive(Serialize, Deserialize)]
+struct TokenSignature {
+    protected: String,
+    signature: String,
+}
+
+/// A JWS in the general JSON serialization defined in section 7.2.1 of
+/// RFC 7515, with the server's signature first and the countersignature
+/// second. Tokens issued by the server may also be compact JWS.
+#[derive(Serialize, Deserialize)]
+struct Token {
+    payload: String,
+    signatures: Vec<TokenSignature>,
+}
+
+impl Token {
+    fn decode(token: &str) -> napi::Result<Self> {
+        let token = token.trim();
+        if token.starts_with('{') {
+            return serde_json::from_str(token)
+                .map_err(|e| invalid(&format!("the token is malformed: {}", e)));
+        }
+
+        let parts = token.split('.').collect::<Vec<_>>();
+        let [protected, payload, signature] = parts.as_slice() else {
+            return Err(invalid(
+                "expected a JWS in the compact or JSON serialization",
+            ));
+        };
+
+        Ok(Self {
+            payload: payload.to_string(),
+            signatures: vec![TokenSignature {
+                protected: protected.to_string(),
+                signature: signature.to_string(),
+            }],
+        })
+    }
+
+    fn signing_input(&self, signature: &TokenSignature) -> String {
+        format!("{}.{}", signature.protected, self.payload)
+    }
+
+    /// Verify the server's signature using the pinned key and
+    /// return the hash a countersignature has to contain.
+    fn verify_server_signature(&self, server_key: RsaPublicKey) -> napi::Result<String> {
+        let server = match self.signatures.as_slice() {
+            [server] | [server, _] => server,
+            [] => return Err(invalid("the token is not signed")),
+            _ => return Err(invalid("the token has more than two signatures")),
+        };
+
+        let header: ServerHeader = parse_part(&server.protected, "header")?;
+        if header.alg != RS256 {
+            return Err(invalid(&format!("only {} is supported", RS256)));
+        }
+        if header.crit.is_some() {
+            return Err(invalid("critical header parameters are not supported"));
+        }
+
+        let signature = decode_part(&server.signature, "signature")?;
+        verify_rs256(
+            server_key,
+            self.signing_input(server).as_bytes(),
+            &signature,
+        )
+        .map_err(|_| invalid("the server signature does not match the pinned key"))?;
+
+        Ok(base64url(&Sha256::digest(signature)))
+    }
+}
+
+/// A token whose server signature was verified,
+/// waiting for the device to countersign it.
+pub struct PendingCountersignature {
+    token: Token,
+    protected: String,
+}
+
+impl PendingCountersignature {
+    /// Verify the server's signature of a token and create the
+    /// countersignature header for the device key `jwk`. Tokens
+    /// which were already countersigned are rejected.
+    pub fn new(token: &str, server_key: RsaPublicKey, jwk: RsaJwk) -> napi::Result<Self> {
+        let token = Token::decode(token)?;
+        let countersigns = token.verify_server_signature(server_key)?;
+        if token.signatures.len() > 1 {
+            return Err(invalid("the token was already countersigned"));
+        }
+
+        let header = CountersignatureHeader {
+            alg: RS256.to_string(),
+            jwk,
+            countersigns,
+        };
+
+        Ok(Self {
+            token,
+            protected: base64url(&serde_json::to_vec(&header).map_napi_error()?),
+        })
+    }
+
+    /// The data the device key signs.
+    pub fn signing_input(&self) -> String {
+        format!("{}.{}", self.protected, self.token.payload)
+    }
+
+    /// Append the countersignature and encode the token
+    /// in the general JSON serialization.
+    pub fn finish(mut self, signature: &[u8]) -> napi::Result<String> {
+        self.token.signatures.push(TokenSignature {
+            protected: self.protected,
+            signature: base64url(signature),
+        });
+
+        serde_json::to_string(&self.token).map_napi_error()
+    }
+}
+
+/// What a token is expected to contain.
+pub struct Expected<'a> {
+    /// The JWK thumbprint of the device which must have countersigned.
+    pub device_thumbprint: Option<&'a str>,
+    pub require_countersignature: bool,
+    /// How many seconds the clocks of the server and client may differ.
+    pub leeway: u64,
+    pub now: u64,
+}
+
+/// The claims of a verified token and the thumbprint
+/// of the device which countersigned it, if any.
+pub struct Verified {
+    pub claims: Map<String, Value>,
+    pub device_thumbprint: Option<String>,
+}
+
+impl Verified {
+    /// The `exp` claim of the token, if it has one.
+    pub fn expires_at(&self) -> Option<u64> {
+        self.claims.get("exp").and_then(Value::as_u64)
+    }
+}
+
+fn time_claim(claims: &Map<String, Value>, name: &str) -> napi::Result<Option<u64>> {
+    claims
+        .get(name)
+        .map(|value| {
+            value
+                .as_u64()
+                .ok_or_else(|| invalid(&format!("the '{}' claim is not a timestamp", name)))
+        })
+        .transpose()
+}
+
+/// Verify the server's signature using the pinned key, the validity
+/// period of the claims and, if present, the countersignature.
+pub fn verify(
+    token: &str,
+    server_key: RsaPublicKey,
+    expected: &Expected,
+) -> napi::Result<Verified> {
+    let token = Token::decode(token)?;
+    let countersigns = token.verify_server_signature(server_key)?;
+
+    let claims: Map<String, Value> = parse_part(&token.payload, "claims")?;
+    if time_claim(&claims, "exp")?
+        .is_some_and(|exp| exp.saturating_add(expected.leeway) <= expected.now)
+    {
+        return Err(invalid("the token has expired"));
+    }
+    if time_claim(&claims, "nbf")?
+        .is_some_and(|nbf| nbf > expected.now.saturating_add(expected.leeway))
+    {
+        return Err(invalid("the token is not valid yet"));
+    }
+
+    let device_thumbprint = match token.signatures.get(1) {
+        Some(countersignature) => {
+            let header: CountersignatureHeader =
+                parse_part(&countersignature.protected, "countersignature header")?;
+            if header.alg != RS256 {
+                return Err(invalid(&format!("only {} is supported", RS256)));
+            }
+            if header.countersigns != countersigns {
+                return Err(invalid("the countersignature belongs to another signature"));
+            }
+
+            let signature = decode_part(&countersignature.signature, "countersignature")?;
+            verify_rs256(
+                header.jwk.public_key()?,
+                token.signing_input(countersignature).as_bytes(),
+                &signature,
+            )
+            .map_err(|_| invalid("the countersignature does not match"))?;
+
+            Some(header.jwk.thumbprint())
+        }
+        None => None,
+    };
+
+    match (&device_thumbprint, expected.device_thumbprint) {
+        (None, _) if expected.require_countersignature || expected.device_thumbprint.is_some() => {
+            Err(invalid("the token was not countersigned"))
+        }
+        (Some(actual), Some(expected)) if actual != expected => {
+            Err(invalid("the token was countersigned by another device"))
+        }
+        _ => Ok(Verified {
+            claims,
+            device_thumbprint,
+        }),
+    }
+}
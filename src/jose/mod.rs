@@ -0,0 +1,144 @@
+use crate::util::mappers::MapNapiError;
+use base64ct::{Base64UrlUnpadded, Encoding};
+use rsa::pkcs1::DecodeRsaPublicKey;
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+use rsa::signature::Verifier;
+use rsa::traits::PublicKeyParts;
+use rsa::{BigUint, RsaPublicKey};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub(crate) mod client_assertion;
+pub(crate) mod dpop;
+pub(crate) mod entitlement;
+
+/// The JWS algorithm of passport keys, RSASSA-PKCS1-v1_5 using SHA-256.
+pub const RS256: &str = "RS256";
+
+fn invalid(reason: &str) -> napi::Error {
+    napi::Error::from_reason(format!("The token is invalid: {}", reason))
+}
+
+/// Encode data using unpadded base64url, as used throughout JOSE.
+pub fn base64url(data: &[u8]) -> String {
+    Base64UrlUnpadded::encode_string(data)
+}
+
+/// The public part of an RSA key as JSON Web Key.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RsaJwk {
+    pub kty: String,
+    pub n: String,
+    pub e: String,
+    /// The private exponent, which must never be part of a public key.
+    #[serde(default, skip_serializing)]
+    d: Option<IgnoredAny>,
+}
+
+impl RsaJwk {
+    pub fn from_pkcs1(pkcs1: &[u8]) -> napi::Result<Self> {
+        let key = RsaPublicKey::from_pkcs1_der(pkcs1).map_napi_error()?;
+        Ok(Self::from_public_key(&key))
+    }
+
+    pub fn from_public_key(key: &RsaPublicKey) -> Self {
+        Self {
+            kty: "RSA".to_string(),
+            n: base64url(&key.n().to_bytes_be()),
+            e: base64url(&key.e().to_bytes_be()),
+            d: None,
+        }
+    }
+
+    /// The JWK thumbprint defined in RFC 7638, the base64url encoded
+    /// SHA-256 hash of the required members in lexicographic order.
+    pub fn thumbprint(&self) -> String {
+        let canonical = format!(r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#, self.e, self.n);
+        base64url(&Sha256::digest(canonical))
+    }
+
+    pub fn public_key(&self) -> napi::Result<RsaPublicKey> {
+        let decode = |value: &str| {
+            Base64UrlUnpadded::decode_vec(value)
+                .map(|bytes| BigUint::from_bytes_be(&bytes))
+                .map_err(|_| invalid("the key is malformed"))
+        };
+
+        if self.kty != "RSA" {
+            return Err(invalid("only RSA keys are supported"));
+        }
+        if self.d.is_some() {
+            return Err(invalid("the key contains a private key"));
+        }
+
+        RsaPublicKey::new(decode(&self.n)?, decode(&self.e)?)
+            .map_err(|_| invalid("the key is not a valid RSA key"))
+    }
+}
+
+/// The part of a compact JWS the account key signs, the
+/// base64url encoded JSON header and claims separated by a dot.
+pub fn signing_input<H: Serialize, C: Serialize>(header: &H, claims: &C) -> napi::Result<String> {
+    Ok(format!(
+        "{}.{}",
+        base64url(&serde_json::to_vec(header).map_napi_error()?),
+        base64url(&serde_json::to_vec(claims).map_napi_error()?)
+    ))
+}
+
+/// Append the signature of [`signing_input`] to form a compact JWS.
+pub fn compact(signing_input: &str, signature: &[u8]) -> String {
+    format!("{}.{}", signing_input, base64url(signature))
+}
+
+fn decode_part(part: &str, name: &str) -> napi::Result<Vec<u8>> {
+    Base64UrlUnpadded::decode_vec(part)
+        .map_err(|_| invalid(&format!("the {} is not base64url encoded", name)))
+}
+
+fn parse_part<T: DeserializeOwned>(part: &str, name: &str) -> napi::Result<T> {
+    serde_json::from_slice(&decode_part(part, name)?)
+        .map_err(|e| invalid(&format!("the {} is malformed: {}", name, e)))
+}
+
+/// A decoded compact JWS whose signature has not been verified yet.
+pub struct DecodedToken<H, C> {
+    pub header: H,
+    pub claims: C,
+    signing_input: String,
+    signature: Vec<u8>,
+}
+
+impl<H: DeserializeOwned, C: DeserializeOwned> DecodedToken<H, C> {
+    pub fn decode(token: &str) -> napi::Result<Self> {
+        let parts = token.trim().split('.').collect::<Vec<_>>();
+        let [header, claims, signature] = parts.as_slice() else {
+            return Err(invalid("expected a compact JWS with three parts"));
+        };
+
+        Ok(Self {
+            header: parse_part(header, "header")?,
+            claims: parse_part(claims, "claims")?,
+            signing_input: format!("{}.{}", header, claims),
+            signature: decode_part(signature, "signature")?,
+        })
+    }
+}
+
+impl<H, C> DecodedToken<H, C> {
+    /// Verify the RS256 signature of the token.
+    pub fn verify(&self, key: RsaPublicKey) -> napi::Result<()> {
+        verify_rs256(key, self.signing_input.as_bytes(), &self.signature)
+    }
+}
+
+/// Verify an RS256 signature over a JWS signing input.
+pub fn verify_rs256(key: RsaPublicKey, signing_input: &[u8], signature: &[u8]) -> napi::Result<()> {
+    let signature =
+        Signature::try_from(signature).map_err(|_| invalid("the signature is malformed"))?;
+
+    VerifyingKey::<Sha256>::new(key)
+        .verify(signing_input, &signature)
+        .map_err(|_| invalid("the signature does not match"))
+}
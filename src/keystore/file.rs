@@ -0,0 +1,378 @@
+use crate::keystore::KeyStore;
+use crate::util::audit::hash_hex;
+use crate::util::errors::OperationError;
+use crate::util::file_lock;
+use crate::util::mappers::MapNapiError;
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use napi::bindgen_prelude::{Either, Promise};
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction};
+use rand::RngCore;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use zeroize::Zeroizing;
+
+/// The magic bytes every key file starts with.
+const MAGIC: &[u8; 4] = b"PPKF";
+/// The version of the key file format written by this store.
+const VERSION: u8 = 1;
+/// The length of the random salt passed to Argon2.
+const SALT_LENGTH: usize = 16;
+/// The length of an AES-GCM nonce.
+const NONCE_LENGTH: usize = 12;
+/// The length of the header: the magic, the version,
+/// the three Argon2 parameters, the salt and the nonce.
+const HEADER_LENGTH: usize = MAGIC.len() + 1 + 3 * 4 + SALT_LENGTH + NONCE_LENGTH;
+/// The maximum Argon2 memory cost accepted from a key file, in KiB.
+/// The parameters are read before the file is authenticated, so
+/// a modified file must not be able to exhaust the memory.
+const MAX_MEMORY_COST: u32 = 1024 * 1024;
+/// The maximum Argon2 time cost accepted from a key file.
+const MAX_TIME_COST: u32 = 64;
+/// The maximum Argon2 parallelism accepted from a key file.
+const MAX_PARALLELISM: u32 = 16;
+
+/// The callback returning the passphrase the key files are encrypted with.
+pub type PassphraseCallback = ThreadsafeFunction<(), ErrorStrategy::Fatal>;
+
+/// The callback set using [`set_passphrase_callback`].
+static PASSPHRASE: RwLock<Option<Arc<PassphraseCallback>>> = RwLock::new(None);
+
+/// Set the callback returning the passphrase of the key files.
+pub fn set_passphrase_callback(callback: Option<PassphraseCallback>) {
+    *PASSPHRASE.write().unwrap() = callback.map(Arc::new);
+}
+
+/// Ask the callback for the passphrase of the key files.
+async fn passphrase() -> napi::Result<Zeroizing<String>> {
+    let callback = PASSPHRASE.read().unwrap().clone().ok_or_else(|| {
+        napi::Error::from_reason(
+            "The key store requires a passphrase, set a callback using Passport.setKeyStorePassphrase",
+        )
+    })?;
+
+    let passphrase = match callback
+        .call_async::<Either<String, Promise<String>>>(())
+        .await?
+    {
+        Either::A(passphrase) => passphrase,
+        Either::B(promise) => promise.await?,
+    };
+
+    Ok(Zeroizing::new(passphrase))
+}
+
+fn invalid_file(reason: &str) -> napi::Error {
+    napi::Error::from_reason(format!("The key file is invalid: {}", reason))
+}
+
+/// A key file. The key is encrypted using AES-256-GCM with a key
+/// derived from the passphrase using Argon2id. The header and the
+/// account ID are authenticated, so modified key files are rejected
+/// and key files cannot be swapped between accounts.
+///
+/// All integers are little endian:
+///
+/// | Field       | Length |
+/// |-------------|--------|
+/// | `PPKF`      | 4      |
+/// | version     | 1      |
+/// | memory cost | 4      |
+/// | time cost   | 4      |
+/// | parallelism | 4      |
+/// | salt        | 16     |
+/// | nonce       | 12     |
+/// | ciphertext  | rest   |
+struct KeyFile {
+    params: Params,
+    salt: [u8; SALT_LENGTH],
+    nonce: [u8; NONCE_LENGTH],
+    ciphertext: Vec<u8>,
+}
+
+impl KeyFile {
+    fn header(&self) -> Vec<u8> {
+        let mut header = Vec::with_capacity(HEADER_LENGTH);
+        header.extend_from_slice(MAGIC);
+        header.push(VERSION);
+        for value in [
+            self.params.m_cost(),
+            self.params.t_cost(),
+            self.params.p_cost(),
+        ] {
+            header.extend_from_slice(&value.to_le_bytes());
+        }
+
+        header.extend_from_slice(&self.salt);
+        header.extend_from_slice(&self.nonce);
+        header
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut data = self.header();
+        data.extend_from_slice(&self.ciphertext);
+        data
+    }
+
+    fn decode(data: &[u8]) -> napi::Result<Self> {
+        if data.len() < MAGIC.len() + 1 || &data[..MAGIC.len()] != MAGIC {
+            return Err(invalid_file("not a key file"));
+        }
+
+        let version = data[MAGIC.len()];
+        if version != VERSION {
+            return Err(invalid_file(&format!("unsupported version {}", version)));
+        }
+
+        if data.len() < HEADER_LENGTH {
+            return Err(invalid_file("the header is truncated"));
+        }
+
+        let (header, ciphertext) = data.split_at(HEADER_LENGTH);
+        let field = |index: usize| {
+            let start = MAGIC.len() + 1 + index * 4;
+            u32::from_le_bytes(header[start..start + 4].try_into().unwrap())
+        };
+
+        let (m_cost, t_cost, p_cost) = (field(0), field(1), field(2));
+        if m_cost > MAX_MEMORY_COST || t_cost > MAX_TIME_COST || p_cost > MAX_PARALLELISM {
+            return Err(invalid_file("the key derivation parameters are too large"));
+        }
+
+        let salt_start = MAGIC.len() + 1 + 3 * 4;
+        let nonce_start = salt_start + SALT_LENGTH;
+        Ok(Self {
+            params: Params::new(m_cost, t_cost, p_cost, Some(32))
+                .map_err(|e| invalid_file(&e.to_string()))?,
+            salt: header[salt_start..nonce_start].try_into().unwrap(),
+            nonce: header[nonce_start..].try_into().unwrap(),
+            ciphertext: ciphertext.to_vec(),
+        })
+    }
+
+    /// The data authenticated together with the ciphertext.
+    fn associated_data(&self, account_id: &str) -> Vec<u8> {
+        let mut aad = self.header();
+        aad.extend_from_slice(account_id.as_bytes());
+        aad
+    }
+
+    /// Derive the AES key from the passphrase. This is slow
+    /// on purpose, so it runs on the blocking thread pool.
+    async fn cipher(&self) -> napi::Result<Aes256Gcm> {
+        let passphrase = passphrase().await?;
+        let (params, salt) = (self.params.clone(), self.salt);
+
+        tokio::task::spawn_blocking(move || {
+            let mut key = Zeroizing::new([0u8; 32]);
+            Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                .hash_password_into(passphrase.as_bytes(), &salt, key.as_mut())
+                .map_err(|e| {
+                    napi::Error::from_reason(format!("Failed to derive the key: {}", e))
+                })?;
+
+            Aes256Gcm::new_from_slice(key.as_ref()).map_napi_error()
+        })
+        .await
+        .map_napi_error()?
+    }
+}
+
+/// A key store keeping every key in a file, encrypted with a
+/// passphrase. The files are written atomically, and access to
+/// the directory is serialized between processes using a lock file.
+pub struct EncryptedFileStore {
+    dir: PathBuf,
+}
+
+impl EncryptedFileStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn key_path(dir: &Path, account_id: &str) -> PathBuf {
+        dir.join(format!("{}.key", hash_hex(account_id.as_bytes())))
+    }
+
+    /// Lock the directory, blocking until the lock is acquired.
+    /// The lock is released once the returned file is dropped.
+    fn lock(dir: &Path, exclusive: bool) -> napi::Result<File> {
+        fs::create_dir_all(dir).map_napi_error()?;
+        file_lock::lock(&dir.join(".lock"), exclusive)
+    }
+
+    /// Run a file operation holding the lock
+    /// of the directory on the blocking thread pool.
+    async fn locked<T, F>(&self, exclusive: bool, f: F) -> Result<T, OperationError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Path) -> Result<T, OperationError> + Send + 'static,
+    {
+        let dir = self.dir.clone();
+        tokio::task::spawn_blocking(move || {
+            let _lock = Self::lock(&dir, exclusive)?;
+            f(&dir)
+        })
+        .await
+        .map_napi_error()?
+    }
+
+    async fn read(&self, account_id: &str) -> Result<KeyFile, OperationError> {
+        let account_id = account_id.to_string();
+        let data = self
+            .locked(false, move |dir| {
+                match fs::read(Self::key_path(dir, &account_id)) {
+                    Err(e) if e.kind() == ErrorKind::NotFound => Err(OperationError::not_found()),
+                    result => Ok(result.map_napi_error()?),
+                }
+            })
+            .await?;
+
+        Ok(KeyFile::decode(&data)?)
+    }
+
+    /// Encrypt a key for the given account.
+    async fn seal(account_id: &str, key: &[u8]) -> Result<KeyFile, OperationError> {
+        let mut file = KeyFile {
+            params: Params::default(),
+            salt: [0u8; SALT_LENGTH],
+            nonce: [0u8; NONCE_LENGTH],
+            ciphertext: vec![],
+        };
+        rand::thread_rng().fill_bytes(&mut file.salt);
+        rand::thread_rng().fill_bytes(&mut file.nonce);
+
+        let aad = file.associated_data(account_id);
+        let payload = Payload {
+            msg: key,
+            aad: &aad,
+        };
+        file.ciphertext = file
+            .cipher()
+            .await?
+            .encrypt(Nonce::from_slice(&file.nonce), payload)
+            .map_err(|_| napi::Error::from_reason("Failed to encrypt the key"))?;
+
+        Ok(file)
+    }
+
+    /// Write a key file. Unless `replace` is set, an existing key
+    /// file is kept and an `AlreadyExists` error is returned, which
+    /// is checked holding the lock, so concurrent writers can't race.
+    async fn write(
+        &self,
+        account_id: &str,
+        file: &KeyFile,
+        replace: bool,
+    ) -> Result<(), OperationError> {
+        let account_id = account_id.to_string();
+        let data = file.encode();
+
+        self.locked(true, move |dir| {
+            let path = Self::key_path(dir, &account_id);
+            if !replace && path.try_exists().map_napi_error()? {
+                return Err(OperationError::already_exists());
+            }
+
+            let tmp = path.with_extension("tmp");
+
+            let mut options = OpenOptions::new();
+            options.create(true).write(true).truncate(true);
+            #[cfg(unix)]
+            options.mode(0o600);
+
+            let mut out = options.open(&tmp).map_napi_error()?;
+            out.write_all(&data)
+                .and_then(|_| out.sync_all())
+                .map_napi_error()?;
+
+            Ok(fs::rename(tmp, path).map_napi_error()?)
+        })
+        .await
+    }
+}
+
+impl KeyStore for EncryptedFileStore {
+    fn contains<'a>(&'a self, account_id: &'a str) -> BoxFuture<'a, napi::Result<bool>> {
+        async move {
+            let path = Self::key_path(&self.dir, account_id);
+            path.try_exists().map_napi_error()
+        }
+        .boxed()
+    }
+
+    fn load<'a>(
+        &'a self,
+        account_id: &'a str,
+    ) -> BoxFuture<'a, Result<Zeroizing<Vec<u8>>, OperationError>> {
+        async move {
+            let file = self.read(account_id).await?;
+            let aad = file.associated_data(account_id);
+            let payload = Payload {
+                msg: &file.ciphertext,
+                aad: &aad,
+            };
+
+            let key = file
+                .cipher()
+                .await?
+                .decrypt(Nonce::from_slice(&file.nonce), payload)
+                .map_err(|_| {
+                    napi::Error::from_reason(
+                        "Failed to decrypt the key, the passphrase is wrong or the key file was modified",
+                    )
+                })?;
+
+            Ok(Zeroizing::new(key))
+        }
+        .boxed()
+    }
+
+    fn store<'a>(
+        &'a self,
+        account_id: &'a str,
+        key: &'a [u8],
+    ) -> BoxFuture<'a, Result<(), OperationError>> {
+        async move {
+            let file = Self::seal(account_id, key).await?;
+            self.write(account_id, &file, true).await
+        }
+        .boxed()
+    }
+
+    fn store_new<'a>(
+        &'a self,
+        account_id: &'a str,
+        key: &'a [u8],
+    ) -> BoxFuture<'a, Result<(), OperationError>> {
+        async move {
+            // Fail before asking for the passphrase, the
+            // check is repeated once the file is locked
+            if self.contains(account_id).await? {
+                return Err(OperationError::already_exists());
+            }
+
+            let file = Self::seal(account_id, key).await?;
+            self.write(account_id, &file, false).await
+        }
+        .boxed()
+    }
+
+    fn delete<'a>(&'a self, account_id: &'a str) -> BoxFuture<'a, Result<(), OperationError>> {
+        let account_id = account_id.to_string();
+        self.locked(true, move |dir| {
+            match fs::remove_file(Self::key_path(dir, &account_id)) {
+                Err(e) if e.kind() == ErrorKind::NotFound => Err(OperationError::not_found()),
+                result => Ok(result.map_napi_error()?),
+            }
+        })
+        .boxed()
+    }
+}
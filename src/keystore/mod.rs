@@ -0,0 +1,123 @@
+use crate::node::key_store_options::KeyStoreOptions;
+use crate::util::errors::OperationError;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use zeroize::Zeroizing;
+
+pub(crate) mod file;
+#[cfg(target_os = "linux")]
+pub(crate) mod secret_service;
+
+/// The application name used if no other name was configured.
+const DEFAULT_APP_NAME: &str = "passport-desktop";
+
+/// The key store configured using [`configure`].
+static CONFIG: RwLock<Option<KeyStoreOptions>> = RwLock::new(None);
+
+/// A store for the private keys generated by this crate.
+/// The keys are stored as PKCS#8 documents, which never
+/// leave the store unencrypted.
+pub trait KeyStore: Send + Sync {
+    /// Whether a key is stored for the given account.
+    fn contains<'a>(&'a self, account_id: &'a str) -> BoxFuture<'a, napi::Result<bool>>;
+
+    /// Load the key of the given account.
+    fn load<'a>(
+        &'a self,
+        account_id: &'a str,
+    ) -> BoxFuture<'a, Result<Zeroizing<Vec<u8>>, OperationError>>;
+
+    /// Store the key of the given account, replacing any existing key.
+    fn store<'a>(
+        &'a self,
+        account_id: &'a str,
+        key: &'a [u8],
+    ) -> BoxFuture<'a, Result<(), OperationError>>;
+
+    /// Store the key of an account which has no key yet. If a key
+    /// is stored for the account, an `AlreadyExists` error is returned.
+    /// Stores which can't check and store atomically only check first.
+    fn store_new<'a>(
+        &'a self,
+        account_id: &'a str,
+        key: &'a [u8],
+    ) -> BoxFuture<'a, Result<(), OperationError>> {
+        async move {
+            if self.contains(account_id).await? {
+                return Err(OperationError::already_exists());
+            }
+
+            self.store(account_id, key).await
+        }
+        .boxed()
+    }
+
+    /// Delete the key of the given account.
+    fn delete<'a>(&'a self, account_id: &'a str) -> BoxFuture<'a, Result<(), OperationError>>;
+}
+
+/// Configure the application name and directory used by the key stores.
+pub fn configure(options: KeyStoreOptions) {
+    *CONFIG.write().unwrap() = Some(options);
+}
+
+/// The name of the application the keys belong to.
+pub fn app_name() -> String {
+    CONFIG
+        .read()
+        .unwrap()
+        .as_ref()
+        .and_then(|options| options.app_name.clone())
+        .unwrap_or_else(|| DEFAULT_APP_NAME.to_string())
+}
+
+/// The directory the encrypted key files are stored in.
+pub fn key_dir() -> PathBuf {
+    if let Some(dir) = CONFIG
+        .read()
+        .unwrap()
+        .as_ref()
+        .and_then(|options| options.directory.clone())
+    {
+        return dir.into();
+    }
+
+    #[cfg(windows)]
+    let data_dir = std::env::var_os("LOCALAPPDATA").map(PathBuf::from);
+    #[cfg(not(windows))]
+    let data_dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")));
+
+    data_dir
+        .unwrap_or_else(std::env::temp_dir)
+        .join(app_name())
+        .join("keys")
+}
+
+/// Whether the keys should be stored in the Secret Service.
+#[cfg(target_os = "linux")]
+fn use_secret_service() -> bool {
+    CONFIG
+        .read()
+        .unwrap()
+        .as_ref()
+        .and_then(|options| options.use_secret_service)
+        .unwrap_or(true)
+}
+
+/// Open the key store of this machine. On Linux, this is the
+/// Secret Service of the user's session, if it is running and
+/// was not disabled. Otherwise, the keys are stored in encrypted files.
+pub async fn open() -> napi::Result<Arc<dyn KeyStore>> {
+    #[cfg(target_os = "linux")]
+    if use_secret_service() {
+        if let Some(store) = secret_service::SecretServiceStore::connect(app_name()).await? {
+            return Ok(Arc::new(store));
+        }
+    }
+
+    Ok(Arc::new(file::EncryptedFileStore::new(key_dir())))
+}
@@ -0,0 +1,371 @@
+use crate::keystore::KeyStore;
+use crate::util::errors::OperationError;
+use crate::util::mappers::MapNapiError;
+use futures::future::BoxFuture;
+use futures::{FutureExt, StreamExt};
+use std::collections::HashMap;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+use zbus::{dbus_proxy, Connection};
+use zeroize::Zeroizing;
+
+/// The content type of the stored keys.
+const CONTENT_TYPE: &str = "application/pkcs8";
+
+/// A secret as transferred by the Secret Service: the session,
+/// the algorithm parameters, the value and its content type.
+type Secret = (OwnedObjectPath, Vec<u8>, Vec<u8>, String);
+
+#[dbus_proxy(
+    interface = "org.freedesktop.Secret.Service",
+    default_service = "org.freedesktop.secrets",
+    default_path = "/org/freedesktop/secrets"
+)]
+trait Service {
+    fn open_session(
+        &self,
+        algorithm: &str,
+        input: &Value<'_>,
+    ) -> zbus::Result<(OwnedValue, OwnedObjectPath)>;
+
+    fn create_collection(
+        &self,
+        properties: HashMap<&str, Value<'_>>,
+        alias: &str,
+    ) -> zbus::Result<(OwnedObjectPath, OwnedObjectPath)>;
+
+    fn unlock(
+        &self,
+        objects: &[ObjectPath<'_>],
+    ) -> zbus::Result<(Vec<OwnedObjectPath>, OwnedObjectPath)>;
+
+    #[dbus_proxy(property)]
+    fn collections(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+}
+
+#[dbus_proxy(
+    interface = "org.freedesktop.Secret.Collection",
+    default_service = "org.freedesktop.secrets"
+)]
+trait Collection {
+    fn create_item(
+        &self,
+        properties: HashMap<&str, Value<'_>>,
+        secret: &(ObjectPath<'_>, &[u8], &[u8], &str),
+        replace: bool,
+    ) -> zbus::Result<(OwnedObjectPath, OwnedObjectPath)>;
+
+    fn search_items(&self, attributes: HashMap<&str, &str>) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    #[dbus_proxy(property)]
+    fn label(&self) -> zbus::Result<String>;
+
+    #[dbus_proxy(property)]
+    fn locked(&self) -> zbus::Result<bool>;
+}
+
+#[dbus_proxy(
+    interface = "org.freedesktop.Secret.Item",
+    default_service = "org.freedesktop.secrets"
+)]
+trait Item {
+    fn get_secret(&self, session: &ObjectPath<'_>) -> zbus::Result<Secret>;
+
+    fn delete(&self) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[dbus_proxy(
+    interface = "org.freedesktop.Secret.Prompt",
+    default_service = "org.freedesktop.secrets"
+)]
+trait Prompt {
+    fn prompt(&self, window_id: &str) -> zbus::Result<()>;
+
+    #[dbus_proxy(signal)]
+    fn completed(&self, dismissed: bool, result: Value<'_>) -> zbus::Result<()>;
+}
+
+/// A key store keeping the keys in the Secret Service of the
+/// user's session, like GNOME Keyring or KWallet. The keys of
+/// an application are stored in a collection named after the
+/// application, with an item for every account. Only this
+/// collection is searched, so items of other collections with
+/// the same attributes are ignored.
+///
+/// The Secret Service can't create an item only if it does not
+/// exist, so `store_new` checks first and may race with another
+/// process creating the same account.
+pub struct SecretServiceStore {
+    connection: Connection,
+    service: ServiceProxy<'static>,
+    /// The session the secrets are transferred in. It is closed
+    /// by the Secret Service once the connection is closed.
+    session: OwnedObjectPath,
+    app_name: String,
+}
+
+impl SecretServiceStore {
+    /// Connect to the Secret Service of the user's session.
+    /// Returns `None` if there is no session bus or no
+    /// Secret Service is running.
+    pub async fn connect(app_name: String) -> napi::Result<Option<Self>> {
+        let connection = match Connection::session().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                tracing::debug!(error = %e, "Failed to connect to the session bus");
+                return Ok(None);
+            }
+        };
+
+        let service = ServiceProxy::new(&connection).await.map_napi_error()?;
+
+        // The secrets are not encrypted again, as they never leave the session bus
+        let session = match service.open_session("plain", &Value::from("")).await {
+            Ok((_, session)) => session,
+            Err(zbus::Error::MethodError(name, _, _))
+                if name == "org.freedesktop.DBus.Error.ServiceUnknown"
+                    || name == "org.freedesktop.DBus.Error.NameHasNoOwner" =>
+            {
+                tracing::debug!("No Secret Service is running");
+                return Ok(None);
+            }
+            Err(e) => return Err(e).map_napi_error(),
+        };
+
+        Ok(Some(Self {
+            connection,
+            service,
+            session,
+            app_name,
+        }))
+    }
+
+    fn attributes<'a>(&'a self, account_id: &'a str) -> HashMap<&'a str, &'a str> {
+        HashMap::from([
+            ("application", self.app_name.as_str()),
+            ("account-id", account_id),
+        ])
+    }
+
+    /// Find the items of an account in the collection
+    /// of the application, unlocking them if required.
+    async fn find(&self, account_id: &str) -> Result<Vec<OwnedObjectPath>, OperationError> {
+        let Some(collection) = self.find_collection().await? else {
+            return Ok(Vec::new());
+        };
+
+        let items = collection
+            .search_items(self.attributes(account_id))
+            .await
+            .map_napi_error()?;
+        if items.is_empty() || !collection.locked().await.map_napi_error()? {
+            return Ok(items);
+        }
+
+        self.unlock(&items).await
+    }
+
+    /// Unlock objects, asking the user to unlock them if required.
+    async fn unlock(
+        &self,
+        objects: &[OwnedObjectPath],
+    ) -> Result<Vec<OwnedObjectPath>, OperationError> {
+        let objects: Vec<ObjectPath<'_>> = objects.iter().map(|o| o.as_ref()).collect();
+        let (mut unlocked, prompt) = self.service.unlock(&objects).await.map_napi_error()?;
+
+        if let Some(result) = self.prompt(prompt).await? {
+            unlocked.extend(Vec::<OwnedObjectPath>::try_from(result).map_napi_error()?);
+        }
+
+        Ok(unlocked)
+    }
+
+    /// Show a prompt of the Secret Service and wait until the user
+    /// completed it. Returns the result of the prompt, or `None`
+    /// if no prompt was required.
+    async fn prompt(&self, prompt: OwnedObjectPath) -> Result<Option<OwnedValue>, OperationError> {
+        if prompt.as_str() == "/" {
+            return Ok(None);
+        }
+
+        let prompt = PromptProxy::builder(&self.connection)
+            .path(prompt)
+            .map_napi_error()?
+            .build()
+            .await
+            .map_napi_error()?;
+
+        // Subscribe before prompting, so the result cannot be missed
+        let mut completed = prompt.receive_completed().await.map_napi_error()?;
+        prompt.prompt("").await.map_napi_error()?;
+
+        let signal = completed.next().await.ok_or_else(|| {
+            napi::Error::from_reason("The connection to the Secret Service was closed")
+        })?;
+        let args = signal.args().map_napi_error()?;
+        if args.dismissed {
+            return Err(OperationError::user_canceled());
+        }
+
+        Ok(Some(args.result.to_owned()))
+    }
+
+    /// Get the collection of the application, if it exists.
+    async fn find_collection(&self) -> Result<Option<CollectionProxy<'static>>, OperationError> {
+        for path in self.service.collections().await.map_napi_error()? {
+            let collection = self.collection_proxy(path).await?;
+            if collection.label().await.map_napi_error()? == self.app_name {
+                return Ok(Some(collection));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Get the collection of the application, creating it if it does not exist.
+    async fn collection(&self) -> Result<CollectionProxy<'static>, OperationError> {
+        if let Some(collection) = self.find_collection().await? {
+            return Ok(collection);
+        }
+
+        tracing::debug!(
+            label = self.app_name,
+            "Creating a Secret Service collection"
+        );
+        let properties = HashMap::from([(
+            "org.freedesktop.Secret.Collection.Label",
+            Value::from(self.app_name.as_str()),
+        )]);
+        let (path, prompt) = self
+            .service
+            .create_collection(properties, "")
+            .await
+            .map_napi_error()?;
+
+        let path = match self.prompt(prompt).await? {
+            Some(result) => OwnedObjectPath::try_from(result).map_napi_error()?,
+            None => path,
+        };
+
+        self.collection_proxy(path).await
+    }
+
+    async fn collection_proxy(
+        &self,
+        path: OwnedObjectPath,
+    ) -> Result<CollectionProxy<'static>, OperationError> {
+        Ok(CollectionProxy::builder(&self.connection)
+            .path(path)
+            .map_napi_error()?
+            .build()
+            .await
+            .map_napi_error()?)
+    }
+
+    async fn item_proxy(
+        &self,
+        path: OwnedObjectPath,
+    ) -> Result<ItemProxy<'static>, OperationError> {
+        Ok(ItemProxy::builder(&self.connection)
+            .path(path)
+            .map_napi_error()?
+            .build()
+            .await
+            .map_napi_error()?)
+    }
+}
+
+impl KeyStore for SecretServiceStore {
+    fn contains<'a>(&'a self, account_id: &'a str) -> BoxFuture<'a, napi::Result<bool>> {
+        async move {
+            let Some(collection) = self.find_collection().await? else {
+                return Ok(false);
+            };
+
+            let items = collection
+                .search_items(self.attributes(account_id))
+                .await
+                .map_napi_error()?;
+
+            Ok(!items.is_empty())
+        }
+        .boxed()
+    }
+
+    fn load<'a>(
+        &'a self,
+        account_id: &'a str,
+    ) -> BoxFuture<'a, Result<Zeroizing<Vec<u8>>, OperationError>> {
+        async move {
+            let path = self
+                .find(account_id)
+                .await?
+                .into_iter()
+                .next()
+                .ok_or_else(OperationError::not_found)?;
+
+            let (_, _, value, _) = self
+                .item_proxy(path)
+                .await?
+                .get_secret(&self.session.as_ref())
+                .await
+                .map_napi_error()?;
+
+            Ok(Zeroizing::new(value))
+        }
+        .boxed()
+    }
+
+    fn store<'a>(
+        &'a self,
+        account_id: &'a str,
+        key: &'a [u8],
+    ) -> BoxFuture<'a, Result<(), OperationError>> {
+        async move {
+            let collection = self.collection().await?;
+            if collection.locked().await.map_napi_error()? {
+                self.unlock(&[collection.path().to_owned().into()]).await?;
+            }
+
+            let label = format!("{} key of {}", self.app_name, account_id);
+            let properties = HashMap::from([
+                ("org.freedesktop.Secret.Item.Label", Value::from(label)),
+                (
+                    "org.freedesktop.Secret.Item.Attributes",
+                    Value::from(self.attributes(account_id)),
+                ),
+            ]);
+
+            let secret = (self.session.as_ref(), &[][..], key, CONTENT_TYPE);
+            let (_, prompt) = collection
+                .create_item(properties, &secret, true)
+                .await
+                .map_napi_error()?;
+
+            self.prompt(prompt).await?;
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn delete<'a>(&'a self, account_id: &'a str) -> BoxFuture<'a, Result<(), OperationError>> {
+        async move {
+            let items = self.find(account_id).await?;
+            if items.is_empty() {
+                return Err(OperationError::not_found());
+            }
+
+            for path in items {
+                let prompt = self
+                    .item_proxy(path)
+                    .await?
+                    .delete()
+                    .await
+                    .map_napi_error()?;
+                self.prompt(prompt).await?;
+            }
+
+            Ok(())
+        }
+        .boxed()
+    }
+}
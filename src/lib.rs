@@ -1,8 +1,17 @@
 #![deny(clippy::all)]
 
+pub(crate) mod backend;
+pub(crate) mod consent;
+pub(crate) mod http;
+pub(crate) mod jose;
+pub(crate) mod keystore;
 mod node;
+pub(crate) mod pgp;
+pub(crate) mod ssh;
 pub(crate) mod util;
+#[cfg(windows)]
 pub(crate) mod win;
+pub(crate) mod x509;
 
 #[macro_use]
 extern crate napi_derive;
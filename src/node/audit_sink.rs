@@ -0,0 +1,129 @@
+use crate::util::audit::{verify_entries, verify_file, AuditLog};
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction};
+use napi::Env;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[napi(object)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// An entry in the audit log.
+pub struct AuditEntry {
+    /// The position of this entry in the log, starting at zero.
+    pub sequence: i64,
+    /// The time the operation finished, in milliseconds since the unix epoch.
+    pub timestamp: i64,
+    /// The operation that was performed, e.g. `sign` or `createAccount`.
+    pub operation: String,
+    /// The ID of the account the operation was performed on.
+    /// Not set for operations which are not bound to an account.
+    pub account_id: Option<String>,
+    /// The outcome of the operation. This is `Success`, the name of
+    /// the `KeyCredentialStatus` or {@link VerificationResult}
+    /// returned by Windows, or `Error` if the operation failed
+    /// for another reason.
+    pub outcome: String,
+    /// The error message, if the operation failed.
+    pub error: Option<String>,
+    /// The hex-encoded SHA-256 hash of the signed challenge.
+    pub challenge_hash: Option<String>,
+    /// The hex-encoded SHA-256 hash of the PKCS#1 encoded public key.
+    pub key_fingerprint: Option<String>,
+    /// The hash of the previous entry in the log.
+    pub previous_hash: String,
+    /// The hash of this entry.
+    pub hash: String,
+}
+
+#[napi(object)]
+/// The result of verifying an audit log.
+pub struct AuditVerification {
+    /// Whether the hash chain of the log is intact.
+    pub valid: bool,
+    /// The number of valid entries before the first invalid entry.
+    pub entries: u32,
+    /// The sequence number of the first invalid entry, if any.
+    pub invalid_sequence: Option<i64>,
+    /// Why the first invalid entry was rejected.
+    pub error: Option<String>,
+}
+
+#[napi]
+/// A sink receiving audit entries for every key creation,
+/// key deletion, signature and consent prompt.
+/// Every entry contains the hash of the previous entry,
+/// so modifications of the log can be detected using
+/// {@link AuditSink.verifyFile} or {@link AuditSink.verifyEntries}.
+///
+/// # Example
+/// ```ts
+/// import { AuditSink, Passport } from 'passport-desktop';
+///
+/// Passport.setAuditSink(AuditSink.file('audit.jsonl'));
+///
+/// // ...
+///
+/// const result = AuditSink.verifyFile('audit.jsonl');
+/// if (!result.valid) {
+///   console.error(`The audit log was modified: ${result.error}`);
+/// }
+/// ```
+pub struct AuditSink {
+    pub(crate) log: Arc<AuditLog>,
+}
+
+#[napi]
+impl AuditSink {
+    #[napi(factory)]
+    /// Create a sink appending entries as JSON lines to a file.
+    /// If the file already contains entries, the hash chain
+    /// is continued from the last entry in the file.
+    ///
+    /// @param path The path to the log file.
+    pub fn file(path: String) -> napi::Result<Self> {
+        Ok(Self {
+            log: Arc::new(AuditLog::file(path)?),
+        })
+    }
+
+    #[napi(factory)]
+    /// Create a sink passing every entry to a callback.
+    /// The callback is responsible for storing the entries.
+    /// The hash chain starts with a new log for every sink.
+    ///
+    /// @param callback The callback receiving the entries.
+    pub fn callback(
+        env: Env,
+        #[napi(ts_arg_type = "(entry: AuditEntry) => void")] mut callback: ThreadsafeFunction<
+            AuditEntry,
+            ErrorStrategy::Fatal,
+        >,
+    ) -> napi::Result<Self> {
+        // Don't keep the process alive just because a sink exists
+        callback.unref(&env)?;
+
+        Ok(Self {
+            log: Arc::new(AuditLog::callback(callback)),
+        })
+    }
+
+    #[napi]
+    /// Verify the hash chain of a log file written by a file sink.
+    ///
+    /// @param path The path to the log file.
+    /// @return The verification result.
+    pub fn verify_file(path: String) -> napi::Result<AuditVerification> {
+        verify_file(path)
+    }
+
+    #[napi]
+    /// Verify the hash chain of entries received by a callback sink.
+    /// The entries must be passed in the order they were received,
+    /// starting at the first entry.
+    ///
+    /// @param entries The entries to verify.
+    /// @return The verification result.
+    pub fn verify_entries(entries: Vec<AuditEntry>) -> AuditVerification {
+        verify_entries(&entries)
+    }
+}
@@ -0,0 +1,26 @@
+#[napi(object)]
+/// Options for creating a client assertion using {@link Passport.createClientAssertion}.
+pub struct ClientAssertionOptions {
+    /// The OAuth client ID, used as issuer and subject of the assertion.
+    pub client_id: String,
+    /// The audience of the assertion, usually the token endpoint URL.
+    pub audience: String,
+    /// How long the assertion is valid, in seconds. Defaults to 60 seconds.
+    pub lifetime_seconds: Option<u32>,
+    /// The key ID in the header of the assertion. Defaults to the
+    /// JWK thumbprint of the account key, as defined in RFC 7638.
+    pub key_id: Option<String>,
+}
+
+#[napi(object)]
+/// The parameters of a refresh token grant created using
+/// {@link Passport.createRefreshTokenGrant}.
+pub struct RefreshTokenGrant {
+    /// The decrypted refresh token.
+    pub refresh_token: String,
+    /// The client assertion proving possession of the account key.
+    pub client_assertion: String,
+    /// The type of the client assertion,
+    /// `urn:ietf:params:oauth:client-assertion-type:jwt-bearer`.
+    pub client_assertion_type: String,
+}
@@ -0,0 +1,50 @@
+use crate::x509::cms::Signer;
+use napi::bindgen_prelude::Buffer;
+use x509_cert::der::Encode;
+
+#[napi(object)]
+/// The result of verifying a CMS signature using {@link Passport.verifyCms}.
+pub struct CmsVerification {
+    /// Whether the signature is valid for the content.
+    pub valid: bool,
+    /// The subject of the signer's certificate, if the signature is valid.
+    pub signer: Option<String>,
+    /// The signing time recorded in the signed attributes,
+    /// as ISO 8601 string, if the signature contains one.
+    pub signing_time: Option<String>,
+    /// The DER encoded certificate of the signer, if the signature is valid.
+    pub certificate: Option<Buffer>,
+    /// The reason the signature is invalid.
+    pub error: Option<String>,
+}
+
+impl From<napi::Result<Signer>> for CmsVerification {
+    fn from(result: napi::Result<Signer>) -> Self {
+        let signer = result.and_then(|signer| {
+            let der = signer.certificate.to_der().map_err(|e| {
+                napi::Error::from_reason(format!("The certificate is invalid: {}", e))
+            })?;
+
+            Ok((signer, der))
+        });
+
+        match signer {
+            Ok((signer, der)) => Self {
+                valid: true,
+                signer: Some(signer.certificate.tbs_certificate.subject.to_string()),
+                signing_time: signer
+                    .signing_time
+                    .map(|time| time.to_date_time().to_string()),
+                certificate: Some(der.into()),
+                error: None,
+            },
+            Err(e) => Self {
+                valid: false,
+                signer: None,
+                signing_time: None,
+                certificate: None,
+                error: Some(e.reason),
+            },
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use napi::bindgen_prelude::{FromNapiValue, ToNapiValue};
+
+#[napi]
+/// The provider asking the user to verify their identity
+/// in {@link Passport.requestVerification}.
+pub enum ConsentProvider {
+    /// Windows Hello, using the `UserConsentVerifier`.
+    /// This is the default provider on Windows.
+    WindowsHello,
+    /// A fingerprint scan using `fprintd` over the system D-Bus.
+    /// This is the default provider on Linux. `fprintd` does not
+    /// show a dialog, so the user must be asked to scan their
+    /// finger by the application. Up to three scans are accepted
+    /// before {@link VerificationResult.RetriesExhausted} is returned.
+    Fprintd,
+    /// polkit, checking whether the user is authorized to perform
+    /// the action set using {@link Passport.setPolkitAction}. The
+    /// polkit authentication agent of the desktop asks the user for
+    /// their password, if required by the policy of the action.
+    /// The message is passed as the `message` detail, which can be
+    /// shown using `$(message)` in the message of the action.
+    Polkit,
+}
+
+impl ConsentProvider {
+    /// The name of the provider, for use in error messages.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::WindowsHello => "WindowsHello",
+            Self::Fprintd => "Fprintd",
+            Self::Polkit => "Polkit",
+        }
+    }
+}
+
+impl Default for ConsentProvider {
+    #[cfg(not(target_os = "linux"))]
+    fn default() -> Self {
+        Self::WindowsHello
+    }
+
+    #[cfg(target_os = "linux")]
+    fn default() -> Self {
+        Self::Fprintd
+    }
+}
@@ -0,0 +1,21 @@
+use napi::bindgen_prelude::{FromNapiValue, ToNapiValue};
+
+#[napi]
+/// The algorithm used to hash large payloads before signing them.
+pub enum DigestAlgorithm {
+    /// SHA-256. This is the default algorithm.
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    /// The name of the algorithm, as used by the `crypto` module.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Sha384 => "sha384",
+            Self::Sha512 => "sha512",
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use crate::jose::dpop::Verified;
+
+#[napi(object)]
+/// What {@link Passport.verifyDpopProof} expects a proof to contain.
+pub struct DpopVerificationOptions {
+    /// The access token sent with the proof. If set,
+    /// the proof must be bound to it using `ath`.
+    pub access_token: Option<String>,
+    /// The nonce the server provided. If set, the proof must contain it.
+    pub nonce: Option<String>,
+    /// The JWK thumbprint the access token is bound to, from
+    /// its `cnf.jkt` claim. If set, the proof must use that key.
+    pub jkt: Option<String>,
+    /// How many seconds `iat` may differ from the current time.
+    /// Defaults to 300 seconds.
+    pub max_age_seconds: Option<u32>,
+    /// The time to check `iat` against, in seconds since
+    /// the unix epoch. Defaults to the current time.
+    pub time: Option<i64>,
+}
+
+#[napi(object)]
+/// The result of verifying a proof using {@link Passport.verifyDpopProof}.
+pub struct DpopVerification {
+    /// Whether the proof is valid for the request.
+    pub valid: bool,
+    /// The JWK thumbprint of the proof's key, to bind access tokens
+    /// to it using `cnf.jkt`, if the proof is valid.
+    pub jkt: Option<String>,
+    /// The unique ID of the proof, to detect replays, if the proof is valid.
+    pub jti: Option<String>,
+    /// When the proof was issued, in seconds since the
+    /// unix epoch, if the proof is valid.
+    pub iat: Option<i64>,
+    /// The reason the proof is invalid.
+    pub error: Option<String>,
+}
+
+impl From<napi::Result<Verified>> for DpopVerification {
+    fn from(result: napi::Result<Verified>) -> Self {
+        match result {
+            Ok(verified) => Self {
+                valid: true,
+                jkt: Some(verified.thumbprint),
+                jti: Some(verified.claims.jti),
+                iat: Some(verified.claims.iat as i64),
+                error: None,
+            },
+            Err(e) => Self {
+                valid: false,
+                jkt: None,
+                jti: None,
+                iat: None,
+                error: Some(e.reason),
+            },
+        }
+    }
+}
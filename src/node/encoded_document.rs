@@ -0,0 +1,11 @@
+use napi::bindgen_prelude::Buffer;
+
+#[napi(object)]
+/// A DER encoded document, like a certificate or a
+/// certificate request, together with its PEM encoding.
+pub struct EncodedDocument {
+    /// The DER encoding of the document.
+    pub der: Buffer,
+    /// The PEM encoding of the document.
+    pub pem: String,
+}
@@ -0,0 +1,64 @@
+use crate::jose::entitlement::{invalid, Verified};
+use napi::bindgen_prelude::Buffer;
+
+#[napi(object)]
+/// What {@link Passport.verifyEntitlement} expects a token to contain.
+pub struct EntitlementVerificationOptions {
+    /// The PEM or DER encoded RSA public key of the device, as
+    /// `SubjectPublicKeyInfo` or PKCS#1 key. If set, the token must
+    /// have been countersigned by that device.
+    pub device_key: Option<Buffer>,
+    /// Whether the token must have been countersigned
+    /// by any device. Defaults to `false`.
+    pub require_countersignature: Option<bool>,
+    /// How many seconds the clocks of the server and the client may
+    /// differ when checking `exp` and `nbf`. Defaults to 60 seconds.
+    pub leeway_seconds: Option<u32>,
+    /// The time to check `exp` and `nbf` against, in seconds
+    /// since the unix epoch. Defaults to the current time.
+    pub time: Option<i64>,
+}
+
+#[napi(object)]
+/// The result of verifying a token using {@link Passport.verifyEntitlement}.
+pub struct EntitlementVerification {
+    /// Whether the server's signature and, if present,
+    /// the countersignature are valid.
+    pub valid: bool,
+    /// The JSON encoded claims of the token, if the token is valid.
+    pub claims: Option<String>,
+    /// When the token expires, in seconds since the unix epoch,
+    /// if the token is valid and has an `exp` claim.
+    pub expires_at: Option<i64>,
+    /// The JWK thumbprint of the device which countersigned
+    /// the token, if the token is valid and countersigned.
+    pub device_jkt: Option<String>,
+    /// The reason the token is invalid.
+    pub error: Option<String>,
+}
+
+impl From<napi::Result<Verified>> for EntitlementVerification {
+    fn from(result: napi::Result<Verified>) -> Self {
+        match result {
+            // The `exp` claim is passed to javascript as a signed
+            // integer, so larger timestamps can't be represented
+            Ok(verified) => match verified.expires_at().map(i64::try_from).transpose() {
+                Ok(expires_at) => Self {
+                    valid: true,
+                    expires_at,
+                    claims: serde_json::to_string(&verified.claims).ok(),
+                    device_jkt: verified.device_thumbprint,
+                    error: None,
+                },
+                Err(_) => Self::from(Err(invalid("the 'exp' claim is out of range"))),
+            },
+            Err(e) => Self {
+                valid: false,
+                claims: None,
+                expires_at: None,
+                device_jkt: None,
+                error: Some(e.reason),
+            },
+        }
+    }
+}
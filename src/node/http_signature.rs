@@ -0,0 +1,88 @@
+use crate::http::Verified;
+use std::collections::HashMap;
+
+#[napi(object)]
+/// The parts of an HTTP request covered by a signature.
+pub struct HttpRequest {
+    /// The request method, e.g. `POST`.
+    pub method: String,
+    /// The absolute target URI, e.g. `https://api.example.com/items?id=1`.
+    pub target_uri: String,
+    /// The header fields of the request. Multiple values
+    /// of a field are combined, separated by `, `.
+    pub headers: Option<HashMap<String, String>>,
+}
+
+#[napi(object)]
+/// Options for signing a request using {@link Passport.signHttpRequest}.
+pub struct HttpSignatureOptions {
+    /// The components to cover, e.g. `@method`, `@target-uri`,
+    /// `@authority`, `@path`, `@query` or lower case header names.
+    pub components: Vec<String>,
+    /// The label of the signature in the headers. Defaults to `sig1`.
+    pub label: Option<String>,
+    /// The key ID. Defaults to the hex encoded SHA-256 fingerprint
+    /// of the PKCS#1 encoded public key of the account.
+    pub key_id: Option<String>,
+    /// The creation time in seconds since the unix epoch. Defaults to now.
+    pub created: Option<u32>,
+    /// How long the signature is valid after its creation, in seconds.
+    /// The signature does not expire if this is not set.
+    pub validity_seconds: Option<u32>,
+    /// A nonce to prevent replays.
+    pub nonce: Option<String>,
+    /// The application specific tag of the signature.
+    pub tag: Option<String>,
+}
+
+#[napi(object)]
+/// The headers of a request signed using {@link Passport.signHttpRequest}.
+pub struct HttpSignature {
+    /// The value of the `Signature-Input` header.
+    pub signature_input: String,
+    /// The value of the `Signature` header.
+    pub signature: String,
+    /// The signature base signed by the account key, e.g. for debugging.
+    pub signature_base: String,
+}
+
+#[napi(object)]
+/// The result of verifying a signature using {@link Passport.verifyHttpSignature}.
+pub struct HttpSignatureVerification {
+    /// Whether the signature is valid for the request.
+    pub valid: bool,
+    /// The label of the verified signature.
+    pub label: Option<String>,
+    /// The key ID of the signature, if it is valid and has one.
+    pub key_id: Option<String>,
+    /// The creation time of the signature in seconds
+    /// since the unix epoch, if it is valid and has one.
+    pub created: Option<i64>,
+    /// The components covered by the signature, if it is valid.
+    pub components: Option<Vec<String>>,
+    /// The reason the signature is invalid.
+    pub error: Option<String>,
+}
+
+impl From<napi::Result<Verified>> for HttpSignatureVerification {
+    fn from(result: napi::Result<Verified>) -> Self {
+        match result {
+            Ok(verified) => Self {
+                valid: true,
+                key_id: verified.params.key_id(),
+                created: verified.params.integer("created"),
+                components: Some(verified.params.components),
+                label: Some(verified.label),
+                error: None,
+            },
+            Err(e) => Self {
+                valid: false,
+                label: None,
+                key_id: None,
+                created: None,
+                components: None,
+                error: Some(e.reason),
+            },
+        }
+    }
+}
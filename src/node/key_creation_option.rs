@@ -1,4 +1,5 @@
 use napi::bindgen_prelude::{FromNapiValue, ToNapiValue};
+#[cfg(windows)]
 use windows::Security::Credentials::KeyCredentialCreationOption;
 
 #[napi]
@@ -10,6 +11,7 @@ pub enum KeyCreationOption {
     FailIfExists,
 }
 
+#[cfg(windows)]
 impl From<KeyCreationOption> for KeyCredentialCreationOption {
     fn from(value: KeyCreationOption) -> Self {
         match value {
@@ -0,0 +1,19 @@
+#[napi(object)]
+#[derive(Clone)]
+/// The options of the store keeping the keys of
+/// {@link PassportBackend.Local}.
+pub struct KeyStoreOptions {
+    /// The name of the application the keys belong to.
+    /// On Linux, the keys are stored in a Secret Service collection
+    /// with this name. Defaults to `passport-desktop`.
+    pub app_name: Option<String>,
+    /// The directory the encrypted key files are stored in,
+    /// if the keys are not stored by the Secret Service. Defaults to
+    /// `$XDG_DATA_HOME/<appName>/keys` on Linux and
+    /// `%LOCALAPPDATA%\<appName>\keys` on Windows.
+    pub directory: Option<String>,
+    /// Whether the keys are stored in the Secret Service on Linux,
+    /// if it is running. Set this to `false` to always use encrypted
+    /// files, e.g. on headless machines. Defaults to `true`.
+    pub use_secret_service: Option<bool>,
+}
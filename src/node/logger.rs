@@ -0,0 +1,122 @@
+use crate::util::logging::{set_log_sink, LogSink, LogTarget};
+use crate::util::mappers::MapNapiError;
+use napi::bindgen_prelude::{FromNapiValue, ToNapiValue};
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction};
+use napi::Env;
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::sync::Mutex;
+
+#[napi]
+/// The level of a log record.
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    /// Get the name of the level as it is written to log files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warn => "warn",
+            Self::Info => "info",
+            Self::Debug => "debug",
+            Self::Trace => "trace",
+        }
+    }
+}
+
+#[napi(object)]
+/// A log record emitted by the native module.
+pub struct LogRecord {
+    /// The time the record was created, in milliseconds since the unix epoch.
+    pub timestamp: i64,
+    /// The level of the record.
+    pub level: LogLevel,
+    /// The module the record was created in.
+    pub target: String,
+    /// The log message.
+    pub message: String,
+    /// The names of the spans the record was created in,
+    /// starting with the outermost span.
+    pub spans: Vec<String>,
+    /// The fields of the record and its spans, for example the
+    /// `operation`, `account_id`, `outcome` or `hresult`.
+    pub fields: HashMap<String, String>,
+    /// The duration of the span in milliseconds,
+    /// if this record marks the end of a span.
+    pub duration_ms: Option<f64>,
+}
+
+#[napi]
+/// Configure where the native module writes its log records to.
+/// Every Passport operation and every call to Windows is traced
+/// with its duration and outcome, including the HRESULT of
+/// failed calls. Logging is disabled by default.
+///
+/// # Example
+/// ```ts
+/// import { Logger, LogLevel } from 'passport-desktop';
+///
+/// Logger.logToCallback((record) => {
+///   console.log(`[${record.level}] ${record.message}`, record.fields);
+/// }, LogLevel.Debug);
+/// ```
+pub struct Logger {}
+
+#[napi]
+impl Logger {
+    #[napi]
+    /// Pass all log records to a callback.
+    /// This replaces any previously configured log target.
+    ///
+    /// @param callback The callback receiving the log records.
+    /// @param level The most verbose level to log, defaults to {@link LogLevel.Info}.
+    pub fn log_to_callback(
+        env: Env,
+        #[napi(ts_arg_type = "(record: LogRecord) => void")] mut callback: ThreadsafeFunction<
+            LogRecord,
+            ErrorStrategy::Fatal,
+        >,
+        level: Option<LogLevel>,
+    ) -> napi::Result<()> {
+        // Don't keep the process alive just because logging is enabled
+        callback.unref(&env)?;
+
+        set_log_sink(Some(LogSink {
+            target: LogTarget::Callback(callback),
+            level: level.unwrap_or(LogLevel::Info).into(),
+        }));
+        Ok(())
+    }
+
+    #[napi]
+    /// Append all log records as JSON lines to a file.
+    /// This replaces any previously configured log target.
+    ///
+    /// @param path The path to the log file.
+    /// @param level The most verbose level to log, defaults to {@link LogLevel.Info}.
+    pub fn log_to_file(path: String, level: Option<LogLevel>) -> napi::Result<()> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_napi_error()?;
+
+        set_log_sink(Some(LogSink {
+            target: LogTarget::File(Mutex::new(file)),
+            level: level.unwrap_or(LogLevel::Info).into(),
+        }));
+        Ok(())
+    }
+
+    #[napi]
+    /// Stop writing log records.
+    pub fn disable() {
+        set_log_sink(None);
+    }
+}
@@ -0,0 +1,33 @@
+use crate::util::minisign::Verified;
+
+#[napi(object)]
+/// The result of verifying a signature using {@link Passport.verifyMinisign}.
+pub struct MinisignVerification {
+    /// Whether the signature is valid for the data.
+    pub valid: bool,
+    /// The hex encoded key ID of the signature, if it is valid.
+    pub key_id: Option<String>,
+    /// The trusted comment of the signature, if it is valid.
+    pub trusted_comment: Option<String>,
+    /// The reason the signature is invalid.
+    pub error: Option<String>,
+}
+
+impl From<napi::Result<Verified>> for MinisignVerification {
+    fn from(result: napi::Result<Verified>) -> Self {
+        match result {
+            Ok(verified) => Self {
+                valid: true,
+                key_id: Some(hex::encode_upper(verified.key_id)),
+                trusted_comment: Some(verified.trusted_comment),
+                error: None,
+            },
+            Err(e) => Self {
+                valid: false,
+                key_id: None,
+                trusted_comment: None,
+                error: Some(e.reason),
+            },
+        }
+    }
+}
@@ -1,4 +1,28 @@
-mod key_creation_option;
+pub(crate) mod audit_sink;
+pub(crate) mod client_assertion;
+pub(crate) mod cms_verification;
+pub(crate) mod consent_provider;
+pub(crate) mod digest_algorithm;
+pub(crate) mod dpop;
+pub(crate) mod encoded_document;
+pub(crate) mod entitlement;
+pub(crate) mod http_signature;
+pub(crate) mod key_creation_option;
+pub(crate) mod key_store_options;
+pub(crate) mod logger;
+pub(crate) mod minisign_verification;
+pub(crate) mod openpgp_key;
+pub(crate) mod otp_algorithm;
+pub(crate) mod otp_store;
 mod passport;
-mod public_key_encoding;
-mod verification_result;
+pub(crate) mod passport_backend;
+pub(crate) mod pkcs11_options;
+pub(crate) mod protected_envelope;
+pub(crate) mod public_key_encoding;
+pub(crate) mod session;
+pub(crate) mod sign_result;
+pub(crate) mod signer;
+pub(crate) mod ssh_agent;
+pub(crate) mod vault_context;
+pub(crate) mod verification_result;
+pub(crate) mod x509_extension;
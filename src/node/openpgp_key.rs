@@ -0,0 +1,15 @@
+#[napi(object)]
+/// An OpenPGP public key created using {@link Passport.createOpenpgpKey}.
+pub struct OpenPgpKey {
+    /// The armored public key block, containing the
+    /// public key, the user ID and its self-signature.
+    pub public_key: String,
+    /// The upper case hex encoded v4 fingerprint of the key.
+    pub fingerprint: String,
+    /// The upper case hex encoded key ID.
+    pub key_id: String,
+    /// The creation time of the key in seconds since the Unix
+    /// epoch. It is part of the fingerprint, so it must be passed
+    /// to {@link Passport.signOpenpgp} when signing data.
+    pub creation_time: u32,
+}
@@ -0,0 +1,35 @@
+use napi::bindgen_prelude::{FromNapiValue, ToNapiValue};
+
+#[napi]
+/// The HMAC algorithm of a one-time password secret.
+pub enum OtpAlgorithm {
+    /// HMAC-SHA-1. This is the default algorithm and
+    /// the only one most authenticator apps support.
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl OtpAlgorithm {
+    /// The name of the algorithm, as used in `otpauth` URIs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sha1 => "SHA1",
+            Self::Sha256 => "SHA256",
+            Self::Sha512 => "SHA512",
+        }
+    }
+
+    /// Get an algorithm by its [`name`](Self::name).
+    pub fn from_name(name: &str) -> napi::Result<Self> {
+        match name {
+            "SHA1" => Ok(Self::Sha1),
+            "SHA256" => Ok(Self::Sha256),
+            "SHA512" => Ok(Self::Sha512),
+            _ => Err(napi::Error::from_reason(format!(
+                "Unsupported OTP algorithm: {}",
+                name
+            ))),
+        }
+    }
+}
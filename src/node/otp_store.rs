@@ -0,0 +1,317 @@
+use crate::backend::KeyBackend;
+use crate::node::otp_algorithm::OtpAlgorithm;
+use crate::node::vault_context::VaultContext;
+use crate::util::file_lock;
+use crate::util::mappers::MapNapiError;
+use crate::util::otp;
+use crate::util::token_file;
+use crate::util::vault::Envelope;
+use base64ct::{Base64, Encoding};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use zeroize::Zeroizing;
+
+/// The version of the store file written by [`OtpStore`].
+const STORE_VERSION: u32 = 1;
+
+#[napi(object)]
+/// A one-time password secret, as shown by the setup page of a service.
+pub struct OtpSecret {
+    /// The base32 encoded secret. Case, spaces and
+    /// padding are ignored.
+    pub secret: String,
+    /// The HMAC algorithm. Defaults to {@link OtpAlgorithm.Sha1}.
+    pub algorithm: Option<OtpAlgorithm>,
+    /// The number of digits of a code, between 6 and 8.
+    /// Defaults to 6.
+    pub digits: Option<u32>,
+    /// The time step of TOTP codes in seconds. Defaults to 30.
+    pub period: Option<u32>,
+    /// The initial counter of an HOTP secret. If set, the secret
+    /// is counter-based (RFC 4226), otherwise time-based (RFC 6238).
+    pub counter: Option<u32>,
+}
+
+/// The encrypted part of an entry.
+#[derive(Serialize, Deserialize)]
+struct StoredSecret {
+    label: String,
+    secret: String,
+    algorithm: String,
+    digits: u32,
+    period: u32,
+}
+
+/// An entry of the store file. The counter of HOTP
+/// secrets is stored in plain text, as it must be updated
+/// before the secret can be decrypted.
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    counter: Option<u64>,
+    envelope: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoreFile {
+    version: u32,
+    entries: BTreeMap<String, Entry>,
+}
+
+#[napi]
+/// A file containing one-time password secrets, each encrypted
+/// like data passed to {@link Passport.protect}. Decrypting a secret
+/// requires the user to verify their identity, so every generated
+/// code is confirmed by the user.
+///
+/// Stores are opened using {@link Passport.openOtpStore}.
+///
+/// # Example
+/// ```ts
+/// import { Passport } from 'passport-desktop';
+///
+/// const passport = new Passport('my-account-id');
+/// const store = passport.openOtpStore('otp.json');
+///
+/// await store.add('admin@example.com', { secret: 'JBSWY3DPEHPK3PXP' });
+/// const code = await store.generateCode('admin@example.com');
+/// ```
+pub struct OtpStore {
+    account_id: String,
+    backend: Arc<dyn KeyBackend>,
+    account_exists: Arc<Mutex<bool>>,
+    path: PathBuf,
+}
+
+#[napi]
+impl OtpStore {
+    #[napi]
+    /// Encrypt a secret and add it to the store, replacing
+    /// an existing secret with the same label. This requires
+    /// the user to verify their identity.
+    ///
+    /// @param label The label of the secret, e.g. the account name.
+    /// @param secret The secret and its parameters.
+    pub async fn add(&self, label: String, secret: OtpSecret) -> napi::Result<()> {
+        let algorithm = secret.algorithm.unwrap_or(OtpAlgorithm::Sha1);
+        let digits = secret.digits.unwrap_or(6);
+        let period = secret.period.unwrap_or(30);
+        if label.is_empty() {
+            return Err(napi::Error::from_reason("The label must not be empty"));
+        } else if !(6..=8).contains(&digits) {
+            return Err(napi::Error::from_reason(
+                "The number of digits must be between 6 and 8",
+            ));
+        } else if period == 0 {
+            return Err(napi::Error::from_reason("The period must not be zero"));
+        }
+
+        otp::decode_base32(&secret.secret)?;
+        let plaintext = Zeroizing::new(
+            serde_json::to_vec(&StoredSecret {
+                label: label.clone(),
+                secret: secret.secret,
+                algorithm: algorithm.name().into(),
+                digits,
+                period,
+            })
+            .map_napi_error()?,
+        );
+
+        let vault = self.vault();
+        let envelope = vault
+            .operation("otpAdd", async {
+                let fingerprint = vault.key_fingerprint().await?;
+                let signature = vault.sign_challenge().await?;
+
+                Ok(
+                    Envelope::seal(&signature, &self.account_id, fingerprint, &plaintext)?
+                        .encode()?,
+                )
+            })
+            .await?;
+
+        let _lock = self.lock(true)?;
+        let mut store = self.read()?;
+        store.entries.insert(
+            label,
+            Entry {
+                counter: secret.counter.map(u64::from),
+                envelope: Base64::encode_string(&envelope),
+            },
+        );
+
+        self.write(&store)
+    }
+
+    #[napi]
+    /// Remove a secret from the store.
+    /// If no secret with the label exists, an error is thrown.
+    ///
+    /// @param label The label of the secret.
+    pub fn remove(&self, label: String) -> napi::Result<()> {
+        let _lock = self.lock(true)?;
+        let mut store = self.read()?;
+        store
+            .entries
+            .remove(&label)
+            .ok_or_else(|| Self::not_found(&label))?;
+
+        self.write(&store)
+    }
+
+    #[napi]
+    /// Get the labels of all secrets in the store, sorted.
+    pub fn labels(&self) -> napi::Result<Vec<String>> {
+        let _lock = self.lock(false)?;
+        Ok(self.read()?.entries.into_keys().collect())
+    }
+
+    #[napi]
+    /// Decrypt a secret and generate the current code. This requires
+    /// the user to verify their identity.
+    ///
+    /// The counter of HOTP secrets is incremented before the user is
+    /// asked to verify their identity, so a canceled verification
+    /// skips a code, which servers tolerate within their look-ahead
+    /// window, but a code is never generated twice. The counter is
+    /// updated holding a lock on the file `<path>.lock`, so stores
+    /// opened by other instances or processes never read the same
+    /// counter.
+    ///
+    /// @param label The label of the secret.
+    /// @param time The time to generate a TOTP code for, in seconds
+    /// since the unix epoch. Defaults to the current time.
+    /// @return The code.
+    pub async fn generate_code(&self, label: String, time: Option<i64>) -> napi::Result<String> {
+        let (envelope, counter) = {
+            let _lock = self.lock(true)?;
+            let mut store = self.read()?;
+            let entry = store
+                .entries
+                .get_mut(&label)
+                .ok_or_else(|| Self::not_found(&label))?;
+
+            let envelope = Base64::decode_vec(&entry.envelope)
+                .map_err(|_| napi::Error::from_reason("The stored secret is not valid base64"))?;
+            let counter = entry.counter;
+            if let Some(counter) = entry.counter.as_mut() {
+                *counter += 1;
+                self.write(&store)?;
+            }
+
+            (envelope, counter)
+        };
+
+        let vault = self.vault();
+        let plaintext = vault
+            .operation("otpGenerateCode", async {
+                let envelope = Envelope::decode(&envelope)?;
+                envelope.check_recipient(&self.account_id, &vault.key_fingerprint().await?)?;
+                let signature = vault.sign_challenge().await?;
+
+                Ok(Zeroizing::new(envelope.open(&signature)?))
+            })
+            .await?;
+
+        let stored: StoredSecret = serde_json::from_slice(&plaintext)
+            .map_err(|_| napi::Error::from_reason("The stored secret is invalid"))?;
+        if stored.label != label {
+            return Err(napi::Error::from_reason(format!(
+                "The stored secret belongs to '{}', not to '{}'",
+                stored.label, label
+            )));
+        }
+
+        let algorithm = OtpAlgorithm::from_name(&stored.algorithm)?;
+        let secret = Zeroizing::new(otp::decode_base32(&stored.secret)?);
+        Ok(match counter {
+            Some(counter) => otp::hotp(&secret, counter, algorithm, stored.digits),
+            None => {
+                let time = match time {
+                    Some(time) => u64::try_from(time)
+                        .map_err(|_| napi::Error::from_reason("The time must not be negative"))?,
+                    None => SystemTime::now()
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .map_napi_error()?
+                        .as_secs(),
+                };
+
+                otp::totp(
+                    &secret,
+                    time,
+                    stored.period as u64,
+                    algorithm,
+                    stored.digits,
+                )
+            }
+        })
+    }
+}
+
+impl OtpStore {
+    pub fn new(
+        account_id: String,
+        backend: Arc<dyn KeyBackend>,
+        account_exists: Arc<Mutex<bool>>,
+        path: PathBuf,
+    ) -> Self {
+        Self {
+            account_id,
+            backend,
+            account_exists,
+            path,
+        }
+    }
+
+    /// The account key protecting the secrets.
+    fn vault(&self) -> VaultContext<'_> {
+        VaultContext {
+            account_id: &self.account_id,
+            backend: &*self.backend,
+            account_exists: &self.account_exists,
+        }
+    }
+
+    /// Lock the store file, so the entries read are not modified
+    /// by another instance or process until the lock is dropped.
+    fn lock(&self, exclusive: bool) -> napi::Result<File> {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".lock");
+
+        file_lock::lock(Path::new(&path), exclusive)
+    }
+
+    fn not_found(label: &str) -> napi::Error {
+        napi::Error::from_reason(format!("The store has no secret labeled '{}'", label))
+    }
+
+    /// Read the store file. A missing file is an empty store.
+    fn read(&self) -> napi::Result<StoreFile> {
+        if !self.path.exists() {
+            return Ok(StoreFile {
+                version: STORE_VERSION,
+                entries: BTreeMap::new(),
+            });
+        }
+
+        let store: StoreFile = serde_json::from_slice(&token_file::read(&self.path)?)
+            .map_err(|e| napi::Error::from_reason(format!("The store file is invalid: {}", e)))?;
+        if store.version != STORE_VERSION {
+            return Err(napi::Error::from_reason(format!(
+                "Unsupported store file version: {}",
+                store.version
+            )));
+        }
+
+        Ok(store)
+    }
+
+    fn write(&self, store: &StoreFile) -> napi::Result<()> {
+        token_file::write(&self.path, &serde_json::to_vec(store).map_napi_error()?)
+    }
+}
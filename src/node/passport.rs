@@ -1,17 +1,56 @@
+use crate::backend::{get_backend, key_fingerprint, pkcs11_backend, KeyBackend};
 use crate::check_account_exists;
+use crate::consent::{get_verifier, set_polkit_action_id};
+use crate::http::{self, SignatureParams};
+use crate::jose::{self, client_assertion, dpop, entitlement, RsaJwk};
+use crate::keystore::{self, file::set_passphrase_callback, file::PassphraseCallback};
+use crate::node::audit_sink::AuditSink;
+use crate::node::client_assertion::{ClientAssertionOptions, RefreshTokenGrant};
+use crate::node::cms_verification::CmsVerification;
+use crate::node::consent_provider::ConsentProvider;
+use crate::node::digest_algorithm::DigestAlgorithm;
+use crate::node::dpop::{DpopVerification, DpopVerificationOptions};
+use crate::node::encoded_document::EncodedDocument;
+use crate::node::entitlement::{EntitlementVerification, EntitlementVerificationOptions};
+use crate::node::http_signature::{
+    HttpRequest, HttpSignature, HttpSignatureOptions, HttpSignatureVerification,
+};
 use crate::node::key_creation_option::KeyCreationOption;
+use crate::node::key_store_options::KeyStoreOptions;
+use crate::node::minisign_verification::MinisignVerification;
+use crate::node::openpgp_key::OpenPgpKey;
+use crate::node::otp_store::OtpStore;
+use crate::node::passport_backend::PassportBackend;
+use crate::node::pkcs11_options::Pkcs11Options;
+use crate::node::protected_envelope::ProtectedEnvelope;
 use crate::node::public_key_encoding::PublicKeyEncoding;
+use crate::node::session::{SessionInfo, SessionOptions};
+use crate::node::sign_result::SignResult;
+use crate::node::signer::{sign_digest, DigestSignature, Signer};
+use crate::node::vault_context::VaultContext;
 use crate::node::verification_result::VerificationResult;
-use crate::util::errors::PassportError;
+use crate::node::x509_extension::X509Extension;
+use crate::pgp::{self, armor::armor, PendingSignature};
+use crate::ssh::{pkcs1_to_public_key_blob, sshsig};
+use crate::util::audit::{self, hash_hex, record_outcome, record_result, AuditEvent};
+use crate::util::digest::hash_file;
+use crate::util::errors::OperationError;
+use crate::util::logging::operation_span;
 use crate::util::mappers::MapNapiError;
-use crate::util::traits::IntoWinBuffer;
-use crate::win::passport::{create_passport_key, get_passport_account, get_passport_account_sync};
+use crate::util::minisign;
+use crate::util::session::{self, PendingSession, Session};
+use crate::util::token_file;
+use crate::util::vault::{self, Envelope};
+use crate::x509::{self, certificate, cms, csr};
 use napi::bindgen_prelude::Buffer;
-use std::sync::Mutex;
-use windows::core::HSTRING;
-use windows::Security::Credentials::UI::UserConsentVerifier;
-use windows::Security::Credentials::{KeyCredentialManager, KeyCredentialStatus};
-use windows::Security::Cryptography::Core::CryptographicPublicKeyBlobType;
+use napi::Env;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tracing::Instrument;
+use x509_cert::der::pem::LineEnding;
+use x509_cert::der::{Encode, EncodePem};
+use zeroize::Zeroizing;
 
 #[napi]
 /// The Passport module provides an interface to the Windows Hello API.
@@ -57,7 +96,9 @@ use windows::Security::Cryptography::Core::CryptographicPublicKeyBlobType;
 /// ```
 pub struct Passport {
     account_id: String,
-    account_exists: Mutex<bool>,
+    account_exists: Arc<Mutex<bool>>,
+    backend: Arc<dyn KeyBackend>,
+    session: Mutex<Option<Session>>,
 }
 
 #[napi]
@@ -75,11 +116,21 @@ impl Passport {
     /// const passport = new Passport('my-account-id');
     /// ```
     ///
+    /// To use keys generated in software instead of Windows Hello,
+    /// for example in tests, pass {@link PassportBackend.Software}.
+    ///
     /// @param accountId The id of the account in the Windows Credential Manager.
-    pub fn new(account_id: String) -> napi::Result<Self> {
+    /// @param backend The backend storing the keys, defaults to {@link PassportBackend.WindowsHello}
+    /// on Windows and {@link PassportBackend.Tpm} on Linux.
+    pub fn new(account_id: String, backend: Option<PassportBackend>) -> napi::Result<Self> {
         Ok(Self {
-            account_exists: Mutex::new(Self::account_with_id_exists(account_id.clone())?),
+            account_exists: Arc::new(Mutex::new(Self::account_with_id_exists(
+                account_id.clone(),
+                backend,
+            )?)),
             account_id,
+            backend: get_backend(backend.unwrap_or_default()),
+            session: Mutex::new(None),
         })
     }
 
@@ -102,18 +153,35 @@ impl Passport {
         &self,
         creation_option: Option<KeyCreationOption>,
     ) -> napi::Result<()> {
-        let create_result =
-            create_passport_key(&self.account_id, creation_option.map(|o| o.into()))
-                .await
-                .map_napi_error()?;
+        let span = operation_span("createAccount", Some(&self.account_id));
+        let result = async {
+            self.backend
+                .create_key(
+                    &self.account_id,
+                    creation_option.unwrap_or(KeyCreationOption::ReplaceExisting),
+                )
+                .await?;
 
-        match create_result {
-            KeyCredentialStatus::Success => {
-                *self.account_exists.lock().unwrap() = true;
-                Ok(())
-            }
-            s => Err(napi::Error::from_credential_status(s)),
+            // Signatures of an existing session no longer match the new key
+            *self.session.lock().unwrap() = None;
+            *self.account_exists.lock().unwrap() = true;
+            Ok(())
         }
+        .instrument(span.clone())
+        .await;
+        let fingerprint = match result {
+            Ok(_) => self.key_fingerprint().await,
+            Err(_) => None,
+        };
+
+        span.in_scope(|| {
+            record_result(
+                AuditEvent::new("createAccount")
+                    .account_id(&self.account_id)
+                    .key_fingerprint(fingerprint),
+                result,
+            )
+        })
     }
 
     #[napi]
@@ -123,7 +191,11 @@ impl Passport {
     /// If the challenge is not verified, an error will be thrown.
     ///
     /// The signature can be verified with the public key, for example
-    /// using the `crypto` module.
+    /// using the `crypto` module. Challenges starting with
+    /// `passport-desktop vault v1` are refused, as their signatures
+    /// are the key of the data encrypted using {@link protect}, and
+    /// so are session statements starting with `passport-desktop
+    /// session v1`, which only {@link unlock} signs.
     ///
     /// # Example
     /// ```ts
@@ -158,43 +230,1157 @@ impl Passport {
     /// @param challenge The challenge to sign.
     /// @return The signature.
     pub async fn sign(&self, challenge: Buffer) -> napi::Result<Buffer> {
-        check_account_exists!(self.account_exists);
-        let credential = get_passport_account(&self.account_id)
+        self.sign_challenge("sign", challenge.to_vec())
             .await
-            .map_napi_error()?;
+            .map(Buffer::from)
+    }
 
-        let status = credential.Status().map_napi_error()?;
-        if status != KeyCredentialStatus::Success {
-            return Err(napi::Error::from_credential_status(status));
+    #[napi]
+    /// Sign data in the SSH signature format, as created by
+    /// `ssh-keygen -Y sign`. The signature can be verified using
+    /// `ssh-keygen -Y verify`, e.g. to sign git commits and tags
+    /// with `gpg.format=ssh`. Like {@link sign}, this opens a
+    /// Windows Hello dialog to verify the user.
+    ///
+    /// The data is hashed using SHA-512 and signed using
+    /// `rsa-sha2-256`. Add the key returned by {@link getPublicKey}
+    /// using {@link PublicKeyEncoding.OpenSsh} to the allowed
+    /// signers file of the verifier.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { Passport } from 'passport-desktop';
+    ///
+    /// const passport = new Passport('my-account-id');
+    /// const signature = await passport.signSshsig('file', data);
+    /// // -----BEGIN SSH SIGNATURE-----
+    /// // U1NIU0lHAAAAAQAAARcAAAAHc3NoLXJzYQAAAAMBAAEAAAEBA...
+    /// // -----END SSH SIGNATURE-----
+    /// ```
+    ///
+    /// @param namespace The namespace of the signature, e.g. `git` or `file`.
+    /// @param data The data to sign.
+    /// @return The armored signature.
+    pub async fn sign_sshsig(&self, namespace: String, data: Buffer) -> napi::Result<String> {
+        let signed = sshsig::signed_data(&namespace, &data)?;
+        let public_key = self
+            .get_public_key(Some(PublicKeyEncoding::Pkcs1RsaPublicKey))
+            .await?;
+        let public_key = pkcs1_to_public_key_blob(&public_key)?;
+        let signature = self.sign_challenge("signSshsig", signed).await?;
+
+        Ok(sshsig::armor(&public_key, &namespace, &signature))
+    }
+
+    #[napi]
+    /// Create an OpenPGP v4 public key for the account key, e.g. for
+    /// release tooling requiring OpenPGP signatures. The key block
+    /// contains the RSA public key and the user ID, bound to the key
+    /// by a self-signature. The self-signature is created using
+    /// {@link sign}, so this opens a Windows Hello dialog to verify
+    /// the user.
+    ///
+    /// The creation time of the key is part of its fingerprint, so
+    /// store the returned `creationTime` and pass it to
+    /// {@link signOpenpgp}. Creating the key again with the same
+    /// creation time results in the same fingerprint.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { Passport } from 'passport-desktop';
+    ///
+    /// const passport = new Passport('my-account-id');
+    /// const key = await passport.createOpenpgpKey(
+    ///   'Release Bot <release@example.com>'
+    /// );
+    /// // gpg --import key.asc
+    /// writeFileSync('key.asc', key.publicKey);
+    /// ```
+    ///
+    /// @param userId The user ID, e.g. `Name <email@example.com>`.
+    /// @param creationTime The creation time in seconds since the Unix epoch. Defaults to now.
+    /// @return The armored public key and its fingerprint.
+    pub async fn create_openpgp_key(
+        &self,
+        user_id: String,
+        creation_time: Option<u32>,
+    ) -> napi::Result<OpenPgpKey> {
+        if user_id.is_empty() {
+            return Err(napi::Error::from_reason("The user ID must not be empty"));
         }
 
-        let credential = credential.Credential().map_napi_error()?;
-        let res = credential
-            .RequestSignAsync(&challenge.into_win_buffer().map_napi_error()?)
+        let creation_time = match creation_time {
+            Some(creation_time) => creation_time,
+            None => pgp::now()?,
+        };
+        let key = self.openpgp_public_key(creation_time).await?;
+        let certification = PendingSignature::user_id_certification(&key, &user_id, creation_time);
+        let signature = self
+            .sign_challenge("createOpenpgpKey", certification.signed_data().to_vec())
+            .await?;
+
+        let mut packets = key.packet();
+        packets.extend(pgp::user_id_packet(&user_id));
+        packets.extend(certification.packet(&signature));
+        Ok(OpenPgpKey {
+            public_key: armor("PGP PUBLIC KEY BLOCK", &packets),
+            fingerprint: hex::encode_upper(key.fingerprint()),
+            key_id: hex::encode_upper(key.key_id()),
+            creation_time,
+        })
+    }
+
+    #[napi]
+    /// Create a detached, armored OpenPGP signature of some data,
+    /// which `gpg --verify` accepts after importing the key created
+    /// using {@link createOpenpgpKey}. The signature is created using
+    /// {@link sign}, so this opens a Windows Hello dialog to verify
+    /// the user.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { Passport } from 'passport-desktop';
+    ///
+    /// const passport = new Passport('my-account-id');
+    /// const signature = await passport.signOpenpgp(data, key.creationTime);
+    /// // -----BEGIN PGP SIGNATURE-----
+    /// // ...
+    /// // -----END PGP SIGNATURE-----
+    /// ```
+    ///
+    /// @param data The data to sign.
+    /// @param creationTime The creation time of the key returned by {@link createOpenpgpKey}.
+    /// @return The armored signature.
+    pub async fn sign_openpgp(&self, data: Buffer, creation_time: u32) -> napi::Result<String> {
+        let key = self.openpgp_public_key(creation_time).await?;
+        let pending = PendingSignature::document(&key, &data, pgp::now()?);
+        let signature = self
+            .sign_challenge("signOpenpgp", pending.signed_data().to_vec())
+            .await?;
+
+        Ok(armor("PGP SIGNATURE", &pending.packet(&signature)))
+    }
+
+    #[napi]
+    /// Export the account's public key in the compact format used by
+    /// {@link signMinisign}, for distributing it to the hosts verifying
+    /// the signatures. The key ID is the SHA-256 fingerprint of the
+    /// PKCS#1 encoded public key, as recorded in the audit log.
+    ///
+    /// @return The encoded public key.
+    pub async fn export_minisign_public_key(&self) -> napi::Result<String> {
+        let public_key = self
+            .get_public_key(Some(PublicKeyEncoding::Pkcs1RsaPublicKey))
+            .await?;
+
+        Ok(minisign::public_key(&public_key))
+    }
+
+    #[napi]
+    /// Sign data in a compact format modelled after minisign, e.g. to
+    /// verify small bundles on hosts without OpenSSL or GnuPG. The
+    /// signature contains an untrusted comment, the algorithm, the key
+    /// ID, the signature and a trusted comment, which is signed along
+    /// with the data. The signature is created using {@link sign}, so
+    /// this opens a Windows Hello dialog to verify the user.
+    ///
+    /// Verify the signatures using {@link verifyMinisign} and the key
+    /// returned by {@link exportMinisignPublicKey}. The format is not
+    /// compatible with minisign itself, as minisign only supports
+    /// Ed25519 keys.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { Passport } from 'passport-desktop';
+    ///
+    /// const passport = new Passport('my-account-id');
+    /// const signature = await passport.signMinisign(bundle, 'file:config.tar');
+    /// // untrusted comment: signature from passport key 3F1C...
+    /// // UlM/HO...
+    /// // trusted comment: file:config.tar
+    /// ```
+    ///
+    /// @param data The data to sign.
+    /// @param trustedComment The trusted comment. Defaults to the current time as `timestamp:<seconds>`.
+    /// @return The encoded signature.
+    pub async fn sign_minisign(
+        &self,
+        data: Buffer,
+        trusted_comment: Option<String>,
+    ) -> napi::Result<String> {
+        let trusted_comment = match trusted_comment {
+            Some(comment) => comment,
+            None => format!(
+                "timestamp:{}",
+                SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .map_napi_error()?
+                    .as_secs()
+            ),
+        };
+
+        let public_key = self
+            .get_public_key(Some(PublicKeyEncoding::Pkcs1RsaPublicKey))
+            .await?;
+        let key_id = vault::key_fingerprint(&public_key);
+        let signed = minisign::signed_data(&key_id, &data, &trusted_comment)?;
+        let signature = self.sign_challenge("signMinisign", signed).await?;
+
+        Ok(minisign::signature(&key_id, &trusted_comment, &signature))
+    }
+
+    #[napi]
+    /// Verify a signature created using {@link signMinisign}. This is
+    /// implemented in pure Rust, works on all platforms and does not
+    /// require an account. Malformed signatures and keys are reported
+    /// as invalid instead of throwing.
+    ///
+    /// @param publicKey The key returned by {@link exportMinisignPublicKey}.
+    /// @param signature The signature.
+    /// @param data The signed data.
+    /// @return The result of the verification.
+    pub fn verify_minisign(
+        public_key: String,
+        signature: String,
+        data: Buffer,
+    ) -> MinisignVerification {
+        minisign::verify(&public_key, &signature, &data).into()
+    }
+
+    #[napi]
+    /// Sign an outgoing HTTP request using HTTP Message Signatures as
+    /// defined in RFC 9421, with the `rsa-v1_5-sha256` algorithm. This
+    /// builds the signature base from the covered components and signs
+    /// it using {@link sign}, which opens a Windows Hello dialog to
+    /// verify the user. Add the returned `Signature-Input` and
+    /// `Signature` headers to the request.
+    ///
+    /// Supported components are `@method`, `@target-uri`, `@authority`,
+    /// `@scheme`, `@request-target`, `@path`, `@query` and header fields,
+    /// using their lower case names. Components with parameters, like
+    /// `@query-param`, are not supported. If a body is covered using
+    /// the `content-digest` header, compute the digest before signing.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { Passport } from 'passport-desktop';
+    ///
+    /// const passport = new Passport('my-account-id');
+    /// const { signatureInput, signature } = await passport.signHttpRequest(
+    ///   {
+    ///     method: 'POST',
+    ///     targetUri: 'https://api.example.com/items',
+    ///     headers: { 'content-type': 'application/json' },
+    ///   },
+    ///   { components: ['@method', '@target-uri', 'content-type'] }
+    /// );
+    ///
+    /// await fetch('https://api.example.com/items', {
+    ///   method: 'POST',
+    ///   headers: {
+    ///     'content-type': 'application/json',
+    ///     'signature-input': signatureInput,
+    ///     signature,
+    ///   },
+    ///   body,
+    /// });
+    /// ```
+    ///
+    /// @param request The request to sign.
+    /// @param options The components to cover and the signature parameters.
+    /// @return The values of the signature headers.
+    pub async fn sign_http_request(
+        &self,
+        request: HttpRequest,
+        options: HttpSignatureOptions,
+    ) -> napi::Result<HttpSignature> {
+        let label = options.label.unwrap_or_else(|| "sig1".to_string());
+        http::check_label(&label)?;
+
+        let created = match options.created {
+            Some(created) => created,
+            None => {
+                let now = SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .map_napi_error()?
+                    .as_secs();
+                u32::try_from(now).map_err(|_| {
+                    napi::Error::from_reason("The current time does not fit the creation time")
+                })?
+            }
+        };
+        let expires = options
+            .validity_seconds
+            .map(|validity| created.saturating_add(validity));
+        let key_id = match options.key_id {
+            Some(key_id) => key_id,
+            None => hash_hex(
+                &self
+                    .get_public_key(Some(PublicKeyEncoding::Pkcs1RsaPublicKey))
+                    .await?,
+            ),
+        };
+        let params = SignatureParams::new(
+            options.components,
+            created,
+            expires,
+            key_id,
+            options.nonce,
+            options.tag,
+        )?;
+
+        let headers = request.headers.unwrap_or_default();
+        let signature_base = http::signature_base(
+            &http::Request {
+                method: &request.method,
+                target_uri: &request.target_uri,
+                headers: &headers,
+            },
+            &params,
+        )?;
+        let signature = self
+            .sign_challenge("signHttpRequest", signature_base.clone().into_bytes())
+            .await?;
+
+        Ok(HttpSignature {
+            signature_input: http::signature_input_header(&label, &params),
+            signature: http::signature_header(&label, &signature),
+            signature_base,
+        })
+    }
+
+    #[napi]
+    /// Verify the HTTP Message Signature of a request signed using
+    /// {@link signHttpRequest}, e.g. on a server running on Linux.
+    /// This works on all platforms and does not require an account.
+    /// Only `rsa-v1_5-sha256` signatures are supported, and expired
+    /// signatures are rejected. Malformed signatures are reported as
+    /// invalid instead of throwing.
+    ///
+    /// The public key of the signer has to be known in advance, e.g.
+    /// by looking up the `keyid` parameter of the `Signature-Input`
+    /// header in the keys registered for the clients.
+    ///
+    /// A valid signature only proves that the returned `components`
+    /// of the request were signed, so servers have to check that they
+    /// cover everything the request depends on. A signature covering no
+    /// components is valid for any request. Signatures without `expires`
+    /// never expire, so servers should also reject signatures whose
+    /// `created` time is too old, and check the nonce to prevent replays.
+    ///
+    /// @param request The received request.
+    /// @param signatureInput The value of the `Signature-Input` header.
+    /// @param signature The value of the `Signature` header.
+    /// @param publicKey The PEM or DER encoded RSA public key of the signer, as `SubjectPublicKeyInfo` or PKCS#1 key.
+    /// @param label The label of the signature to verify. May be omitted if the request has a single signature.
+    /// @return The result of the verification.
+    pub fn verify_http_signature(
+        request: HttpRequest,
+        signature_input: String,
+        signature: String,
+        public_key: Buffer,
+        label: Option<String>,
+    ) -> napi::Result<HttpSignatureVerification> {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_napi_error()?
+            .as_secs();
+        let headers = request.headers.unwrap_or_default();
+        let request = http::Request {
+            method: &request.method,
+            target_uri: &request.target_uri,
+            headers: &headers,
+        };
+
+        Ok(http::verify(
+            &request,
+            &signature_input,
+            &signature,
+            &public_key,
+            label.as_deref(),
+            now,
+        )
+        .into())
+    }
+
+    #[napi]
+    /// Create a DPoP proof as defined in RFC 9449, binding OAuth access
+    /// tokens to the account key. The proof is a JWT with the account's
+    /// public key as JWK in its header, a random `jti`, the method, the
+    /// target URI and the issue time, signed using `RS256`. The proof
+    /// is signed using {@link sign}, so this opens a Windows Hello
+    /// dialog to verify the user.
+    ///
+    /// Send the proof in the `DPoP` header of the token request and of
+    /// every request using the access token, creating a new proof for
+    /// every request.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { Passport } from 'passport-desktop';
+    ///
+    /// const passport = new Passport('my-account-id');
+    /// const proof = await passport.createDpopProof(
+    ///   'GET',
+    ///   'https://api.example.com/items',
+    ///   accessToken
+    /// );
+    ///
+    /// await fetch('https://api.example.com/items', {
+    ///   headers: { authorization: `DPoP ${accessToken}`, dpop: proof },
+    /// });
+    /// ```
+    ///
+    /// @param htm The method of the request.
+    /// @param htu The target URI of the request. The query and fragment are removed.
+    /// @param accessToken The access token sent with the request, bound to the proof using `ath`.
+    /// @param nonce The nonce provided by the server in the `DPoP-Nonce` header.
+    /// @return The proof.
+    pub async fn create_dpop_proof(
+        &self,
+        htm: String,
+        htu: String,
+        access_token: Option<String>,
+        nonce: Option<String>,
+    ) -> napi::Result<String> {
+        let iat = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
             .map_napi_error()?
-            .get()
-            .map_napi_error()?;
+            .as_secs();
+        let claims = dpop::Claims::new(htm, &htu, iat, access_token.as_deref(), nonce)?;
+        let public_key = self
+            .get_public_key(Some(PublicKeyEncoding::Pkcs1RsaPublicKey))
+            .await?;
+        let header = dpop::Header::new(RsaJwk::from_pkcs1(&public_key)?);
+
+        let signing_input = jose::signing_input(&header, &claims)?;
+        let signature = self
+            .sign_challenge("createDpopProof", signing_input.clone().into_bytes())
+            .await?;
+
+        Ok(jose::compact(&signing_input, &signature))
+    }
+
+    #[napi]
+    /// Verify a DPoP proof created using {@link createDpopProof}, e.g. on
+    /// an authorization or resource server running on Linux. This checks
+    /// the type, the signature using the key in the header, the method,
+    /// the target URI and the issue time, and the access token hash,
+    /// nonce and key thumbprint if they are expected. Only `RS256` proofs
+    /// are supported. Malformed proofs are reported as invalid instead
+    /// of throwing.
+    ///
+    /// The returned `jkt` is the JWK thumbprint of the key, which an
+    /// authorization server puts into the `cnf.jkt` claim of the access
+    /// tokens it issues. Replays have to be detected by the caller,
+    /// e.g. by remembering the `jti` of recent proofs.
+    ///
+    /// @param proof The value of the `DPoP` header.
+    /// @param htm The method of the received request.
+    /// @param htu The target URI of the received request.
+    /// @param options The access token, nonce and key the proof is expected to be bound to.
+    /// @return The result of the verification.
+    pub fn verify_dpop_proof(
+        proof: String,
+        htm: String,
+        htu: String,
+        options: Option<DpopVerificationOptions>,
+    ) -> napi::Result<DpopVerification> {
+        let options = options.unwrap_or(DpopVerificationOptions {
+            access_token: None,
+            nonce: None,
+            jkt: None,
+            max_age_seconds: None,
+            time: None,
+        });
+        let now = match options.time {
+            Some(time) => u64::try_from(time)
+                .map_err(|_| napi::Error::from_reason("The time must not be negative"))?,
+            None => SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_napi_error()?
+                .as_secs(),
+        };
+
+        let expected = dpop::Expected {
+            htm: &htm,
+            htu: &htu,
+            access_token: options.access_token.as_deref(),
+            nonce: options.nonce.as_deref(),
+            thumbprint: options.jkt.as_deref(),
+            max_age: options.max_age_seconds.unwrap_or(300) as u64,
+            now,
+        };
+
+        Ok(dpop::verify(&proof, &expected).into())
+    }
+
+    #[napi]
+    /// Create a `private_key_jwt` client assertion as defined in RFC 7523,
+    /// proving possession of the account key to an OAuth server. The
+    /// assertion is a JWT issued by the client for itself, valid for the
+    /// given audience, with a random `jti`, signed using `RS256`. It is
+    /// signed using {@link sign}, so this opens a Windows Hello dialog to
+    /// verify the user.
+    ///
+    /// Register the account's public key with the OAuth server, and send
+    /// the assertion as `client_assertion` parameter, together with the
+    /// `client_assertion_type` `urn:ietf:params:oauth:client-assertion-type:jwt-bearer`.
+    ///
+    /// @param options The client ID, audience and lifetime of the assertion.
+    /// @return The assertion.
+    pub async fn create_client_assertion(
+        &self,
+        options: ClientAssertionOptions,
+    ) -> napi::Result<String> {
+        let iat = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_napi_error()?
+            .as_secs();
+        let claims = client_assertion::Claims::new(
+            options.client_id,
+            options.audience,
+            iat,
+            options.lifetime_seconds.unwrap_or(60) as u64,
+        )?;
+        let key_id = match options.key_id {
+            Some(key_id) => key_id,
+            None => {
+                let public_key = self
+                    .get_public_key(Some(PublicKeyEncoding::Pkcs1RsaPublicKey))
+                    .await?;
+                RsaJwk::from_pkcs1(&public_key)?.thumbprint()
+            }
+        };
+
+        let signing_input = jose::signing_input(&client_assertion::Header::new(key_id), &claims)?;
+        let signature = self
+            .sign_challenge("createClientAssertion", signing_input.clone().into_bytes())
+            .await?;
+
+        Ok(jose::compact(&signing_input, &signature))
+    }
+
+    #[napi]
+    /// Store a refresh token bound to the account key in a file. The token
+    /// is encrypted using {@link protect}, so a stolen file is useless
+    /// without the account key. This opens a Windows Hello dialog to
+    /// verify the user. The file is replaced at once and, on Unix, is
+    /// only readable by the current user.
+    ///
+    /// Store the new refresh token again after every refresh if the
+    /// server rotates refresh tokens.
+    ///
+    /// @param path The path of the file.
+    /// @param refreshToken The refresh token to store.
+    pub async fn store_refresh_token(
+        &self,
+        path: String,
+        refresh_token: String,
+    ) -> napi::Result<()> {
+        if refresh_token.is_empty() {
+            return Err(napi::Error::from_reason(
+                "The refresh token must not be empty",
+            ));
+        }
+
+        let envelope = self.protect(refresh_token.into_bytes().into()).await?;
+        token_file::write(Path::new(&path), &envelope)
+    }
+
+    #[napi]
+    /// Create the parameters of a refresh token grant for a refresh token
+    /// stored using {@link storeRefreshToken}: the decrypted refresh token
+    /// and a client assertion created using {@link createClientAssertion},
+    /// proving possession of the account key to a server which binds the
+    /// refresh token to the key. This decrypts the token and signs the
+    /// assertion, so the user has to verify their identity twice,
+    /// depending on the backend.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { Passport } from 'passport-desktop';
+    ///
+    /// const passport = new Passport('my-account-id');
+    /// const grant = await passport.createRefreshTokenGrant('token.bin', {
+    ///   clientId: 'desktop-app',
+    ///   audience: 'https://auth.example.com/token',
+    /// });
+    ///
+    /// const response = await fetch('https://auth.example.com/token', {
+    ///   method: 'POST',
+    ///   body: new URLSearchParams({
+    ///     grant_type: 'refresh_token',
+    ///     refresh_token: grant.refreshToken,
+    ///     client_assertion_type: grant.clientAssertionType,
+    ///     client_assertion: grant.clientAssertion,
+    ///   }),
+    /// });
+    /// ```
+    ///
+    /// @param path The path of the file the refresh token was stored in.
+    /// @param options The client ID, audience and lifetime of the assertion.
+    /// @return The refresh token and the client assertion.
+    pub async fn create_refresh_token_grant(
+        &self,
+        path: String,
+        options: ClientAssertionOptions,
+    ) -> napi::Result<RefreshTokenGrant> {
+        let envelope = token_file::read(Path::new(&path))?;
+        let refresh_token = Zeroizing::new(self.unprotect(envelope.into()).await?.to_vec());
+        let refresh_token = std::str::from_utf8(&refresh_token)
+            .map_err(|_| napi::Error::from_reason("The stored refresh token is not valid UTF-8"))?
+            .to_string();
+
+        Ok(RefreshTokenGrant {
+            client_assertion: self.create_client_assertion(options).await?,
+            client_assertion_type: client_assertion::ASSERTION_TYPE.to_string(),
+            refresh_token,
+        })
+    }
+
+    #[napi]
+    /// Countersign an entitlement token issued by a server, e.g. to
+    /// report the usage of a license by this device. The server's
+    /// signature is verified using the pinned key first, then the
+    /// countersignature is created using {@link sign}, which opens
+    /// a Windows Hello dialog to verify the user.
+    ///
+    /// The token must be an `RS256` JWS signed by the server, in the
+    /// compact or the general JSON serialization. The countersigned
+    /// token is a JWS in the general JSON serialization with a second
+    /// signature over the same payload. Its protected header contains
+    /// the account's public key as JWK and the SHA-256 hash of the
+    /// server's signature, binding both signatures together. Tokens
+    /// can only be countersigned once. The validity period of the
+    /// token is not checked, so expired tokens can be reported.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { Passport } from 'passport-desktop';
+    ///
+    /// const passport = new Passport('my-account-id');
+    /// const report = await passport.countersignEntitlement(token, serverKey);
+    /// ```
+    ///
+    /// @param token The token issued by the server.
+    /// @param serverKey The pinned PEM or DER encoded RSA public key of the server, as `SubjectPublicKeyInfo` or PKCS#1 key.
+    /// @return The countersigned token.
+    pub async fn countersign_entitlement(
+        &self,
+        token: String,
+        server_key: Buffer,
+    ) -> napi::Result<String> {
+        let server_key = http::parse_public_key(&server_key)?;
+        let public_key = self
+            .get_public_key(Some(PublicKeyEncoding::Pkcs1RsaPublicKey))
+            .await?;
+        let pending = entitlement::PendingCountersignature::new(
+            &token,
+            server_key,
+            RsaJwk::from_pkcs1(&public_key)?,
+        )?;
+
+        let signature = self
+            .sign_challenge(
+                "countersignEntitlement",
+                pending.signing_input().into_bytes(),
+            )
+            .await?;
+
+        pending.finish(&signature)
+    }
+
+    #[napi]
+    /// Verify an entitlement token offline, e.g. a license issued by a
+    /// server, and its countersignature created using
+    /// {@link countersignEntitlement}, if present. This is implemented
+    /// in pure Rust, works on all platforms and does not require an
+    /// account. Only `RS256` signatures are supported. Tokens which
+    /// expired according to their `exp` claim or are not valid yet
+    /// according to their `nbf` claim are rejected. Malformed tokens
+    /// and keys are reported as invalid instead of throwing.
+    ///
+    /// The server's signature is verified using the pinned key only,
+    /// keys or certificates in the token's headers are ignored. The
+    /// returned `deviceJkt` is the JWK thumbprint of the device which
+    /// countersigned the token, e.g. to look up the registered device.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { Passport } from 'passport-desktop';
+    ///
+    /// const { valid, claims } = Passport.verifyEntitlement(token, serverKey);
+    /// if (valid) {
+    ///   const { features } = JSON.parse(claims);
+    /// }
+    /// ```
+    ///
+    /// @param token The token, as issued by the server or countersigned.
+    /// @param serverKey The pinned PEM or DER encoded RSA public key of the server, as `SubjectPublicKeyInfo` or PKCS#1 key.
+    /// @param options The device which must have countersigned the token and the time to check the validity period against.
+    /// @return The result of the verification.
+    pub fn verify_entitlement(
+        token: String,
+        server_key: Buffer,
+        options: Option<EntitlementVerificationOptions>,
+    ) -> napi::Result<EntitlementVerification> {
+        let options = options.unwrap_or(EntitlementVerificationOptions {
+            device_key: None,
+            require_countersignature: None,
+            leeway_seconds: None,
+            time: None,
+        });
+        let now = match options.time {
+            Some(time) => u64::try_from(time)
+                .map_err(|_| napi::Error::from_reason("The time must not be negative"))?,
+            None => SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_napi_error()?
+                .as_secs(),
+        };
+
+        let result = http::parse_public_key(&server_key).and_then(|server_key| {
+            let device_thumbprint = options
+                .device_key
+                .map(|key| {
+                    http::parse_public_key(&key)
+                        .map(|key| RsaJwk::from_public_key(&key).thumbprint())
+                })
+                .transpose()?;
+            let expected = entitlement::Expected {
+                device_thumbprint: device_thumbprint.as_deref(),
+                require_countersignature: options.require_countersignature.unwrap_or(false),
+                leeway: options.leeway_seconds.unwrap_or(60) as u64,
+                now,
+            };
+
+            entitlement::verify(&token, server_key, &expected)
+        });
+
+        Ok(result.into())
+    }
+
+    #[napi]
+    /// Encrypt data with a key only this account can derive, e.g. to
+    /// protect the key of a password vault. The key is derived from
+    /// the signature of a fixed challenge of this account, so the user
+    /// has to verify their identity, just like when calling {@link sign}.
+    /// The data can be decrypted using {@link unprotect} as long as the
+    /// account key is not replaced. Use {@link rewrap} to replace the
+    /// key without losing access to the data.
+    ///
+    /// The data is encrypted using AES-256-GCM with a key derived from
+    /// the signature using HKDF-SHA256 and a random salt. The returned
+    /// envelope contains a format version, the account ID, the
+    /// fingerprint of the account key, the salt, the nonce and the
+    /// encrypted data. Use {@link decodeEnvelope} to read these fields.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { Passport } from 'passport-desktop';
+    ///
+    /// const passport = new Passport('my-account-id');
+    /// const envelope = await passport.protect(vaultKey);
+    ///
+    /// // Later, after the user verified their identity again
+    /// const decrypted = await passport.unprotect(envelope);
+    /// ```
+    ///
+    /// @param data The data to encrypt.
+    /// @return The envelope containing the encrypted data.
+    pub async fn protect(&self, data: Buffer) -> napi::Result<Buffer> {
+        let data = data.to_vec();
+        self.vault()
+            .operation("protect", async {
+                let fingerprint = self.vault().key_fingerprint().await?;
+                let signature = self.vault().sign_challenge().await?;
+
+                Ok(Envelope::seal(&signature, &self.account_id, fingerprint, &data)?.encode()?)
+            })
+            .await
+            .map(Buffer::from)
+    }
+
+    #[napi]
+    /// Decrypt data encrypted using {@link protect}. This requires
+    /// the user to verify their identity, just like when calling
+    /// {@link sign}. If the data was encrypted by another account,
+    /// another key of this account or was modified, an error is thrown.
+    /// The account and key are checked before the user is asked to
+    /// verify their identity.
+    ///
+    /// @param envelope The envelope returned by {@link protect}.
+    /// @return The decrypted data.
+    pub async fn unprotect(&self, envelope: Buffer) -> napi::Result<Buffer> {
+        let envelope = envelope.to_vec();
+        self.vault()
+            .operation("unprotect", async {
+                let envelope = Envelope::decode(&envelope)?;
+                envelope
+                    .check_recipient(&self.account_id, &self.vault().key_fingerprint().await?)?;
+                let signature = self.vault().sign_challenge().await?;
+
+                Ok(envelope.open(&signature)?)
+            })
+            .await
+            .map(Buffer::from)
+    }
+
+    #[napi]
+    /// Replace the key of this account and re-encrypt data encrypted
+    /// using {@link protect} with the new key. Replacing the key using
+    /// {@link createAccount} makes all envelopes of the account
+    /// unreadable, this decrypts them using the old key first.
+    ///
+    /// The new key is created under a temporary ID and only replaces
+    /// the old key once every envelope was encrypted using it. If any
+    /// envelope cannot be decrypted, or the user declines to verify
+    /// their identity for the old or, depending on the backend, the new
+    /// key, the old key is kept and an error is thrown. Store the
+    /// returned envelopes before discarding the old ones. An empty list
+    /// is rejected, use {@link createAccount} to replace the key instead.
+    /// Windows Hello keys cannot be renamed, so they cannot be rewrapped.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { Passport } from 'passport-desktop';
+    ///
+    /// const passport = new Passport('my-account-id');
+    /// const [envelope] = await passport.rewrap([oldEnvelope]);
+    /// ```
+    ///
+    /// @param envelopes The envelopes returned by {@link protect}.
+    /// @return The envelopes encrypted with the new key, in the same order.
+    pub async fn rewrap(&self, envelopes: Vec<Buffer>) -> napi::Result<Vec<Buffer>> {
+        let envelopes: Vec<Vec<u8>> = envelopes.iter().map(|e| e.to_vec()).collect();
+        self.vault()
+            .operation("rewrap", async {
+                if envelopes.is_empty() {
+                    return Err(napi::Error::from_reason(
+                        "No envelopes were passed, use createAccount to replace the key",
+                    )
+                    .into());
+                }
+                if !self.backend.can_move_keys() {
+                    return Err(napi::Error::from_reason(
+                        "The backend cannot replace keys without losing the envelopes",
+                    )
+                    .into());
+                }
+
+                let envelopes = envelopes
+                    .iter()
+                    .map(|e| Envelope::decode(e))
+                    .collect::<napi::Result<Vec<_>>>()?;
+                let fingerprint = self.vault().key_fingerprint().await?;
+                for envelope in &envelopes {
+                    envelope.check_recipient(&self.account_id, &fingerprint)?;
+                }
+
+                let signature = self.vault().sign_challenge().await?;
+                let plaintexts = envelopes
+                    .iter()
+                    .map(|e| e.open(&signature).map(Zeroizing::new))
+                    .collect::<napi::Result<Vec<_>>>()?;
+
+                // The new key is created under a temporary ID and only replaces
+                // the old key once every envelope was sealed using it, so the
+                // old key is kept if the user declines to use the new key
+                let temporary_id = vault::rewrap_account_id(&self.account_id);
+                let rewrapped = async {
+                    self.backend
+                        .create_key(&temporary_id, KeyCreationOption::ReplaceExisting)
+                        .await?;
+                    let public_key = self
+                        .backend
+                        .public_key(&temporary_id, PublicKeyEncoding::Pkcs1RsaPublicKey)
+                        .await?;
+                    let signature = self
+                        .backend
+                        .sign(&temporary_id, &self.vault().challenge())
+                        .await?;
+
+                    let fingerprint = vault::key_fingerprint(&public_key);
+                    plaintexts
+                        .iter()
+                        .map(|plaintext| {
+                            let envelope = Envelope::seal(
+                                &signature,
+                                &self.account_id,
+                                fingerprint,
+                                plaintext,
+                            )?;
+                            Ok(envelope.encode()?.into())
+                        })
+                        .collect::<Result<Vec<Buffer>, OperationError>>()
+                }
+                .await;
+
+                let result = match rewrapped {
+                    Ok(rewrapped) => self
+                        .backend
+                        .move_key(&temporary_id, &self.account_id)
+                        .await
+                        .map(|_| rewrapped),
+                    Err(e) => Err(e),
+                };
+                match &result {
+                    Ok(_) => *self.session.lock().unwrap() = None,
+                    // The temporary key may not have been created yet
+                    Err(_) => {
+                        let _ = self.backend.delete_key(&temporary_id).await;
+                    }
+                }
+
+                result
+            })
+            .await
+    }
+
+    #[napi]
+    /// Decode an envelope returned by {@link protect} without
+    /// decrypting it, e.g. to find the account it belongs to.
+    /// Throws an error if the envelope is malformed.
+    ///
+    /// @param envelope The envelope to decode.
+    /// @return The fields of the envelope.
+    pub fn decode_envelope(envelope: Buffer) -> napi::Result<ProtectedEnvelope> {
+        Envelope::decode(&envelope).map(ProtectedEnvelope::from)
+    }
+
+    #[napi]
+    /// Encode the fields of an envelope decoded using
+    /// {@link decodeEnvelope}. The fields are authenticated
+    /// when decrypting the envelope, so changing any of
+    /// them makes the envelope unreadable.
+    ///
+    /// @param envelope The fields of the envelope.
+    /// @return The encoded envelope.
+    pub fn encode_envelope(envelope: ProtectedEnvelope) -> napi::Result<Buffer> {
+        Envelope::try_from(envelope)?.encode().map(Buffer::from)
+    }
+
+    #[napi]
+    /// Sign multiple challenges in a single operation.
+    /// If the account does not exist, an error will be thrown.
+    /// Otherwise, a {@link SignResult} is returned for every
+    /// challenge, in the order the challenges were passed in,
+    /// even if some of the challenges could not be signed.
+    /// Like {@link sign}, vault challenges and session statements
+    /// are refused, without affecting the other challenges.
+    ///
+    /// Windows Hello asks the user to verify every signature,
+    /// but once the user cancels a dialog, the remaining challenges
+    /// are skipped without showing further dialogs. To sign
+    /// many challenges with a single dialog, use {@link unlock}
+    /// and {@link signWithSession} instead.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { Passport } from 'passport-desktop';
+    ///
+    /// const passport = new Passport('my-account-id');
+    /// const results = await passport.signMany(challenges);
+    ///
+    /// for (const { signature, outcome, error } of results) {
+    ///   if (signature) {
+    ///     // Use the signature
+    ///   } else {
+    ///     console.error(`Failed to sign: ${outcome}: ${error}`);
+    ///   }
+    /// }
+    /// ```
+    ///
+    /// @see {@link sign}
+    /// @param challenges The challenges to sign.
+    /// @return The result for every challenge.
+    pub async fn sign_many(&self, challenges: Vec<Buffer>) -> napi::Result<Vec<SignResult>> {
+        let challenges: Vec<Vec<u8>> = challenges.iter().map(|c| c.to_vec()).collect();
+        let span = operation_span("signMany", Some(&self.account_id));
+        let result = async {
+            check_account_exists!(self.account_exists);
+            // Refused challenges fail on their own, without stopping the others
+            let refuse = |challenge: &[u8]| {
+                vault::refuse_challenge(challenge)?;
+                session::refuse_statement(challenge)
+            };
+            let allowed: Vec<Vec<u8>> = challenges
+                .iter()
+                .filter(|challenge| refuse(challenge).is_ok())
+                .cloned()
+                .collect();
+            let mut signed = if allowed.is_empty() {
+                vec![]
+            } else {
+                self.backend.sign_many(&self.account_id, &allowed).await
+            }
+            .into_iter();
+            let results: Vec<Result<Vec<u8>, OperationError>> = challenges
+                .iter()
+                .map(|challenge| match refuse(challenge) {
+                    Ok(()) => signed.next().unwrap_or_else(|| {
+                        Err(napi::Error::from_reason(
+                            "The backend returned no result for the challenge",
+                        )
+                        .into())
+                    }),
+                    Err(e) => Err(e.into()),
+                })
+                .collect();
 
-        let status = res.Status().map_napi_error()?;
-        if status != KeyCredentialStatus::Success {
-            return Err(napi::Error::from_credential_status(status));
+            let failed = results.iter().filter(|r| r.is_err()).count();
+            tracing::info!(
+                signed = results.len() - failed,
+                failed,
+                "Signed {} of {} challenges",
+                results.len() - failed,
+                results.len()
+            );
+
+            Ok(results)
         }
+        .instrument(span.clone())
+        .await;
+        let fingerprint = self.key_fingerprint().await;
+
+        span.in_scope(|| {
+            let results = record_result(
+                AuditEvent::new("signMany")
+                    .account_id(&self.account_id)
+                    .key_fingerprint(fingerprint.clone()),
+                result,
+            )?;
+
+            // Every challenge gets its own entry, just like a call to sign
+            results
+                .into_iter()
+                .zip(&challenges)
+                .map(|(result, challenge)| {
+                    record_outcome(
+                        AuditEvent::new("sign")
+                            .account_id(&self.account_id)
+                            .challenge(challenge)
+                            .key_fingerprint(fingerprint.clone()),
+                        &result,
+                    )?;
+
+                    Ok(result.into())
+                })
+                .collect()
+        })
+    }
+
+    #[napi]
+    /// Sign a file without loading it into memory.
+    /// The file is hashed in chunks using the given algorithm,
+    /// defaulting to {@link DigestAlgorithm.Sha256}, and the
+    /// digest is signed like a challenge passed to {@link sign}.
+    /// If the account does not exist, an error will be thrown.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { Passport } from 'passport-desktop';
+    ///
+    /// const passport = new Passport('my-account-id');
+    /// const { signature, digest, algorithm } = await passport.signFile('large-file.bin');
+    /// ```
+    ///
+    /// @see {@link DigestSignature} on how to verify the signature.
+    /// @param path The path to the file to sign.
+    /// @param algorithm The algorithm to hash the file with.
+    /// @return The signature over the digest of the file.
+    pub async fn sign_file(
+        &self,
+        path: String,
+        algorithm: Option<DigestAlgorithm>,
+    ) -> napi::Result<DigestSignature> {
+        let algorithm = algorithm.unwrap_or(DigestAlgorithm::Sha256);
+        let digest = async {
+            check_account_exists!(self.account_exists);
+            let digest = hash_file(path.into(), algorithm)
+                .instrument(tracing::debug_span!(
+                    "hashFile",
+                    algorithm = algorithm.name()
+                ))
+                .await?;
+
+            Ok(digest)
+        };
+
+        sign_digest(
+            &*self.backend,
+            &self.account_id,
+            "signFile",
+            digest,
+            algorithm,
+        )
+        .await
+    }
+
+    #[napi]
+    /// Create a {@link Signer} for signing a payload passed in chunks,
+    /// for example a stream. The chunks are hashed using the given
+    /// algorithm, defaulting to {@link DigestAlgorithm.Sha256}, and
+    /// the digest is signed like a challenge passed to {@link sign}.
+    /// If the account does not exist, an error will be thrown.
+    ///
+    /// @see {@link DigestSignature} on how to verify the signature.
+    /// @param algorithm The algorithm to hash the payload with.
+    /// @return The signer.
+    pub fn create_signer(&self, algorithm: Option<DigestAlgorithm>) -> napi::Result<Signer> {
+        check_account_exists!(self.account_exists);
+        Ok(Signer::new(
+            self.account_id.clone(),
+            self.backend.clone(),
+            algorithm.unwrap_or(DigestAlgorithm::Sha256),
+        ))
+    }
 
-        Buffer::from_win_buffer(res.Result().map_napi_error()?).map_napi_error()
+    #[napi]
+    /// Open a {@link OtpStore} storing one-time password secrets in the
+    /// given file, encrypted like data passed to {@link protect}. The
+    /// file is created when the first secret is added. Every generated
+    /// code requires the user to verify their identity.
+    /// If the account does not exist, an error will be thrown.
+    ///
+    /// @param path The path of the store file.
+    /// @return The store.
+    pub fn open_otp_store(&self, path: String) -> napi::Result<OtpStore> {
+        check_account_exists!(self.account_exists);
+        Ok(OtpStore::new(
+            self.account_id.clone(),
+            self.backend.clone(),
+            self.account_exists.clone(),
+            PathBuf::from(path),
+        ))
     }
 
     #[napi]
     /// Delete the account from the Windows Credential Manager.
     /// If the account does not exist, an error will be thrown.
     pub async fn delete_account(&self) -> napi::Result<()> {
-        check_account_exists!(self.account_exists);
-        KeyCredentialManager::DeleteAsync(&HSTRING::from(&self.account_id))
-            .map_napi_error()?
-            .await
-            .map_napi_error()?;
+        let span = operation_span("deleteAccount", Some(&self.account_id));
+        let fingerprint = self.key_fingerprint().await;
+        let result = async {
+            check_account_exists!(self.account_exists);
+            self.backend.delete_key(&self.account_id).await?;
 
-        *self.account_exists.lock().unwrap() = false;
-        Ok(())
+            *self.session.lock().unwrap() = None;
+            *self.account_exists.lock().unwrap() = false;
+            Ok(())
+        }
+        .instrument(span.clone())
+        .await;
+
+        span.in_scope(|| {
+            record_result(
+                AuditEvent::new("deleteAccount")
+                    .account_id(&self.account_id)
+                    .key_fingerprint(fingerprint),
+                result,
+            )
+        })
     }
 
     #[napi]
@@ -232,26 +1418,345 @@ impl Passport {
         &self,
         encoding: Option<PublicKeyEncoding>,
     ) -> napi::Result<Buffer> {
-        check_account_exists!(self.account_exists);
-        let credential = get_passport_account(&self.account_id)
-            .await
-            .map_napi_error()?;
+        async {
+            check_account_exists!(self.account_exists);
+            self.backend
+                .public_key(
+                    &self.account_id,
+                    encoding.unwrap_or(PublicKeyEncoding::Pkcs1RsaPublicKey),
+                )
+                .await
+                .map(Buffer::from)
+                .map_err(napi::Error::from)
+        }
+        .instrument(operation_span("getPublicKey", Some(&self.account_id)))
+        .await
+    }
+
+    #[napi]
+    /// Create a PKCS#10 certificate signing request for the account key,
+    /// e.g. to have a certificate for the device issued by a CA. The
+    /// request is signed using {@link sign}, so this opens a Windows
+    /// Hello dialog to verify the user.
+    ///
+    /// The subject is a distinguished name in the format of RFC 4514.
+    /// Subject alternative names are prefixed with their type, like
+    /// `DNS:`, `IP:`, `email:` or `URI:`, and are requested together
+    /// with the other extensions in an `extensionRequest` attribute.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { Passport } from 'passport-desktop';
+    ///
+    /// const passport = new Passport('my-account-id');
+    /// const { pem } = await passport.createCsr(
+    ///   'CN=device-1,O=Example',
+    ///   ['DNS:device-1.example.com'],
+    ///   // Key usage: digital signature
+    ///   [{ oid: '2.5.29.15', critical: true, value: Buffer.from('03020780', 'hex') }]
+    /// );
+    /// ```
+    ///
+    /// @param subject The subject of the request, e.g. `CN=device-1,O=Example`.
+    /// @param subjectAltNames The subject alternative names to request.
+    /// @param extensions Additional extensions to request.
+    /// @return The certificate signing request.
+    pub async fn create_csr(
+        &self,
+        subject: String,
+        subject_alt_names: Option<Vec<String>>,
+        extensions: Option<Vec<X509Extension>>,
+    ) -> napi::Result<EncodedDocument> {
+        let subject = x509::parse_name(&subject)?;
+        let extensions = x509::build_extensions(
+            &subject,
+            &subject_alt_names.unwrap_or_default(),
+            extensions.unwrap_or_default(),
+        )?;
+
+        let public_key = self
+            .get_public_key(Some(PublicKeyEncoding::X509SubjectPublicKeyInfo))
+            .await?;
+        let info = csr::request_info(subject, &public_key, extensions)?;
+        let signature = self
+            .sign_challenge("createCsr", info.to_der().map_napi_error()?)
+            .await?;
+
+        let request = csr::certificate_request(info, &signature)?;
+        Ok(EncodedDocument {
+            der: request.to_der().map_napi_error()?.into(),
+            pem: request.to_pem(LineEnding::LF).map_napi_error()?,
+        })
+    }
 
-        let status = credential.Status().map_napi_error()?;
-        if status != KeyCredentialStatus::Success {
-            return Err(napi::Error::from_credential_status(status));
+    #[napi]
+    /// Create an X.509 v3 certificate for the account key, signed by
+    /// the key itself, e.g. for mutual TLS with local services. The
+    /// certificate is signed using {@link sign}, so this opens a
+    /// Windows Hello dialog to verify the user.
+    ///
+    /// The subject, subject alternative names and extensions use the
+    /// same format as in {@link createCsr}. The issuer is the subject,
+    /// and the certificate has a random serial number. Use
+    /// {@link matchesCertificate} to check whether a certificate
+    /// presented by a peer belongs to an account.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { Passport } from 'passport-desktop';
+    ///
+    /// const passport = new Passport('my-account-id');
+    /// const { pem } = await passport.createSelfSignedCertificate(
+    ///   'CN=device-1',
+    ///   365,
+    ///   ['DNS:localhost', 'IP:127.0.0.1']
+    /// );
+    /// ```
+    ///
+    /// @param subject The subject and issuer of the certificate.
+    /// @param validityDays The number of days the certificate is valid, starting now.
+    /// @param subjectAltNames The subject alternative names of the certificate.
+    /// @param extensions Additional extensions of the certificate.
+    /// @return The certificate.
+    pub async fn create_self_signed_certificate(
+        &self,
+        subject: String,
+        validity_days: u32,
+        subject_alt_names: Option<Vec<String>>,
+        extensions: Option<Vec<X509Extension>>,
+    ) -> napi::Result<EncodedDocument> {
+        if validity_days == 0 {
+            return Err(napi::Error::from_reason(
+                "The certificate must be valid for at least one day",
+            ));
         }
 
-        let credential = credential.Credential().map_napi_error()?;
-        let res = credential
-            .RetrievePublicKeyWithBlobType(
-                encoding
-                    .map(|e| e.into())
-                    .unwrap_or(CryptographicPublicKeyBlobType::Pkcs1RsaPublicKey),
+        let subject = x509::parse_name(&subject)?;
+        let extensions = x509::build_extensions(
+            &subject,
+            &subject_alt_names.unwrap_or_default(),
+            extensions.unwrap_or_default(),
+        )?;
+
+        let public_key = self
+            .get_public_key(Some(PublicKeyEncoding::X509SubjectPublicKeyInfo))
+            .await?;
+        let tbs_certificate = certificate::self_signed_tbs_certificate(
+            subject,
+            &public_key,
+            Duration::from_secs(validity_days as u64 * 24 * 60 * 60),
+            extensions,
+        )?;
+        let signature = self
+            .sign_challenge(
+                "createCertificate",
+                tbs_certificate.to_der().map_napi_error()?,
             )
-            .map_napi_error()?;
+            .await?;
+
+        let certificate = certificate::certificate(tbs_certificate, &signature)?;
+        Ok(EncodedDocument {
+            der: certificate.to_der().map_napi_error()?.into(),
+            pem: certificate.to_pem(LineEnding::LF).map_napi_error()?,
+        })
+    }
+
+    #[napi]
+    /// Check whether a certificate was issued for the key of this
+    /// account, by comparing the public key of the certificate to
+    /// the account's public key. This does not check the signature,
+    /// the validity period or the extensions of the certificate.
+    /// Throws if the certificate cannot be parsed.
+    ///
+    /// @param certificate The PEM or DER encoded certificate.
+    /// @return Whether the certificate contains the account's public key.
+    pub async fn matches_certificate(&self, certificate: Buffer) -> napi::Result<bool> {
+        let certificate = certificate::parse(&certificate)?;
+        let public_key = self
+            .get_public_key(Some(PublicKeyEncoding::X509SubjectPublicKeyInfo))
+            .await?;
+
+        certificate::has_public_key(&certificate, &public_key)
+    }
+
+    #[napi]
+    /// Create a detached CMS (PKCS #7) signature of some content, e.g.
+    /// for document pipelines expecting `SignedData`. The signature
+    /// contains the certificate and signed attributes with the content
+    /// type, the SHA-256 digest of the content and the signing time.
+    /// The signed attributes are signed using {@link sign}, so this
+    /// opens a Windows Hello dialog to verify the user.
+    ///
+    /// The certificate must have been issued for the account key,
+    /// e.g. using {@link createSelfSignedCertificate} or from a
+    /// request created using {@link createCsr}. The signature can be
+    /// verified using {@link verifyCms} or `openssl cms -verify`.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { Passport } from 'passport-desktop';
+    ///
+    /// const passport = new Passport('my-account-id');
+    /// const { pem } = await passport.signCms(document, certificate);
+    /// ```
+    ///
+    /// @param content The content to sign.
+    /// @param certificate The PEM or DER encoded certificate of the account key.
+    /// @return The `ContentInfo` containing the signature.
+    pub async fn sign_cms(
+        &self,
+        content: Buffer,
+        certificate: Buffer,
+    ) -> napi::Result<EncodedDocument> {
+        let certificate = certificate::parse(&certificate)?;
+        let public_key = self
+            .get_public_key(Some(PublicKeyEncoding::X509SubjectPublicKeyInfo))
+            .await?;
+        if !certificate::has_public_key(&certificate, &public_key)? {
+            return Err(napi::Error::from_reason(
+                "The certificate was not issued for the key of this account",
+            ));
+        }
+
+        let attributes = cms::signed_attributes(&content, SystemTime::now())?;
+        let signature = self
+            .sign_challenge("signCms", attributes.to_der().map_napi_error()?)
+            .await?;
 
-        Buffer::from_win_buffer(res).map_napi_error()
+        let content_info = cms::signed_data(certificate, attributes, &signature)?;
+        let der = content_info.to_der().map_napi_error()?;
+        Ok(EncodedDocument {
+            pem: cms::to_pem(&der)?,
+            der: der.into(),
+        })
+    }
+
+    #[napi]
+    /// Verify a detached CMS signature created using {@link signCms}.
+    /// Only signatures with a single RSA signer using SHA-256 and
+    /// signed attributes are supported. This works on all platforms
+    /// and does not require an account.
+    ///
+    /// The signer's certificate is taken from the signature, unless
+    /// one is passed. The certificate itself is not validated, so
+    /// check whether the returned certificate is trusted, e.g. using
+    /// {@link matchesCertificate}. Malformed signatures are reported
+    /// as invalid instead of throwing.
+    ///
+    /// @param signature The PEM or DER encoded signature.
+    /// @param content The signed content.
+    /// @param certificate The PEM or DER encoded certificate of the signer.
+    /// @return The result of the verification.
+    pub fn verify_cms(
+        signature: Buffer,
+        content: Buffer,
+        certificate: Option<Buffer>,
+    ) -> napi::Result<CmsVerification> {
+        let certificate = certificate
+            .map(|certificate| certificate::parse(&certificate))
+            .transpose()?;
+
+        Ok(cms::verify(&signature, &content, certificate).into())
+    }
+
+    #[napi]
+    /// Unlock a signing session. This signs a statement binding a new,
+    /// in-memory session key to the account, which opens a single
+    /// Windows Hello dialog. Afterwards, {@link signWithSession} signs
+    /// using the session key without asking the user again, until
+    /// the session expires, runs out of signatures or is locked
+    /// using {@link lock}. Unlocking a new session locks the
+    /// previous one.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { Passport } from 'passport-desktop';
+    ///
+    /// const passport = new Passport('my-account-id');
+    /// const session = await passport.unlock({
+    ///   durationSeconds: 60,
+    ///   maxSignatures: records.length,
+    /// });
+    ///
+    /// const signatures = [];
+    /// for (const record of records) {
+    ///   signatures.push(await passport.signWithSession(record));
+    /// }
+    ///
+    /// passport.lock();
+    /// ```
+    ///
+    /// @see {@link SessionInfo} on how to verify session signatures.
+    /// @param options The limits of the session.
+    /// @return Information about the session.
+    pub async fn unlock(&self, options: SessionOptions) -> napi::Result<SessionInfo> {
+        *self.session.lock().unwrap() = None;
+
+        let span = operation_span("unlock", Some(&self.account_id));
+        let result = self.unlock_session(options).instrument(span.clone()).await;
+
+        let mut event = AuditEvent::new("unlock").account_id(&self.account_id);
+        if let Ok(info) = &result {
+            event = event.challenge(&info.statement);
+        }
+        let fingerprint = self.key_fingerprint().await;
+
+        span.in_scope(|| record_result(event.key_fingerprint(fingerprint), result))
+    }
+
+    #[napi]
+    /// Sign a challenge with the key of the session unlocked
+    /// using {@link unlock}. This does not ask the user for
+    /// verification. If no session is unlocked, the session
+    /// expired or no signatures are left, an error will be thrown
+    /// and the session will be locked.
+    ///
+    /// @see {@link SessionInfo} on how to verify session signatures.
+    /// @param challenge The challenge to sign.
+    /// @return The signature.
+    pub fn sign_with_session(&self, challenge: Buffer) -> napi::Result<Buffer> {
+        let _span = operation_span("signWithSession", Some(&self.account_id)).entered();
+        let mut event = AuditEvent::new("signWithSession")
+            .account_id(&self.account_id)
+            .challenge(&challenge);
+
+        let mut session = self.session.lock().unwrap();
+        let result = match session.as_mut() {
+            Some(s) => {
+                event = event.key_fingerprint(Some(hash_hex(s.public_key())));
+                s.sign(&challenge).map_err(OperationError::from)
+            }
+            None => Err(napi::Error::from_reason("No session is unlocked").into()),
+        };
+
+        if result.is_err() {
+            *session = None;
+        }
+
+        record_result(event, result).map(Buffer::from)
+    }
+
+    #[napi]
+    /// Lock the session unlocked using {@link unlock}.
+    /// The session key is discarded, so no more signatures
+    /// can be created without unlocking a new session.
+    pub fn lock(&self) -> napi::Result<()> {
+        let _span = operation_span("lock", Some(&self.account_id)).entered();
+        *self.session.lock().unwrap() = None;
+
+        audit::record(AuditEvent::new("lock").account_id(&self.account_id))
+    }
+
+    #[napi(getter)]
+    /// The currently unlocked session, if any. Sessions which
+    /// expired or ran out of signatures are not returned.
+    pub fn session(&self) -> Option<SessionInfo> {
+        self.session
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|s| s.is_valid())
+            .map(Session::info)
     }
 
     #[napi(getter)]
@@ -271,12 +1776,11 @@ impl Passport {
     /// This will return `false` on non-Windows platforms and if the
     /// user does not have permission to use Windows Hello.
     ///
+    /// @param backend The backend to check, defaults to the platform's default backend.
     /// @return Whether the Passport API is available.
-    pub fn available() -> napi::Result<bool> {
-        KeyCredentialManager::IsSupportedAsync()
-            .map_napi_error()?
-            .get()
-            .map_napi_error()
+    pub fn available(backend: Option<PassportBackend>) -> napi::Result<bool> {
+        let _span = operation_span("available", None).entered();
+        get_backend(backend.unwrap_or_default()).available()
     }
 
     #[napi]
@@ -284,18 +1788,14 @@ impl Passport {
     /// Credential Manager.
     ///
     /// @param id The ID of the account to check.
+    /// @param backend The backend to check, defaults to the platform's default backend.
     /// @return Whether the account exists.
-    pub fn account_with_id_exists(id: String) -> napi::Result<bool> {
-        let status = get_passport_account_sync(&id)
-            .map_napi_error()?
-            .Status()
-            .map_napi_error()?;
-
-        match status {
-            KeyCredentialStatus::Success => Ok(true),
-            KeyCredentialStatus::NotFound => Ok(false),
-            s => Err(napi::Error::from_credential_status(s)),
-        }
+    pub fn account_with_id_exists(
+        id: String,
+        backend: Option<PassportBackend>,
+    ) -> napi::Result<bool> {
+        let _span = operation_span("accountWithIdExists", Some(&id)).entered();
+        get_backend(backend.unwrap_or_default()).account_exists(&id)
     }
 
     #[napi]
@@ -306,6 +1806,12 @@ impl Passport {
     /// another value from {@link VerificationResult} specifying the rejection
     /// reason.
     ///
+    /// The user is asked by the given {@link ConsentProvider}, defaulting
+    /// to {@link ConsentProvider.WindowsHello} on Windows and
+    /// {@link ConsentProvider.Fprintd} on Linux. If the provider is
+    /// not supported on this platform,
+    /// {@link VerificationResult.DeviceNotPresent} is returned.
+    ///
     /// # Example
     /// ```ts
     /// import { Passport, VerificationResult } from 'passport-desktop';
@@ -319,13 +1825,248 @@ impl Passport {
     /// ```
     ///
     /// @param message The message to show to the user.
+    /// @param provider The provider asking the user.
     /// @return The result of the verification request.
-    pub async fn request_verification(message: String) -> napi::Result<VerificationResult> {
-        VerificationResult::try_from(
-            UserConsentVerifier::RequestVerificationAsync(&HSTRING::from(message))
-                .map_napi_error()?
-                .await
-                .map_napi_error()?,
-        )
+    pub async fn request_verification(
+        message: String,
+        provider: Option<ConsentProvider>,
+    ) -> napi::Result<VerificationResult> {
+        let span = operation_span("requestVerification", None);
+        let result = get_verifier(provider.unwrap_or_default())
+            .verify(&message)
+            .instrument(span.clone())
+            .await;
+
+        let _span = span.entered();
+        let event = AuditEvent::new("requestVerification");
+        match result {
+            Ok(verification) => {
+                tracing::Span::current().record("outcome", verification.name());
+                audit::record(event.outcome(verification.name()))?;
+                Ok(verification)
+            }
+            Err(e) => record_result(event, Err(OperationError::from(e))),
+        }
+    }
+
+    #[napi]
+    /// Set the polkit action checked by {@link ConsentProvider.Polkit}.
+    /// The action must be defined in a policy installed in
+    /// `/usr/share/polkit-1/actions`. If no action ID is given, the
+    /// action is reset to `io.github.markusjx.passport-desktop.verify`,
+    /// which is defined in the policy shipped in the `polkit` directory
+    /// of this package.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { ConsentProvider, Passport } from 'passport-desktop';
+    ///
+    /// Passport.setPolkitAction('com.example.app.verify');
+    /// const result = await Passport.requestVerification(
+    ///   'Unlock your vault',
+    ///   ConsentProvider.Polkit
+    /// );
+    /// ```
+    ///
+    /// @param actionId The ID of the polkit action.
+    pub fn set_polkit_action(action_id: Option<String>) -> napi::Result<()> {
+        set_polkit_action_id(action_id)
+    }
+
+    #[napi]
+    /// Set the provider asking the user for consent before signing
+    /// using a key of {@link PassportBackend.Tpm}. TPM keys are not
+    /// protected by a PIN, so the user is asked before every signature,
+    /// or once for all items signed using {@link Passport.signMany}.
+    /// If no provider is given, the default provider of
+    /// {@link Passport.requestVerification} is used.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { ConsentProvider, Passport } from 'passport-desktop';
+    ///
+    /// Passport.setTpmConsentProvider(ConsentProvider.Polkit);
+    /// ```
+    ///
+    /// @param provider The provider asking the user.
+    pub fn set_tpm_consent_provider(provider: Option<ConsentProvider>) {
+        #[cfg(target_os = "linux")]
+        crate::backend::tpm::set_consent_provider(provider);
+        #[cfg(not(target_os = "linux"))]
+        let _ = provider;
+    }
+
+    #[napi]
+    /// Configure the token used by {@link PassportBackend.Pkcs11}.
+    /// This loads the PKCS#11 module, opens a session with the token
+    /// and logs in, if a PIN is given. The session is kept open
+    /// until another token is configured. Modules are loaded once
+    /// per process, so configuring a token again, or another token
+    /// of the same module, only opens a new session.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { Passport, PassportBackend } from 'passport-desktop';
+    ///
+    /// Passport.configurePkcs11({
+    ///   modulePath: '/usr/lib/softhsm/libsofthsm2.so',
+    ///   tokenLabel: 'passport',
+    ///   pin: '1234',
+    /// });
+    ///
+    /// const passport = new Passport('my-account-id', PassportBackend.Pkcs11);
+    /// ```
+    ///
+    /// @param options The module and token to use.
+    pub fn configure_pkcs11(options: Pkcs11Options) -> napi::Result<()> {
+        pkcs11_backend().configure(&options)
+    }
+
+    #[napi]
+    /// Configure the key store keeping the keys of
+    /// {@link PassportBackend.Local}. This only affects
+    /// keys created or loaded after the call.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { Passport, PassportBackend } from 'passport-desktop';
+    ///
+    /// Passport.configureKeyStore({ appName: 'my-app' });
+    /// const passport = new Passport('my-account-id', PassportBackend.Local);
+    /// ```
+    ///
+    /// @param options The application name and key directory to use.
+    pub fn configure_key_store(options: KeyStoreOptions) {
+        keystore::configure(options);
+    }
+
+    #[napi]
+    /// Set the callback returning the passphrase the key files of
+    /// {@link PassportBackend.Local} are encrypted with. The callback
+    /// is called every time a key file is read or written. Passing
+    /// `null` removes the callback. If no passphrase can be returned,
+    /// the callback should return a rejected promise instead of
+    /// throwing, which fails the operation using the key.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { Passport } from 'passport-desktop';
+    ///
+    /// Passport.setKeyStorePassphrase(async () => {
+    ///   return await askUserForPassphrase();
+    /// });
+    /// ```
+    ///
+    /// @param callback The callback returning the passphrase.
+    pub fn set_key_store_passphrase(
+        env: Env,
+        #[napi(ts_arg_type = "(() => string | Promise<string>) | null")] callback: Option<
+            PassphraseCallback,
+        >,
+    ) -> napi::Result<()> {
+        let callback = match callback {
+            Some(mut callback) => {
+                // Don't keep the process alive just because a callback is set
+                callback.unref(&env)?;
+                Some(callback)
+            }
+            None => None,
+        };
+
+        set_passphrase_callback(callback);
+        Ok(())
+    }
+
+    #[napi]
+    /// Record all key creations, key deletions, signatures and
+    /// consent prompts of all Passport instances to an audit sink.
+    /// This replaces any previously set sink.
+    ///
+    /// # Example
+    /// ```ts
+    /// import { AuditSink, Passport } from 'passport-desktop';
+    ///
+    /// Passport.setAuditSink(AuditSink.file('audit.jsonl'));
+    /// ```
+    ///
+    /// @param sink The sink to write the audit entries to.
+    pub fn set_audit_sink(sink: &AuditSink) {
+        audit::set_audit_log(Some(sink.log.clone()));
+    }
+
+    #[napi]
+    /// Stop recording operations to the audit sink set
+    /// using {@link setAuditSink}.
+    pub fn clear_audit_sink() {
+        audit::set_audit_log(None);
+    }
+}
+
+impl Passport {
+    /// Get the fingerprint of the account's public key
+    /// for recording it in the audit log.
+    async fn key_fingerprint(&self) -> Option<String> {
+        if !self.account_exists() {
+            return None;
+        }
+
+        key_fingerprint(&*self.backend, &self.account_id).await
+    }
+
+    /// Sign a challenge using the account key
+    /// and record the signature in the audit log.
+    async fn sign_challenge(
+        &self,
+        operation: &'static str,
+        challenge: Vec<u8>,
+    ) -> napi::Result<Vec<u8>> {
+        let event = AuditEvent::new(operation)
+            .account_id(&self.account_id)
+            .challenge(&challenge);
+        let span = operation_span(operation, Some(&self.account_id));
+        let result = async {
+            check_account_exists!(self.account_exists);
+            vault::refuse_challenge(&challenge)?;
+            session::refuse_statement(&challenge)?;
+            self.backend.sign(&self.account_id, &challenge).await
+        }
+        .instrument(span.clone())
+        .await;
+        let fingerprint = self.key_fingerprint().await;
+
+        span.in_scope(|| record_result(event.key_fingerprint(fingerprint), result))
+    }
+
+    /// The OpenPGP public key packet of the account key.
+    async fn openpgp_public_key(&self, creation_time: u32) -> napi::Result<pgp::PublicKey> {
+        let public_key = self
+            .get_public_key(Some(PublicKeyEncoding::Pkcs1RsaPublicKey))
+            .await?;
+
+        pgp::PublicKey::from_pkcs1(&public_key, creation_time)
+    }
+
+    /// The account key protecting envelopes.
+    fn vault(&self) -> VaultContext<'_> {
+        VaultContext {
+            account_id: &self.account_id,
+            backend: &*self.backend,
+            account_exists: &self.account_exists,
+        }
+    }
+
+    async fn unlock_session(&self, options: SessionOptions) -> Result<SessionInfo, OperationError> {
+        check_account_exists!(self.account_exists);
+        let pending = PendingSession::new(&self.account_id, &options).await?;
+        let endorsement = self
+            .backend
+            .sign(&self.account_id, &pending.statement)
+            .await?;
+
+        let session = pending.endorse(endorsement);
+        let info = session.info();
+        *self.session.lock().unwrap() = Some(session);
+
+        Ok(info)
     }
 }
@@ -0,0 +1,64 @@
+use napi::bindgen_prelude::{FromNapiValue, ToNapiValue};
+
+#[napi]
+/// The backend storing the keys of a {@link Passport} account.
+pub enum PassportBackend {
+    /// The keys are stored and protected by Windows Hello.
+    /// This is the default backend on Windows.
+    WindowsHello,
+    /// The keys are generated in software and only kept in memory
+    /// for the lifetime of the process. Signing does not require
+    /// any user interaction. This is mainly useful for testing
+    /// code using Passport on machines without Windows Hello.
+    Software,
+    /// The keys are created by the TPM 2.0 of the machine.
+    /// This is the default backend on Linux.
+    ///
+    /// The keys are stored as blobs wrapped by a primary key of the
+    /// TPM's owner hierarchy, so they can only be used with the TPM
+    /// they were created on. The blobs are stored in the directory
+    /// set in `PASSPORT_TPM_KEY_DIR`, defaulting to
+    /// `$XDG_DATA_HOME/passport-desktop/tpm`. The TPM is accessed
+    /// through the TCTI set in `TPM2TOOLS_TCTI`, `TCTI` or `TEST_TCTI`,
+    /// defaulting to `/dev/tpmrm0`.
+    Tpm,
+    /// The keys are stored on a PKCS#11 token, using the account ID
+    /// as the label of the key pair. The token must be configured
+    /// using {@link Passport.configurePkcs11} before it can be used.
+    Pkcs11,
+    /// The keys are generated in software and kept in the key store
+    /// of the machine, so they can be used by other processes.
+    /// On Linux, the keys are stored in the Secret Service of the
+    /// user's session, in a collection named after the application.
+    /// If no Secret Service is running, and on other platforms,
+    /// the keys are stored in files encrypted with the passphrase
+    /// returned by the callback set using
+    /// {@link Passport.setKeyStorePassphrase}. The key store
+    /// can be configured using {@link Passport.configureKeyStore}.
+    Local,
+}
+
+impl PassportBackend {
+    /// The name of the backend, for use in error messages.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::WindowsHello => "WindowsHello",
+            Self::Software => "Software",
+            Self::Tpm => "Tpm",
+            Self::Pkcs11 => "Pkcs11",
+            Self::Local => "Local",
+        }
+    }
+}
+
+impl Default for PassportBackend {
+    #[cfg(not(target_os = "linux"))]
+    fn default() -> Self {
+        Self::WindowsHello
+    }
+
+    #[cfg(target_os = "linux")]
+    fn default() -> Self {
+        Self::Tpm
+    }
+}
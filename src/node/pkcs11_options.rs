@@ -0,0 +1,13 @@
+#[napi(object)]
+/// Options for using a PKCS#11 token with {@link PassportBackend.Pkcs11}.
+pub struct Pkcs11Options {
+    /// The path to the PKCS#11 module of the token,
+    /// e.g. `/usr/lib/softhsm/libsofthsm2.so`.
+    pub module_path: String,
+    /// The label of the token to use. If not set,
+    /// the first slot with a token present is used.
+    pub token_label: Option<String>,
+    /// The user PIN of the token. If not set,
+    /// the token is used without logging in.
+    pub pin: Option<String>,
+}
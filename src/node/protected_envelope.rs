@@ -0,0 +1,75 @@
+use crate::util::vault::Envelope;
+use napi::bindgen_prelude::Buffer;
+
+#[napi(object)]
+/// The fields of an envelope returned by {@link Passport.protect},
+/// decoded using {@link Passport.decodeEnvelope}.
+pub struct ProtectedEnvelope {
+    /// The version of the envelope format.
+    pub version: u32,
+    /// The ID of the account the data was protected by.
+    /// Not set for version 1 envelopes.
+    pub account_id: Option<String>,
+    /// The hex encoded SHA-256 hash of the PKCS#1 encoded public key
+    /// the data was protected by. Not set for version 1 envelopes.
+    pub key_fingerprint: Option<String>,
+    /// The salt used to derive the encryption key.
+    pub salt: Buffer,
+    /// The AES-GCM nonce.
+    pub nonce: Buffer,
+    /// The encrypted data, followed by the authentication tag.
+    pub ciphertext: Buffer,
+}
+
+impl From<Envelope> for ProtectedEnvelope {
+    fn from(envelope: Envelope) -> Self {
+        Self {
+            version: envelope.version as u32,
+            account_id: envelope.account_id,
+            key_fingerprint: envelope.key_fingerprint.map(hex::encode),
+            salt: envelope.salt.to_vec().into(),
+            nonce: envelope.nonce.to_vec().into(),
+            ciphertext: envelope.ciphertext.into(),
+        }
+    }
+}
+
+fn invalid_field(name: &str) -> napi::Error {
+    napi::Error::from_reason(format!("The envelope field '{}' is invalid", name))
+}
+
+impl TryFrom<ProtectedEnvelope> for Envelope {
+    type Error = napi::Error;
+
+    fn try_from(envelope: ProtectedEnvelope) -> napi::Result<Self> {
+        let key_fingerprint = envelope
+            .key_fingerprint
+            .map(|fingerprint| {
+                hex::decode(fingerprint)
+                    .ok()
+                    .and_then(|fingerprint| fingerprint.try_into().ok())
+                    .ok_or_else(|| invalid_field("keyFingerprint"))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            version: envelope
+                .version
+                .try_into()
+                .map_err(|_| invalid_field("version"))?,
+            account_id: envelope.account_id,
+            key_fingerprint,
+            salt: envelope
+                .salt
+                .as_ref()
+                .try_into()
+                .map_err(|_| invalid_field("salt"))?,
+            nonce: envelope
+                .nonce
+                .as_ref()
+                .try_into()
+                .map_err(|_| invalid_field("nonce"))?,
+            ciphertext: envelope.ciphertext.to_vec(),
+        })
+    }
+}
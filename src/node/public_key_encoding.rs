@@ -1,4 +1,5 @@
 use napi::bindgen_prelude::{FromNapiValue, ToNapiValue};
+#[cfg(windows)]
 use windows::Security::Cryptography::Core::CryptographicPublicKeyBlobType;
 
 #[napi]
@@ -12,8 +13,13 @@ pub enum PublicKeyEncoding {
     BCryptPublicKey,
     Capi1PublicKey,
     BCryptEccFullPublicKey,
+    /// The key is an OpenSSH `ssh-rsa` public key in the format
+    /// of an `authorized_keys` line, without a comment.
+    /// Convert the buffer to a string to use it.
+    OpenSsh,
 }
 
+#[cfg(windows)]
 impl From<PublicKeyEncoding> for CryptographicPublicKeyBlobType {
     fn from(value: PublicKeyEncoding) -> Self {
         match value {
@@ -28,6 +34,8 @@ impl From<PublicKeyEncoding> for CryptographicPublicKeyBlobType {
             PublicKeyEncoding::BCryptEccFullPublicKey => {
                 CryptographicPublicKeyBlobType::BCryptEccFullPublicKey
             }
+            // Windows has no OpenSSH encoding, the key is converted from PKCS#1
+            PublicKeyEncoding::OpenSsh => CryptographicPublicKeyBlobType::Pkcs1RsaPublicKey,
         }
     }
 }
@@ -0,0 +1,42 @@
+use napi::bindgen_prelude::Buffer;
+
+#[napi(object)]
+/// Options for unlocking a signing session using {@link Passport.unlock}.
+pub struct SessionOptions {
+    /// How long the session may be used, in seconds.
+    pub duration_seconds: u32,
+    /// How many signatures may be created in the session.
+    pub max_signatures: u32,
+}
+
+#[napi(object)]
+/// Information about an unlocked signing session.
+///
+/// Signatures created in a session are made with a session key
+/// which only exists in memory. In order to verify them, first
+/// verify the `endorsement` over the `statement` using the
+/// public key of the account, then verify the signatures using
+/// the `publicKey` of the session. The statement binds the
+/// session key to the account and the limits of the session:
+///
+/// ```text
+/// passport-desktop session v1
+/// account: <account id>
+/// expires: <expiresAt>
+/// signatures: <maxSignatures>
+/// key: <hex-encoded publicKey>
+/// ```
+pub struct SessionInfo {
+    /// The PKCS#1 encoded public key of the session.
+    pub public_key: Buffer,
+    /// The statement signed by the account key when unlocking the session.
+    pub statement: Buffer,
+    /// The signature of the account key over the statement.
+    pub endorsement: Buffer,
+    /// When the session expires, in milliseconds since the unix epoch.
+    pub expires_at: i64,
+    /// How many signatures may be created in the session.
+    pub max_signatures: u32,
+    /// How many signatures may still be created in the session.
+    pub remaining_signatures: u32,
+}
@@ -0,0 +1,33 @@
+use crate::util::errors::OperationError;
+use napi::bindgen_prelude::Buffer;
+
+#[napi(object)]
+/// The result of signing a single challenge using {@link Passport.signMany}.
+pub struct SignResult {
+    /// The signature, if the challenge was signed.
+    pub signature: Option<Buffer>,
+    /// `Success` if the challenge was signed, otherwise the name
+    /// of the `KeyCredentialStatus` returned by Windows, the
+    /// {@link VerificationResult} if the user could not be verified,
+    /// or `Error` if signing failed for another reason.
+    pub outcome: String,
+    /// The error message, if the challenge was not signed.
+    pub error: Option<String>,
+}
+
+impl From<Result<Vec<u8>, OperationError>> for SignResult {
+    fn from(result: Result<Vec<u8>, OperationError>) -> Self {
+        match result {
+            Ok(signature) => Self {
+                signature: Some(signature.into()),
+                outcome: "Success".into(),
+                error: None,
+            },
+            Err(e) => Self {
+                signature: None,
+                outcome: e.outcome,
+                error: Some(e.error.reason),
+            },
+        }
+    }
+}
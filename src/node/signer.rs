@@ -0,0 +1,171 @@
+use crate::backend::{key_fingerprint, KeyBackend};
+use crate::node::digest_algorithm::DigestAlgorithm;
+use crate::util::audit::{record_result, AuditEvent};
+use crate::util::digest::Hasher;
+use crate::util::errors::OperationError;
+use crate::util::logging::operation_span;
+use napi::bindgen_prelude::Buffer;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tracing::Instrument;
+
+#[napi(object)]
+/// A signature over the digest of a payload.
+///
+/// The signature is a regular passport signature with the
+/// `digest` as the signed challenge. In order to verify it,
+/// hash the payload using the `algorithm`, compare the result
+/// to the `digest` and verify the signature over the `digest`.
+///
+/// # Example
+/// ```ts
+/// import { createHash, createPublicKey, createVerify } from 'node:crypto';
+///
+/// const digest = createHash(result.algorithm).update(payload).digest();
+/// if (!digest.equals(result.digest)) {
+///   throw new Error('The payload does not match the digest');
+/// }
+///
+/// const verify = createVerify('SHA256');
+/// verify.write(result.digest);
+/// verify.end();
+///
+/// verify.verify(key, result.signature); // true
+/// ```
+pub struct DigestSignature {
+    /// The signature over the digest.
+    pub signature: Buffer,
+    /// The digest of the payload.
+    pub digest: Buffer,
+    /// The name of the algorithm used to create the digest,
+    /// e.g. `sha256`. This can be passed to `crypto.createHash`.
+    pub algorithm: String,
+}
+
+#[napi]
+/// Signs a payload passed in chunks. Every chunk is hashed
+/// as soon as it is passed to {@link update}, so the payload
+/// never has to be loaded into memory at once. Once all chunks
+/// were passed, {@link final} signs the digest. A signer can
+/// only be used once.
+///
+/// Signers are created using {@link Passport.createSigner}.
+///
+/// # Example
+/// ```ts
+/// import { Passport } from 'passport-desktop';
+/// import { createReadStream } from 'node:fs';
+///
+/// const passport = new Passport('my-account-id');
+/// const signer = passport.createSigner();
+///
+/// for await (const chunk of createReadStream('large-file.bin')) {
+///   signer.update(chunk);
+/// }
+///
+/// const { signature, digest, algorithm } = await signer.final();
+/// ```
+pub struct Signer {
+    account_id: String,
+    backend: Arc<dyn KeyBackend>,
+    hasher: Mutex<Option<Hasher>>,
+}
+
+#[napi]
+impl Signer {
+    #[napi]
+    /// Add a chunk of the payload to the digest.
+    /// Throws if the signer was already finalized.
+    ///
+    /// @param data The next chunk of the payload.
+    pub fn update(&self, data: Buffer) -> napi::Result<()> {
+        self.hasher
+            .lock()
+            .unwrap()
+            .as_mut()
+            .ok_or_else(Self::finalized_error)?
+            .update(&data);
+
+        Ok(())
+    }
+
+    #[napi(js_name = "final")]
+    /// Sign the digest of all chunks passed to {@link update}.
+    /// This will open a Windows Hello dialog to verify the user.
+    /// Throws if the signer was already finalized.
+    ///
+    /// @return The signature over the digest.
+    pub async fn finish(&self) -> napi::Result<DigestSignature> {
+        let hasher = self
+            .hasher
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or_else(Self::finalized_error)?;
+
+        let algorithm = hasher.algorithm();
+        sign_digest(
+            &*self.backend,
+            &self.account_id,
+            "signStream",
+            async { Ok(hasher.finalize()) },
+            algorithm,
+        )
+        .await
+    }
+}
+
+impl Signer {
+    pub fn new(
+        account_id: String,
+        backend: Arc<dyn KeyBackend>,
+        algorithm: DigestAlgorithm,
+    ) -> Self {
+        Self {
+            account_id,
+            backend,
+            hasher: Mutex::new(Some(Hasher::new(algorithm))),
+        }
+    }
+
+    fn finalized_error() -> napi::Error {
+        napi::Error::from_reason("The signer was already finalized")
+    }
+}
+
+/// Sign a digest once it was calculated and record the signature
+/// in the audit log. If calculating the digest fails, for example
+/// because a file can't be read, the failure is recorded as well.
+pub async fn sign_digest<F>(
+    backend: &dyn KeyBackend,
+    account_id: &str,
+    operation: &'static str,
+    digest: F,
+    algorithm: DigestAlgorithm,
+) -> napi::Result<DigestSignature>
+where
+    F: Future<Output = Result<Vec<u8>, OperationError>>,
+{
+    let span = operation_span(operation, Some(account_id));
+    let mut signed_digest = None;
+    let result = async {
+        let digest = signed_digest.insert(digest.await?);
+        tracing::debug!(algorithm = algorithm.name(), "Signing digest");
+        backend.sign(account_id, digest).await
+    }
+    .instrument(span.clone())
+    .await;
+    let fingerprint = key_fingerprint(backend, account_id).await;
+
+    let mut event = AuditEvent::new(operation).account_id(account_id);
+    if let Some(digest) = &signed_digest {
+        event = event.challenge(digest);
+    }
+    let signature = span.in_scope(|| record_result(event.key_fingerprint(fingerprint), result))?;
+
+    Ok(DigestSignature {
+        signature: signature.into(),
+        digest: signed_digest.unwrap_or_default().into(),
+        algorithm: algorithm.name().into(),
+    })
+}
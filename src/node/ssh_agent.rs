@@ -0,0 +1,184 @@
+use crate::backend::get_backend;
+use crate::node::passport_backend::PassportBackend;
+use crate::ssh::agent::{self, Agent, AgentIdentity};
+use crate::ssh::authorized_key;
+use crate::util::mappers::MapNapiError;
+use napi::bindgen_prelude::within_runtime_if_available;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+#[napi(object)]
+/// A passport account served as identity by an {@link SshAgent}.
+pub struct SshIdentity {
+    /// The ID of the account.
+    pub account_id: String,
+    /// The backend storing the key of the account,
+    /// defaults to the platform's default backend.
+    pub backend: Option<PassportBackend>,
+    /// The comment shown by `ssh-add -l`, defaults to the account ID.
+    pub comment: Option<String>,
+}
+
+#[napi]
+/// An SSH agent signing using passport keys, so SSH keys can be
+/// protected by Windows Hello. Every identity is a passport account,
+/// whose key is offered to SSH servers once the account exists.
+/// Signing asks the user for consent, just like {@link Passport.sign}.
+///
+/// Only listing identities and signing using `rsa-sha2-256` or
+/// `rsa-sha2-512` is supported. Windows Hello can only sign using
+/// SHA-256, so clients have to be configured to use `rsa-sha2-256`,
+/// e.g. by setting `PubkeyAcceptedAlgorithms rsa-sha2-256` in the
+/// SSH configuration. Signatures are recorded in the audit log as
+/// `sshSign` operations.
+///
+/// The agent only signs SSH public key authentication requests for
+/// the requested key and blobs of the SSH signature format, as used
+/// by `ssh-keygen -Y sign`. Other data is refused, so clients of the
+/// socket cannot obtain signatures used for other purposes, like the
+/// key derived by {@link Passport.protect}.
+///
+/// # Example
+/// ```ts
+/// import { SshAgent } from 'passport-desktop';
+///
+/// const agent = SshAgent.listen('/run/user/1000/passport-agent.sock', [
+///   { accountId: 'my-ssh-key' },
+/// ]);
+///
+/// // Add this to the authorized_keys file of the server
+/// const [publicKey] = await agent.publicKeys();
+/// ```
+///
+/// Clients connect to the agent using `SSH_AUTH_SOCK`.
+pub struct SshAgent {
+    path: String,
+    agent: Arc<Agent>,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+#[napi]
+impl SshAgent {
+    #[napi(factory)]
+    /// Start an agent listening on a Unix socket at `path`, or on the
+    /// named pipe `path` on Windows, e.g. `\\.\pipe\openssh-ssh-agent`.
+    /// Throws if the path is already in use. The agent does not keep
+    /// the process alive, and stops once {@link close} is called.
+    ///
+    /// @param path The path of the socket or named pipe.
+    /// @param identities The accounts to serve.
+    pub fn listen(path: String, identities: Vec<SshIdentity>) -> napi::Result<Self> {
+        let agent = Arc::new(Agent::new(
+            identities
+                .into_iter()
+                .map(|identity| AgentIdentity {
+                    comment: identity
+                        .comment
+                        .unwrap_or_else(|| identity.account_id.clone()),
+                    account_id: identity.account_id,
+                    backend: get_backend(identity.backend.unwrap_or_default()),
+                })
+                .collect(),
+        ));
+
+        let task = within_runtime_if_available(|| Self::spawn_listener(&path, agent.clone()))?;
+        tracing::info!(path, "The SSH agent is listening");
+
+        Ok(Self {
+            path,
+            agent,
+            task: Mutex::new(Some(task)),
+        })
+    }
+
+    #[napi(getter)]
+    /// The path the agent is listening on.
+    pub fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    #[napi]
+    /// Get the public keys of all identities whose account exists,
+    /// in the format of an `authorized_keys` file, e.g.
+    /// `ssh-rsa AAAAB3NzaC1yc2E... my-ssh-key`.
+    ///
+    /// @return The public keys, in the order of the identities.
+    pub async fn public_keys(&self) -> Vec<String> {
+        self.agent
+            .public_keys()
+            .await
+            .into_iter()
+            .map(|(identity, key)| authorized_key(&key, Some(&identity.comment)))
+            .collect()
+    }
+
+    #[napi]
+    /// Stop accepting new clients, disconnect the connected
+    /// clients and remove the socket. Calling this more than
+    /// once has no effect.
+    pub fn close(&self) -> napi::Result<()> {
+        let Some(task) = self.task.lock().unwrap().take() else {
+            return Ok(());
+        };
+
+        task.abort();
+        tracing::info!(path = self.path, "The SSH agent was closed");
+
+        #[cfg(unix)]
+        std::fs::remove_file(&self.path).map_napi_error()?;
+        Ok(())
+    }
+}
+
+impl SshAgent {
+    #[cfg(unix)]
+    fn spawn_listener(path: &str, agent: Arc<Agent>) -> napi::Result<JoinHandle<()>> {
+        use rand::RngCore;
+        use std::fs::{DirBuilder, Permissions};
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+        use std::path::Path;
+
+        // Only the user may ask the agent to sign. The socket is bound in
+        // a private directory and linked to the path once only the user
+        // can connect, which also fails if the path is already in use.
+        let path = Path::new(path);
+        let mut suffix = [0u8; 8];
+        rand::thread_rng().fill_bytes(&mut suffix);
+        let dir = path.with_file_name(format!(".passport-agent-{}", hex::encode(suffix)));
+        DirBuilder::new()
+            .mode(0o700)
+            .create(&dir)
+            .map_napi_error()?;
+
+        let socket = dir.join("agent.sock");
+        let listener = tokio::net::UnixListener::bind(&socket).and_then(|listener| {
+            std::fs::set_permissions(&socket, Permissions::from_mode(0o600))?;
+            std::fs::hard_link(&socket, path)?;
+            Ok(listener)
+        });
+        if let Err(e) = std::fs::remove_dir_all(&dir) {
+            tracing::warn!(error = %e, "Failed to remove the directory the SSH agent was bound in");
+        }
+
+        Ok(tokio::spawn(agent::listen_unix(
+            listener.map_napi_error()?,
+            agent,
+        )))
+    }
+
+    #[cfg(windows)]
+    fn spawn_listener(path: &str, agent: Arc<Agent>) -> napi::Result<JoinHandle<()>> {
+        use tokio::net::windows::named_pipe::ServerOptions;
+
+        let server = ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(path)
+            .map_napi_error()?;
+
+        Ok(tokio::spawn(agent::listen_pipe(
+            path.to_string(),
+            server,
+            agent,
+        )))
+    }
+}
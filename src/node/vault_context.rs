@@ -0,0 +1,63 @@
+use crate::backend::{key_fingerprint, KeyBackend};
+use crate::check_account_exists;
+use crate::node::public_key_encoding::PublicKeyEncoding;
+use crate::util::audit::{record_result, AuditEvent};
+use crate::util::errors::OperationError;
+use crate::util::logging::operation_span;
+use crate::util::vault::{self, vault_challenge};
+use std::future::Future;
+use std::sync::Mutex;
+use tracing::Instrument;
+
+/// The account key protecting envelopes, shared by
+/// {@link Passport} and the stores opened using it.
+pub struct VaultContext<'a> {
+    pub account_id: &'a str,
+    pub backend: &'a dyn KeyBackend,
+    pub account_exists: &'a Mutex<bool>,
+}
+
+impl VaultContext<'_> {
+    /// The challenge whose signature the vault keys are derived from.
+    pub fn challenge(&self) -> Vec<u8> {
+        vault_challenge(self.account_id)
+    }
+
+    /// The fingerprint of the account key, which envelopes
+    /// are bound to. Fails if the account does not exist.
+    pub async fn key_fingerprint(&self) -> Result<[u8; 32], OperationError> {
+        check_account_exists!(self.account_exists);
+        let public_key = self
+            .backend
+            .public_key(self.account_id, PublicKeyEncoding::Pkcs1RsaPublicKey)
+            .await?;
+
+        Ok(vault::key_fingerprint(&public_key))
+    }
+
+    /// Sign the challenge using the account key, which
+    /// requires the user to verify their identity.
+    pub async fn sign_challenge(&self) -> Result<Vec<u8>, OperationError> {
+        check_account_exists!(self.account_exists);
+        self.backend.sign(self.account_id, &self.challenge()).await
+    }
+
+    /// Run a vault operation and record it in the audit log.
+    pub async fn operation<T, F>(&self, operation: &'static str, f: F) -> napi::Result<T>
+    where
+        F: Future<Output = Result<T, OperationError>>,
+    {
+        let event = AuditEvent::new(operation)
+            .account_id(self.account_id)
+            .challenge(&self.challenge());
+        let span = operation_span(operation, Some(self.account_id));
+        let result = f.instrument(span.clone()).await;
+        let exists = *self.account_exists.lock().unwrap();
+        let fingerprint = match exists {
+            true => key_fingerprint(self.backend, self.account_id).await,
+            false => None,
+        };
+
+        span.in_scope(|| record_result(event.key_fingerprint(fingerprint), result))
+    }
+}
@@ -1,4 +1,5 @@
 use napi::bindgen_prelude::{FromNapiValue, ToNapiValue};
+#[cfg(windows)]
 use windows::Security::Credentials::UI::UserConsentVerificationResult;
 
 #[napi]
@@ -26,6 +27,7 @@ pub enum VerificationResult {
     Canceled,
 }
 
+#[cfg(windows)]
 impl TryFrom<UserConsentVerificationResult> for VerificationResult {
     type Error = napi::Error;
 
@@ -47,3 +49,18 @@ impl TryFrom<UserConsentVerificationResult> for VerificationResult {
         })
     }
 }
+
+impl VerificationResult {
+    /// Get the name of the result as it is recorded in the audit log.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Verified => "Verified",
+            Self::DeviceNotPresent => "DeviceNotPresent",
+            Self::NotConfiguredForUser => "NotConfiguredForUser",
+            Self::DisabledByPolicy => "DisabledByPolicy",
+            Self::DeviceBusy => "DeviceBusy",
+            Self::RetriesExhausted => "RetriesExhausted",
+            Self::Canceled => "Canceled",
+        }
+    }
+}
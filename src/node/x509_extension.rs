@@ -0,0 +1,14 @@
+use napi::bindgen_prelude::Buffer;
+
+#[napi(object)]
+/// An X.509 extension added to a certificate or certificate request.
+pub struct X509Extension {
+    /// The object identifier of the extension, e.g. `2.5.29.15`
+    /// for the key usage.
+    pub oid: String,
+    /// Whether the extension is critical. Defaults to `false`.
+    pub critical: Option<bool>,
+    /// The DER encoded value of the extension, without
+    /// the octet string wrapping it.
+    pub value: Buffer,
+}
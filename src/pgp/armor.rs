@@ -0,0 +1,38 @@
+use base64ct::{Base64, Encoding};
+
+/// The length of the lines of armored data, as written by GnuPG.
+const LINE_LENGTH: usize = 64;
+
+const CRC24_INIT: u32 = 0x00b7_04ce;
+const CRC24_POLY: u32 = 0x0186_4cfb;
+
+/// The checksum appended to armored data, defined in RFC 4880.
+fn crc24(data: &[u8]) -> u32 {
+    let mut crc = CRC24_INIT;
+    for byte in data {
+        crc ^= (*byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x0100_0000 != 0 {
+                crc ^= CRC24_POLY;
+            }
+        }
+    }
+
+    crc & 0x00ff_ffff
+}
+
+/// Encode packets using ASCII armor, e.g. as `PGP SIGNATURE`
+/// or `PGP PUBLIC KEY BLOCK`.
+pub fn armor(label: &str, data: &[u8]) -> String {
+    let mut armored = format!("-----BEGIN {}-----\n\n", label);
+    let encoded = Base64::encode_string(data);
+    for line in encoded.as_bytes().chunks(LINE_LENGTH) {
+        armored.push_str(std::str::from_utf8(line).unwrap());
+        armored.push('\n');
+    }
+
+    let checksum = Base64::encode_string(&crc24(data).to_be_bytes()[1..]);
+    armored.push_str(&format!("={}\n-----END {}-----\n", checksum, label));
+    armored
+}
@@ -0,0 +1,213 @@
+use crate::util::mappers::MapNapiError;
+use rsa::pkcs1::DecodeRsaPublicKey;
+use rsa::traits::PublicKeyParts;
+use rsa::RsaPublicKey;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::time::SystemTime;
+
+pub(crate) mod armor;
+
+/// The packet version of keys and signatures, defined in RFC 4880.
+const VERSION: u8 = 4;
+/// The public key algorithm ID of RSA keys.
+const RSA: u8 = 1;
+/// The hash algorithm ID of SHA-256, the only hash passport keys sign with.
+const SHA256: u8 = 8;
+
+const SIGNATURE_TAG: u8 = 2;
+const PUBLIC_KEY_TAG: u8 = 6;
+const USER_ID_TAG: u8 = 13;
+
+/// A signature over a binary document.
+const BINARY_DOCUMENT: u8 = 0x00;
+/// A self-signature binding a user ID to the key.
+const POSITIVE_CERTIFICATION: u8 = 0x13;
+
+const CREATION_TIME_SUBPACKET: u8 = 2;
+const ISSUER_SUBPACKET: u8 = 16;
+const PREFERRED_HASH_SUBPACKET: u8 = 21;
+const KEY_FLAGS_SUBPACKET: u8 = 27;
+const ISSUER_FINGERPRINT_SUBPACKET: u8 = 33;
+
+/// The key may certify user IDs and sign data.
+const CERTIFY_AND_SIGN: u8 = 0x03;
+
+/// The current time as OpenPGP timestamp.
+pub fn now() -> napi::Result<u32> {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_napi_error()?;
+
+    Ok(now.as_secs() as u32)
+}
+
+/// Encode a packet using the new packet format.
+fn packet(tag: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![0xc0 | tag];
+    packet.extend(body_length(body.len()));
+    packet.extend_from_slice(body);
+    packet
+}
+
+/// Encode the length of a packet body or subpacket.
+fn body_length(length: usize) -> Vec<u8> {
+    match length {
+        0..=191 => vec![length as u8],
+        192..=8383 => {
+            let length = length - 192;
+            vec![(length >> 8) as u8 + 192, length as u8]
+        }
+        _ => {
+            let mut encoded = vec![0xff];
+            encoded.extend_from_slice(&(length as u32).to_be_bytes());
+            encoded
+        }
+    }
+}
+
+/// Encode an unsigned big-endian integer as multiprecision integer.
+fn mpi(value: &[u8]) -> Vec<u8> {
+    let start = value.iter().position(|b| *b != 0).unwrap_or(value.len());
+    let value = &value[start..];
+    let bits = value.first().map_or(0, |b| {
+        (value.len() - 1) * 8 + (8 - b.leading_zeros() as usize)
+    });
+
+    let mut encoded = (bits as u16).to_be_bytes().to_vec();
+    encoded.extend_from_slice(value);
+    encoded
+}
+
+fn subpacket(kind: u8, data: &[u8]) -> Vec<u8> {
+    let mut subpacket = body_length(data.len() + 1);
+    subpacket.push(kind);
+    subpacket.extend_from_slice(data);
+    subpacket
+}
+
+/// An RSA public key packet. The creation time is part of the
+/// fingerprint, so the same time must be used every time the
+/// key of an account is encoded.
+pub struct PublicKey {
+    body: Vec<u8>,
+}
+
+impl PublicKey {
+    pub fn from_pkcs1(pkcs1: &[u8], creation_time: u32) -> napi::Result<Self> {
+        let key = RsaPublicKey::from_pkcs1_der(pkcs1).map_napi_error()?;
+
+        let mut body = vec![VERSION];
+        body.extend_from_slice(&creation_time.to_be_bytes());
+        body.push(RSA);
+        body.extend(mpi(&key.n().to_bytes_be()));
+        body.extend(mpi(&key.e().to_bytes_be()));
+        Ok(Self { body })
+    }
+
+    /// The key as it is hashed for fingerprints and key signatures.
+    fn hashed(&self) -> Vec<u8> {
+        let mut hashed = vec![0x99];
+        hashed.extend_from_slice(&(self.body.len() as u16).to_be_bytes());
+        hashed.extend_from_slice(&self.body);
+        hashed
+    }
+
+    /// The SHA-1 fingerprint of a v4 key.
+    pub fn fingerprint(&self) -> [u8; 20] {
+        Sha1::digest(self.hashed()).into()
+    }
+
+    /// The key ID, the low 64 bits of the fingerprint.
+    pub fn key_id(&self) -> [u8; 8] {
+        self.fingerprint()[12..].try_into().unwrap()
+    }
+
+    pub fn packet(&self) -> Vec<u8> {
+        packet(PUBLIC_KEY_TAG, &self.body)
+    }
+}
+
+pub fn user_id_packet(user_id: &str) -> Vec<u8> {
+    packet(USER_ID_TAG, user_id.as_bytes())
+}
+
+/// A v4 signature waiting for the account key to sign [`Self::signed_data`].
+/// Passport keys sign using RSASSA-PKCS1-v1_5 with SHA-256, so signing the
+/// data produces the signature over the SHA-256 hash OpenPGP expects.
+pub struct PendingSignature {
+    /// The signature packet up to and including the hashed subpackets.
+    hashed: Vec<u8>,
+    signed_data: Vec<u8>,
+    key_id: [u8; 8],
+}
+
+impl PendingSignature {
+    fn new(key: &PublicKey, kind: u8, creation_time: u32, subpackets: &[Vec<u8>]) -> Self {
+        let mut hashed_subpackets =
+            subpacket(CREATION_TIME_SUBPACKET, &creation_time.to_be_bytes());
+        let mut issuer = vec![VERSION];
+        issuer.extend_from_slice(&key.fingerprint());
+        hashed_subpackets.extend(subpacket(ISSUER_FINGERPRINT_SUBPACKET, &issuer));
+        for data in subpackets {
+            hashed_subpackets.extend_from_slice(data);
+        }
+
+        let mut hashed = vec![VERSION, kind, RSA, SHA256];
+        hashed.extend_from_slice(&(hashed_subpackets.len() as u16).to_be_bytes());
+        hashed.extend(hashed_subpackets);
+
+        Self {
+            hashed,
+            signed_data: vec![],
+            key_id: key.key_id(),
+        }
+    }
+
+    /// Append the hashed part of the signature and its trailer to the signed data.
+    fn finish(mut self, prefix: Vec<u8>) -> Self {
+        self.signed_data = prefix;
+        self.signed_data.extend_from_slice(&self.hashed);
+        self.signed_data.extend_from_slice(&[VERSION, 0xff]);
+        self.signed_data
+            .extend_from_slice(&(self.hashed.len() as u32).to_be_bytes());
+        self
+    }
+
+    /// A detached signature over binary data.
+    pub fn document(key: &PublicKey, data: &[u8], creation_time: u32) -> Self {
+        Self::new(key, BINARY_DOCUMENT, creation_time, &[]).finish(data.to_vec())
+    }
+
+    /// The self-signature binding a user ID to the key.
+    pub fn user_id_certification(key: &PublicKey, user_id: &str, creation_time: u32) -> Self {
+        let subpackets = [
+            subpacket(KEY_FLAGS_SUBPACKET, &[CERTIFY_AND_SIGN]),
+            subpacket(PREFERRED_HASH_SUBPACKET, &[SHA256]),
+        ];
+
+        let mut prefix = key.hashed();
+        prefix.push(0xb4);
+        prefix.extend_from_slice(&(user_id.len() as u32).to_be_bytes());
+        prefix.extend_from_slice(user_id.as_bytes());
+        Self::new(key, POSITIVE_CERTIFICATION, creation_time, &subpackets).finish(prefix)
+    }
+
+    /// The data the account key has to sign.
+    pub fn signed_data(&self) -> &[u8] {
+        &self.signed_data
+    }
+
+    /// Encode the signature packet using the signature of [`Self::signed_data`].
+    pub fn packet(self, signature: &[u8]) -> Vec<u8> {
+        let digest = Sha256::digest(&self.signed_data);
+
+        let mut body = self.hashed;
+        let unhashed_subpackets = subpacket(ISSUER_SUBPACKET, &self.key_id);
+        body.extend_from_slice(&(unhashed_subpackets.len() as u16).to_be_bytes());
+        body.extend(unhashed_subpackets);
+        body.extend_from_slice(&digest[..2]);
+        body.extend(mpi(signature));
+        packet(SIGNATURE_TAG, &body)
+    }
+}
@@ -0,0 +1,299 @@
+use crate::backend::{key_fingerprint, KeyBackend};
+use crate::node::digest_algorithm::DigestAlgorithm;
+use crate::node::public_key_encoding::PublicKeyEncoding;
+use crate::ssh::{pkcs1_to_public_key_blob, sshsig, SshReader, SshWriter};
+use crate::util::audit::{record_result, AuditEvent};
+use crate::util::logging::operation_span;
+use crate::util::mappers::MapNapiError;
+use crate::util::session;
+use crate::util::vault;
+use futures::FutureExt;
+use std::io::ErrorKind;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::task::JoinSet;
+use tracing::Instrument;
+
+/// The message types of the agent protocol, as defined in
+/// `draft-miller-ssh-agent`. All other requests fail.
+const SSH_AGENT_FAILURE: u8 = 5;
+const SSH_AGENTC_REQUEST_IDENTITIES: u8 = 11;
+const SSH_AGENT_IDENTITIES_ANSWER: u8 = 12;
+const SSH_AGENTC_SIGN_REQUEST: u8 = 13;
+const SSH_AGENT_SIGN_RESPONSE: u8 = 14;
+/// The flags of a sign request selecting the signature algorithm.
+const SSH_AGENT_RSA_SHA2_256: u32 = 2;
+const SSH_AGENT_RSA_SHA2_512: u32 = 4;
+/// The maximum length of a request, matching OpenSSH.
+const MAX_MESSAGE_LENGTH: usize = 256 * 1024;
+/// The message type of user authentication requests, defined in RFC 4252.
+const SSH_MSG_USERAUTH_REQUEST: u8 = 50;
+
+/// Check that the data of a sign request is a public key authentication
+/// request as defined in section 7 of RFC 4252 for the given key and
+/// signature algorithm, or a blob of the `PROTOCOL.sshsig` format.
+///
+/// Passport keys are used for more than SSH, e.g. the vault key is
+/// derived from the signature of a fixed challenge, so the agent
+/// must not sign arbitrary data for any client of its socket.
+fn check_signed_data(data: &[u8], key: &[u8], algorithm: &str) -> napi::Result<()> {
+    vault::refuse_challenge(data)?;
+    session::refuse_statement(data)?;
+    if sshsig::is_signed_data(data) {
+        return Ok(());
+    }
+
+    let mut reader = SshReader::new(data);
+    let session_id = reader.string()?;
+    let message_type = reader.byte()?;
+    let _user = reader.string()?;
+    let _service = reader.string()?;
+    let method = reader.string()?;
+    let has_signature = reader.byte()?;
+    let key_algorithm = reader.string()?;
+    let key_blob = reader.string()?;
+
+    if session_id.is_empty()
+        || message_type != SSH_MSG_USERAUTH_REQUEST
+        || method != b"publickey"
+        || has_signature != 1
+        || !reader.is_empty()
+    {
+        return Err(napi::Error::from_reason(
+            "The agent only signs SSH authentication requests and SSH signatures",
+        ));
+    }
+    if key_algorithm != algorithm.as_bytes() || key_blob != key {
+        return Err(napi::Error::from_reason(
+            "The authentication request is for another key or algorithm",
+        ));
+    }
+
+    Ok(())
+}
+
+/// A passport account served by the agent.
+pub struct AgentIdentity {
+    pub account_id: String,
+    pub backend: Arc<dyn KeyBackend>,
+    pub comment: String,
+}
+
+/// Answers requests of the SSH agent protocol using passport keys.
+/// Only listing the identities and signing is supported, keys
+/// cannot be added, removed or locked by clients. Only SSH
+/// authentication requests and SSH signatures are signed.
+pub struct Agent {
+    identities: Vec<AgentIdentity>,
+}
+
+impl Agent {
+    pub fn new(identities: Vec<AgentIdentity>) -> Self {
+        Self { identities }
+    }
+
+    /// Get the SSH public key blobs of all identities whose account
+    /// exists. Identities whose key cannot be read are skipped.
+    pub async fn public_keys(&self) -> Vec<(&AgentIdentity, Vec<u8>)> {
+        let mut keys = vec![];
+        for identity in &self.identities {
+            let key = identity
+                .backend
+                .public_key(&identity.account_id, PublicKeyEncoding::Pkcs1RsaPublicKey)
+                .await
+                .map_err(|e| e.error)
+                .and_then(|key| pkcs1_to_public_key_blob(&key));
+
+            match key {
+                Ok(key) => keys.push((identity, key)),
+                Err(e) => tracing::debug!(
+                    account_id = identity.account_id,
+                    error = %e,
+                    "Skipping an identity without a key"
+                ),
+            }
+        }
+
+        keys
+    }
+
+    /// Answer the requests of a client until it disconnects.
+    pub async fn serve<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        mut stream: S,
+    ) -> napi::Result<()> {
+        loop {
+            let mut length = [0u8; 4];
+            match stream.read_exact(&mut length).await {
+                Ok(_) => {}
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(e).map_napi_error(),
+            }
+
+            let length = u32::from_be_bytes(length) as usize;
+            if length == 0 || length > MAX_MESSAGE_LENGTH {
+                return Err(napi::Error::from_reason(format!(
+                    "The SSH agent request has an invalid length of {} bytes",
+                    length
+                )));
+            }
+
+            let mut request = vec![0u8; length];
+            stream.read_exact(&mut request).await.map_napi_error()?;
+
+            let mut response = SshWriter::default();
+            response.string(self.handle(&request).await);
+            stream
+                .write_all(&response.into_bytes())
+                .await
+                .map_napi_error()?;
+        }
+    }
+
+    async fn handle(&self, request: &[u8]) -> Vec<u8> {
+        let result = match request[0] {
+            SSH_AGENTC_REQUEST_IDENTITIES => Ok(self.identities_answer().await),
+            SSH_AGENTC_SIGN_REQUEST => self.sign(&request[1..]).await,
+            message_type => Err(napi::Error::from_reason(format!(
+                "The request type {} is not supported",
+                message_type
+            ))),
+        };
+
+        result.unwrap_or_else(|e| {
+            tracing::debug!(error = %e, "Failed to answer an SSH agent request");
+            vec![SSH_AGENT_FAILURE]
+        })
+    }
+
+    async fn identities_answer(&self) -> Vec<u8> {
+        let keys = self.public_keys().await;
+        let mut answer = SshWriter::default();
+        answer
+            .byte(SSH_AGENT_IDENTITIES_ANSWER)
+            .u32(keys.len() as u32);
+
+        for (identity, key) in keys {
+            answer.string(key).string(&identity.comment);
+        }
+
+        answer.into_bytes()
+    }
+
+    async fn sign(&self, request: &[u8]) -> napi::Result<Vec<u8>> {
+        let mut reader = SshReader::new(request);
+        let key = reader.string()?;
+        let data = reader.string()?;
+        let flags = reader.u32()?;
+
+        // Prefer SHA-512 if both flags are set, like OpenSSH
+        let (name, algorithm) = if flags & SSH_AGENT_RSA_SHA2_512 != 0 {
+            ("rsa-sha2-512", DigestAlgorithm::Sha512)
+        } else if flags & SSH_AGENT_RSA_SHA2_256 != 0 {
+            ("rsa-sha2-256", DigestAlgorithm::Sha256)
+        } else {
+            return Err(napi::Error::from_reason(
+                "ssh-rsa signatures using SHA-1 are not supported",
+            ));
+        };
+
+        let identity = self
+            .public_keys()
+            .await
+            .into_iter()
+            .find(|(_, blob)| blob == key)
+            .map(|(identity, _)| identity)
+            .ok_or_else(|| napi::Error::from_reason("The key is not served by this agent"))?;
+        check_signed_data(data, key, name)?;
+
+        let span = operation_span("sshSign", Some(&identity.account_id));
+        let result = identity
+            .backend
+            .sign_with_digest(&identity.account_id, data, algorithm)
+            .instrument(span.clone())
+            .await;
+        let fingerprint = key_fingerprint(&*identity.backend, &identity.account_id).await;
+        let signature = span.in_scope(|| {
+            record_result(
+                AuditEvent::new("sshSign")
+                    .account_id(&identity.account_id)
+                    .challenge(data)
+                    .key_fingerprint(fingerprint),
+                result,
+            )
+        })?;
+
+        let mut blob = SshWriter::default();
+        blob.string(name).string(signature);
+
+        let mut response = SshWriter::default();
+        response
+            .byte(SSH_AGENT_SIGN_RESPONSE)
+            .string(blob.into_bytes());
+
+        Ok(response.into_bytes())
+    }
+}
+
+/// Accept clients on a Unix socket until the task is aborted.
+/// Aborting the task disconnects the connected clients too.
+#[cfg(unix)]
+pub async fn listen_unix(listener: tokio::net::UnixListener, agent: Arc<Agent>) {
+    let mut clients = JoinSet::new();
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => spawn_client(&mut clients, stream, agent.clone()),
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to accept an SSH agent client");
+                return;
+            }
+        }
+    }
+}
+
+/// Accept clients on a named pipe until the task is aborted.
+/// Every client connects to its own instance of the pipe.
+/// Aborting the task disconnects the connected clients too.
+#[cfg(windows)]
+pub async fn listen_pipe(
+    path: String,
+    mut server: tokio::net::windows::named_pipe::NamedPipeServer,
+    agent: Arc<Agent>,
+) {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let mut clients = JoinSet::new();
+    loop {
+        if let Err(e) = server.connect().await {
+            tracing::warn!(error = %e, "Failed to accept an SSH agent client");
+            return;
+        }
+
+        let client = server;
+        server = match ServerOptions::new().create(&path) {
+            Ok(server) => server,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to create the next SSH agent pipe");
+                return;
+            }
+        };
+
+        spawn_client(&mut clients, client, agent.clone());
+    }
+}
+
+/// Serve a client in a task of `clients`, which are
+/// aborted once the set is dropped with the listener.
+fn spawn_client<S>(clients: &mut JoinSet<()>, stream: S, agent: Arc<Agent>)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    // Forget the clients which disconnected, so the set does not grow
+    while let Some(Some(_)) = clients.join_next().now_or_never() {}
+
+    clients.spawn(async move {
+        if let Err(e) = agent.serve(stream).await {
+            tracing::debug!(error = %e, "The SSH agent client failed");
+        }
+    });
+}
@@ -0,0 +1,135 @@
+use crate::util::mappers::MapNapiError;
+use base64ct::{Base64, Encoding};
+use rsa::pkcs1::DecodeRsaPublicKey;
+use rsa::traits::PublicKeyParts;
+use rsa::RsaPublicKey;
+
+pub(crate) mod agent;
+pub(crate) mod sshsig;
+
+/// The name of RSA public keys in the SSH protocol.
+pub const SSH_RSA: &str = "ssh-rsa";
+
+/// Writes values using the SSH wire encoding defined in RFC 4251.
+#[derive(Default)]
+pub struct SshWriter {
+    data: Vec<u8>,
+}
+
+impl SshWriter {
+    pub fn byte(&mut self, value: u8) -> &mut Self {
+        self.data.push(value);
+        self
+    }
+
+    pub fn u32(&mut self, value: u32) -> &mut Self {
+        self.data.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    /// Write a length-prefixed string.
+    pub fn string<T: AsRef<[u8]>>(&mut self, value: T) -> &mut Self {
+        let value = value.as_ref();
+        self.u32(value.len() as u32);
+        self.data.extend_from_slice(value);
+        self
+    }
+
+    /// Write an unsigned big-endian integer as a positive `mpint`.
+    pub fn mpint(&mut self, value: &[u8]) -> &mut Self {
+        let start = value.iter().position(|b| *b != 0).unwrap_or(value.len());
+        let value = &value[start..];
+
+        // A set high bit would make the value negative
+        if value.first().is_some_and(|b| b & 0x80 != 0) {
+            self.u32(value.len() as u32 + 1).byte(0);
+            self.data.extend_from_slice(value);
+            self
+        } else {
+            self.string(value)
+        }
+    }
+
+    /// Write raw bytes without a length prefix.
+    pub fn raw(&mut self, value: &[u8]) -> &mut Self {
+        self.data.extend_from_slice(value);
+        self
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+/// Reads values encoded using the SSH wire encoding,
+/// failing if the data ends before a value.
+pub struct SshReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> SshReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn take(&mut self, length: usize) -> napi::Result<&'a [u8]> {
+        if self.data.len() < length {
+            return Err(napi::Error::from_reason("The SSH message is truncated"));
+        }
+
+        let (value, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(value)
+    }
+
+    pub fn byte(&mut self) -> napi::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn u32(&mut self) -> napi::Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    /// Read a length-prefixed string.
+    pub fn string(&mut self) -> napi::Result<&'a [u8]> {
+        let length = self.u32()? as usize;
+        self.take(length)
+    }
+
+    /// Whether all data was read.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+/// Encode an RSA public key as SSH public key blob.
+pub fn rsa_public_key_blob(key: &RsaPublicKey) -> Vec<u8> {
+    let mut writer = SshWriter::default();
+    writer
+        .string(SSH_RSA)
+        .mpint(&key.e().to_bytes_be())
+        .mpint(&key.n().to_bytes_be());
+
+    writer.into_bytes()
+}
+
+/// Encode a PKCS#1 encoded RSA public key as SSH public key blob.
+pub fn pkcs1_to_public_key_blob(pkcs1: &[u8]) -> napi::Result<Vec<u8>> {
+    let key = RsaPublicKey::from_pkcs1_der(pkcs1).map_napi_error()?;
+    Ok(rsa_public_key_blob(&key))
+}
+
+/// Convert a PKCS#1 encoded RSA public key to an `authorized_keys` line.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn openssh_public_key(pkcs1: &[u8]) -> napi::Result<String> {
+    Ok(authorized_key(&pkcs1_to_public_key_blob(pkcs1)?, None))
+}
+
+/// Format an SSH public key blob as line of an `authorized_keys` file.
+pub fn authorized_key(blob: &[u8], comment: Option<&str>) -> String {
+    let key = format!("{} {}", SSH_RSA, Base64::encode_string(blob));
+    match comment {
+        Some(comment) if !comment.is_empty() => format!("{} {}", key, comment),
+        _ => key,
+    }
+}
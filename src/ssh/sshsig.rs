@@ -0,0 +1,91 @@
+use crate::ssh::{SshReader, SshWriter};
+use base64ct::{Base64, Encoding};
+use sha2::{Digest, Sha512};
+
+/// The magic preamble of signatures and signed data.
+const MAGIC: &[u8] = b"SSHSIG";
+/// The version of the signature format.
+const VERSION: u32 = 1;
+/// The algorithm hashing the message, the default of `ssh-keygen`.
+const HASH_ALGORITHM: &str = "sha512";
+/// The signature algorithm of passport keys.
+const SIGNATURE_ALGORITHM: &str = "rsa-sha2-256";
+/// The length of the lines of the armored signature.
+const LINE_LENGTH: usize = 70;
+
+/// The blob signed by the account key, as defined in `PROTOCOL.sshsig`.
+/// The namespace binds the signature to its purpose, like `git` or
+/// `file`, so it cannot be reused in another context.
+pub fn signed_data(namespace: &str, message: &[u8]) -> napi::Result<Vec<u8>> {
+    if namespace.is_empty() {
+        return Err(napi::Error::from_reason("The namespace must not be empty"));
+    }
+
+    let mut writer = SshWriter::default();
+    writer
+        .raw(MAGIC)
+        .string(namespace)
+        .string("")
+        .string(HASH_ALGORITHM)
+        .string(Sha512::digest(message));
+
+    Ok(writer.into_bytes())
+}
+
+/// Whether data is a blob like the ones returned by [`signed_data`],
+/// with any namespace and a SHA-256 or SHA-512 hash of the message.
+pub fn is_signed_data(data: &[u8]) -> bool {
+    let Some(data) = data.strip_prefix(MAGIC) else {
+        return false;
+    };
+
+    let mut reader = SshReader::new(data);
+    let blob = (|| {
+        let namespace = reader.string()?;
+        let _reserved = reader.string()?;
+        let hash_algorithm = reader.string()?;
+        let hash = reader.string()?;
+
+        napi::Result::Ok((namespace, hash_algorithm, hash))
+    })();
+
+    match blob {
+        Ok((namespace, hash_algorithm, hash)) => {
+            let hash_length = match hash_algorithm {
+                b"sha256" => 32,
+                b"sha512" => 64,
+                _ => return false,
+            };
+
+            !namespace.is_empty() && hash.len() == hash_length && reader.is_empty()
+        }
+        Err(_) => false,
+    }
+}
+
+/// Encode a signature over the data returned by [`signed_data`] as
+/// armored SSH signature, as written by `ssh-keygen -Y sign`.
+pub fn armor(public_key: &[u8], namespace: &str, signature: &[u8]) -> String {
+    let mut signature_blob = SshWriter::default();
+    signature_blob.string(SIGNATURE_ALGORITHM).string(signature);
+
+    let mut writer = SshWriter::default();
+    writer
+        .raw(MAGIC)
+        .u32(VERSION)
+        .string(public_key)
+        .string(namespace)
+        .string("")
+        .string(HASH_ALGORITHM)
+        .string(signature_blob.into_bytes());
+
+    let encoded = Base64::encode_string(&writer.into_bytes());
+    let mut armored = String::from("-----BEGIN SSH SIGNATURE-----\n");
+    for line in encoded.as_bytes().chunks(LINE_LENGTH) {
+        armored.push_str(std::str::from_utf8(line).unwrap());
+        armored.push('\n');
+    }
+
+    armored.push_str("-----END SSH SIGNATURE-----\n");
+    armored
+}
@@ -0,0 +1,316 @@
+use crate::node::audit_sink::{AuditEntry, AuditVerification};
+use crate::util::errors::OperationError;
+use crate::util::mappers::MapNapiError;
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use sha2::{Digest, Sha256};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The previous hash of the first entry in an audit log.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+static AUDIT_LOG: Mutex<Option<Arc<AuditLog>>> = Mutex::new(None);
+
+/// Set the audit log all passport operations are recorded to.
+pub fn set_audit_log(log: Option<Arc<AuditLog>>) {
+    *AUDIT_LOG.lock().unwrap() = log;
+}
+
+/// Whether an audit log is installed.
+pub fn enabled() -> bool {
+    AUDIT_LOG.lock().unwrap().is_some()
+}
+
+/// Record an event to the currently installed audit log, if any.
+/// Failing to write the entry is reported as an error, as silently
+/// dropping entries would defeat the purpose of the audit log.
+pub fn record(event: AuditEvent) -> napi::Result<()> {
+    let log = AUDIT_LOG.lock().unwrap().clone();
+    match log {
+        Some(log) => log.append(event),
+        None => Ok(()),
+    }
+}
+
+/// Record the outcome of an operation and pass the result on.
+/// The outcome is also recorded in the current tracing span.
+/// If the operation succeeded but the audit entry could not be
+/// written, the audit error is returned instead.
+pub fn record_result<T>(event: AuditEvent, result: Result<T, OperationError>) -> napi::Result<T> {
+    let outcome = match &result {
+        Ok(_) => "Success",
+        Err(e) => e.outcome.as_str(),
+    };
+    tracing::Span::current().record("outcome", outcome);
+    record_outcome(event, &result)?;
+
+    result.map_err(napi::Error::from)
+}
+
+/// Record the outcome of an operation without consuming its result.
+pub fn record_outcome<T>(
+    event: AuditEvent,
+    result: &Result<T, OperationError>,
+) -> napi::Result<()> {
+    match result {
+        Ok(_) => record(event.outcome("Success")),
+        Err(e) => record(
+            event
+                .outcome(e.outcome.as_str())
+                .error(e.error.reason.clone()),
+        ),
+    }
+}
+
+/// Hash some data for use in an audit entry.
+pub fn hash_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// An operation which should be written to the audit log.
+pub struct AuditEvent {
+    operation: String,
+    account_id: Option<String>,
+    outcome: String,
+    error: Option<String>,
+    challenge_hash: Option<String>,
+    key_fingerprint: Option<String>,
+}
+
+impl AuditEvent {
+    pub fn new(operation: &str) -> Self {
+        Self {
+            operation: operation.into(),
+            account_id: None,
+            outcome: "Success".into(),
+            error: None,
+            challenge_hash: None,
+            key_fingerprint: None,
+        }
+    }
+
+    pub fn account_id(mut self, account_id: &str) -> Self {
+        self.account_id = Some(account_id.into());
+        self
+    }
+
+    pub fn outcome<T: Into<String>>(mut self, outcome: T) -> Self {
+        self.outcome = outcome.into();
+        self
+    }
+
+    pub fn error(mut self, error: String) -> Self {
+        self.error = Some(error);
+        self
+    }
+
+    pub fn challenge(mut self, challenge: &[u8]) -> Self {
+        self.challenge_hash = Some(hash_hex(challenge));
+        self
+    }
+
+    pub fn key_fingerprint(mut self, fingerprint: Option<String>) -> Self {
+        self.key_fingerprint = fingerprint;
+        self
+    }
+}
+
+enum AuditTarget {
+    File(File),
+    Callback(ThreadsafeFunction<AuditEntry, ErrorStrategy::Fatal>),
+}
+
+struct AuditState {
+    target: AuditTarget,
+    sequence: i64,
+    last_hash: String,
+}
+
+/// A hash-chained audit log. Every entry contains the hash
+/// of the previous entry, so removing, reordering or changing
+/// entries can be detected using [`verify_entries`].
+pub struct AuditLog {
+    state: Mutex<AuditState>,
+}
+
+impl AuditLog {
+    /// Create an audit log writing JSON lines to a file.
+    /// If the file already contains entries, the hash chain
+    /// is continued from the last entry in the file.
+    pub fn file<P: AsRef<Path>>(path: P) -> napi::Result<Self> {
+        let path = path.as_ref();
+        let (sequence, last_hash) = match File::open(path) {
+            Ok(file) => match read_entries(file)? {
+                (_, Some(error)) => {
+                    return Err(napi::Error::from_reason(format!(
+                        "The audit log can't be continued: {}",
+                        error
+                    )))
+                }
+                (mut entries, None) => match entries.pop() {
+                    Some(last) => (last.sequence + 1, last.hash),
+                    None => (0, GENESIS_HASH.into()),
+                },
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (0, GENESIS_HASH.into()),
+            Err(e) => return Err(e).map_napi_error(),
+        };
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_napi_error()?;
+
+        Ok(Self::new(AuditTarget::File(file), sequence, last_hash))
+    }
+
+    /// Create an audit log passing every entry to a javascript callback.
+    pub fn callback(callback: ThreadsafeFunction<AuditEntry, ErrorStrategy::Fatal>) -> Self {
+        Self::new(AuditTarget::Callback(callback), 0, GENESIS_HASH.into())
+    }
+
+    fn new(target: AuditTarget, sequence: i64, last_hash: String) -> Self {
+        Self {
+            state: Mutex::new(AuditState {
+                target,
+                sequence,
+                last_hash,
+            }),
+        }
+    }
+
+    fn append(&self, event: AuditEvent) -> napi::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let mut entry = AuditEntry {
+            sequence: state.sequence,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as i64)
+                .unwrap_or_default(),
+            operation: event.operation,
+            account_id: event.account_id,
+            outcome: event.outcome,
+            error: event.error,
+            challenge_hash: event.challenge_hash,
+            key_fingerprint: event.key_fingerprint,
+            previous_hash: state.last_hash.clone(),
+            hash: String::new(),
+        };
+        entry.hash = entry_hash(&entry)?;
+
+        match &mut state.target {
+            AuditTarget::File(file) => {
+                let line = serde_json::to_string(&entry).map_napi_error()?;
+                writeln!(file, "{}", line)
+                    .and_then(|_| file.flush())
+                    .map_napi_error()?;
+            }
+            AuditTarget::Callback(callback) => {
+                callback.call(entry.clone(), ThreadsafeFunctionCallMode::NonBlocking);
+            }
+        }
+
+        state.sequence += 1;
+        state.last_hash = entry.hash;
+        Ok(())
+    }
+}
+
+/// Calculate the hash of an entry. The hash covers the JSON
+/// serialization of the entry with an empty `hash` field,
+/// which includes the hash of the previous entry.
+fn entry_hash(entry: &AuditEntry) -> napi::Result<String> {
+    let unhashed = AuditEntry {
+        hash: String::new(),
+        ..entry.clone()
+    };
+
+    serde_json::to_vec(&unhashed)
+        .map(|json| hash_hex(&json))
+        .map_napi_error()
+}
+
+/// Read the entries of a JSON lines file. Reading stops at the first
+/// line which is not an entry, which is returned as error message,
+/// as a modified line must be reported instead of failing to verify.
+fn read_entries(file: File) -> napi::Result<(Vec<AuditEntry>, Option<String>)> {
+    let mut entries = vec![];
+    for (i, line) in BufReader::new(file).split(b'\n').enumerate() {
+        let line = line.map_napi_error()?;
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+
+        match serde_json::from_slice(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => {
+                let error = format!("Line {} is not a valid audit entry: {}", i + 1, e);
+                return Ok((entries, Some(error)));
+            }
+        }
+    }
+
+    Ok((entries, None))
+}
+
+/// Verify the audit log stored in a JSON lines file.
+pub fn verify_file<P: AsRef<Path>>(path: P) -> napi::Result<AuditVerification> {
+    let file = File::open(path).map_napi_error()?;
+    let (entries, error) = read_entries(file)?;
+
+    let verification = verify_entries(&entries);
+    Ok(match error {
+        Some(error) if verification.valid => AuditVerification {
+            valid: false,
+            invalid_sequence: Some(verification.entries as i64),
+            error: Some(error),
+            ..verification
+        },
+        _ => verification,
+    })
+}
+
+/// Verify that a list of entries forms an unbroken hash chain,
+/// starting at the first entry of the log.
+pub fn verify_entries(entries: &[AuditEntry]) -> AuditVerification {
+    let mut previous_hash = GENESIS_HASH.to_string();
+
+    for (i, entry) in entries.iter().enumerate() {
+        let error = if entry.sequence != i as i64 {
+            Some(format!(
+                "Expected sequence number {}, got {}",
+                i, entry.sequence
+            ))
+        } else if entry.previous_hash != previous_hash {
+            Some("The previous hash does not match the previous entry".to_string())
+        } else {
+            match entry_hash(entry) {
+                Ok(hash) if hash == entry.hash => None,
+                Ok(_) => Some("The entry hash does not match its contents".to_string()),
+                Err(e) => Some(e.reason),
+            }
+        };
+
+        if let Some(error) = error {
+            return AuditVerification {
+                valid: false,
+                entries: i as u32,
+                invalid_sequence: Some(i as i64),
+                error: Some(error),
+            };
+        }
+
+        previous_hash = entry.hash.clone();
+    }
+
+    AuditVerification {
+        valid: true,
+        entries: entries.len() as u32,
+        invalid_sequence: None,
+        error: None,
+    }
+}
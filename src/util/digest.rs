@@ -0,0 +1,73 @@
+use crate::node::digest_algorithm::DigestAlgorithm;
+use crate::util::mappers::MapNapiError;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::fs::File;
+use std::io::{ErrorKind, Read};
+use std::path::PathBuf;
+
+/// The size of the chunks files are read in.
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// An incremental hash using one of the supported digest algorithms.
+pub enum Hasher {
+    Sha256(Sha256),
+    Sha384(Sha384),
+    Sha512(Sha512),
+}
+
+impl Hasher {
+    pub fn new(algorithm: DigestAlgorithm) -> Self {
+        match algorithm {
+            DigestAlgorithm::Sha256 => Self::Sha256(Sha256::new()),
+            DigestAlgorithm::Sha384 => Self::Sha384(Sha384::new()),
+            DigestAlgorithm::Sha512 => Self::Sha512(Sha512::new()),
+        }
+    }
+
+    pub fn algorithm(&self) -> DigestAlgorithm {
+        match self {
+            Self::Sha256(_) => DigestAlgorithm::Sha256,
+            Self::Sha384(_) => DigestAlgorithm::Sha384,
+            Self::Sha512(_) => DigestAlgorithm::Sha512,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha256(h) => h.update(data),
+            Self::Sha384(h) => h.update(data),
+            Self::Sha512(h) => h.update(data),
+        }
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        match self {
+            Self::Sha256(h) => h.finalize().to_vec(),
+            Self::Sha384(h) => h.finalize().to_vec(),
+            Self::Sha512(h) => h.finalize().to_vec(),
+        }
+    }
+}
+
+/// Hash a file without loading it into memory.
+/// The file is read on the blocking thread pool.
+pub async fn hash_file(path: PathBuf, algorithm: DigestAlgorithm) -> napi::Result<Vec<u8>> {
+    tokio::task::spawn_blocking(move || {
+        let mut file = File::open(path).map_napi_error()?;
+        let mut hasher = Hasher::new(algorithm);
+        let mut buf = vec![0u8; CHUNK_SIZE];
+
+        loop {
+            match file.read(&mut buf) {
+                Ok(0) => break,
+                Ok(read) => hasher.update(&buf[..read]),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e).map_napi_error(),
+            }
+        }
+
+        Ok(hasher.finalize())
+    })
+    .await
+    .map_napi_error()?
+}
@@ -1,13 +1,18 @@
+use crate::node::verification_result::VerificationResult;
+#[cfg(windows)]
 use windows::Security::Credentials::KeyCredentialStatus;
 
+#[cfg_attr(not(windows), allow(dead_code))]
 pub trait PassportError {
     fn user_cancelled() -> Self;
     fn not_found() -> Self;
     fn user_prefers_password() -> Self;
     fn credential_already_exists() -> Self;
     fn security_device_locked() -> Self;
+    #[cfg(windows)]
     fn unknown(status: KeyCredentialStatus) -> Self;
 
+    #[cfg(windows)]
     fn from_credential_status(status: KeyCredentialStatus) -> Self;
 }
 
@@ -44,6 +49,7 @@ impl PassportError for napi::Error {
         )
     }
 
+    #[cfg(windows)]
     fn unknown(status: KeyCredentialStatus) -> Self {
         napi::Error::new(
             napi::Status::GenericFailure,
@@ -51,7 +57,13 @@ impl PassportError for napi::Error {
         )
     }
 
+    #[cfg(windows)]
     fn from_credential_status(status: KeyCredentialStatus) -> Self {
+        tracing::warn!(
+            status = credential_status_name(status),
+            "The key credential operation failed"
+        );
+
         match status {
             KeyCredentialStatus::Success => napi::Error::new(
                 napi::Status::GenericFailure,
@@ -66,3 +78,114 @@ impl PassportError for napi::Error {
         }
     }
 }
+
+/// Get the name of a [`KeyCredentialStatus`] as it is
+/// recorded in the audit log.
+#[cfg(windows)]
+pub fn credential_status_name(status: KeyCredentialStatus) -> String {
+    match status {
+        KeyCredentialStatus::Success => "Success".into(),
+        KeyCredentialStatus::UnknownError => "UnknownError".into(),
+        KeyCredentialStatus::NotFound => "NotFound".into(),
+        KeyCredentialStatus::UserCanceled => "UserCanceled".into(),
+        KeyCredentialStatus::UserPrefersPassword => "UserPrefersPassword".into(),
+        KeyCredentialStatus::CredentialAlreadyExists => "CredentialAlreadyExists".into(),
+        KeyCredentialStatus::SecurityDeviceLocked => "SecurityDeviceLocked".into(),
+        s => format!("Unknown({})", s.0),
+    }
+}
+
+/// An error which remembers the outcome of the operation
+/// that failed, so it can be written to the audit log
+/// before being passed on to javascript.
+pub struct OperationError {
+    pub outcome: String,
+    pub error: napi::Error,
+}
+
+impl OperationError {
+    #[cfg(windows)]
+    pub fn from_credential_status(status: KeyCredentialStatus) -> Self {
+        Self {
+            outcome: credential_status_name(status),
+            error: napi::Error::from_credential_status(status),
+        }
+    }
+
+    /// The account does not exist in the backend.
+    pub fn not_found() -> Self {
+        Self {
+            outcome: "NotFound".into(),
+            error: napi::Error::new(
+                napi::Status::GenericFailure,
+                "The passport account does not exist",
+            ),
+        }
+    }
+
+    /// The user canceled a prompt of the backend.
+    pub fn user_canceled() -> Self {
+        Self {
+            outcome: "UserCanceled".into(),
+            error: napi::Error::user_cancelled(),
+        }
+    }
+
+    /// The user could not be verified before using a key.
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    pub fn not_verified(result: VerificationResult) -> Self {
+        match result {
+            VerificationResult::Canceled => Self::user_canceled(),
+            result => Self {
+                outcome: result.name().into(),
+                error: napi::Error::new(
+                    napi::Status::GenericFailure,
+                    format!("The user could not be verified: {}", result.name()),
+                ),
+            },
+        }
+    }
+
+    /// The account already exists in the backend.
+    pub fn already_exists() -> Self {
+        Self {
+            outcome: "CredentialAlreadyExists".into(),
+            error: napi::Error::credential_already_exists(),
+        }
+    }
+
+    /// Whether the user declined to verify their identity.
+    pub fn declined_by_user(&self) -> bool {
+        matches!(
+            self.outcome.as_str(),
+            "UserCanceled" | "UserPrefersPassword"
+        )
+    }
+
+    /// Create the error for an item of a batch which was not
+    /// attempted, because this error already occurred.
+    pub fn skipped(&self) -> Self {
+        Self {
+            outcome: self.outcome.clone(),
+            error: napi::Error::new(
+                napi::Status::GenericFailure,
+                format!("Skipped: {}", self.error.reason),
+            ),
+        }
+    }
+}
+
+impl From<napi::Error> for OperationError {
+    fn from(error: napi::Error) -> Self {
+        Self {
+            outcome: "Error".into(),
+            error,
+        }
+    }
+}
+
+impl From<OperationError> for napi::Error {
+    fn from(value: OperationError) -> Self {
+        value.error
+    }
+}
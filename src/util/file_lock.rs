@@ -0,0 +1,25 @@
+use crate::util::mappers::MapNapiError;
+use std::fs::{File, OpenOptions};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+/// Lock a lock file, creating it if it does not exist. The lock
+/// is released once the returned file is dropped. Locks belong
+/// to the opened file, so they exclude other processes as well
+/// as other handles opened by this process.
+pub fn lock(path: &Path, exclusive: bool) -> napi::Result<File> {
+    let mut options = OpenOptions::new();
+    options.create(true).truncate(false).write(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let file = options.open(path).map_napi_error()?;
+    if exclusive {
+        file.lock().map_napi_error()?;
+    } else {
+        file.lock_shared().map_napi_error()?;
+    }
+
+    Ok(file)
+}
@@ -0,0 +1,234 @@
+use crate::node::logger::{LogLevel, LogRecord};
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::Write;
+use std::sync::{Mutex, Once, RwLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::{Event, Level, Metadata, Span, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{Layer, Registry};
+
+static LOG_SINK: RwLock<Option<LogSink>> = RwLock::new(None);
+static INIT: Once = Once::new();
+
+pub enum LogTarget {
+    File(Mutex<File>),
+    Callback(ThreadsafeFunction<LogRecord, ErrorStrategy::Fatal>),
+}
+
+/// Where log records are written to and which
+/// records are written.
+pub struct LogSink {
+    pub target: LogTarget,
+    pub level: Level,
+}
+
+/// Route all tracing events to a sink, or stop
+/// logging if `None` is passed. The tracing subscriber
+/// is installed the first time a sink is set.
+pub fn set_log_sink(sink: Option<LogSink>) {
+    INIT.call_once(|| {
+        // If the process already has a subscriber,
+        // there is nothing we can do about it
+        let _ = tracing::subscriber::set_global_default(Registry::default().with(LogLayer));
+    });
+
+    *LOG_SINK.write().unwrap() = sink;
+}
+
+/// Create the span wrapping a single passport operation.
+/// The `outcome` field is recorded once the operation finished.
+pub fn operation_span(operation: &'static str, account_id: Option<&str>) -> Span {
+    tracing::info_span!(
+        "passport",
+        operation,
+        account_id,
+        outcome = tracing::field::Empty
+    )
+}
+
+fn write_record(record: LogRecord) {
+    let sink = LOG_SINK.read().unwrap();
+    let Some(sink) = sink.as_ref() else {
+        return;
+    };
+
+    match &sink.target {
+        LogTarget::File(file) => {
+            let line = serde_json::json!({
+                "timestamp": record.timestamp,
+                "level": record.level.name(),
+                "target": record.target,
+                "message": record.message,
+                "spans": record.spans,
+                "fields": record.fields,
+                "durationMs": record.duration_ms,
+            });
+
+            // There is nowhere left to report a failure to write a log record to
+            let _ = writeln!(file.lock().unwrap(), "{}", line);
+        }
+        LogTarget::Callback(callback) => {
+            callback.call(record, ThreadsafeFunctionCallMode::NonBlocking);
+        }
+    }
+}
+
+fn unix_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[derive(Default)]
+struct FieldVisitor(BTreeMap<String, String>);
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.insert(field.name().into(), format!("{:?}", value));
+    }
+}
+
+struct SpanData {
+    fields: BTreeMap<String, String>,
+    start: Instant,
+}
+
+/// A tracing layer writing events and span durations to the
+/// current [`LogSink`]. The sink may be changed at any time,
+/// thus no interest is cached for callsites.
+struct LogLayer;
+
+impl<S> Layer<S> for LogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn register_callsite(&self, _metadata: &'static Metadata<'static>) -> Interest {
+        Interest::sometimes()
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, _ctx: Context<'_, S>) -> bool {
+        LOG_SINK
+            .read()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|sink| *metadata.level() <= sink.level)
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanData {
+                fields: visitor.0,
+                start: Instant::now(),
+            });
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(data) = span.extensions_mut().get_mut::<SpanData>() {
+                let mut visitor = FieldVisitor(std::mem::take(&mut data.fields));
+                values.record(&mut visitor);
+                data.fields = visitor.0;
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut spans = vec![];
+        let mut fields = BTreeMap::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                spans.push(span.name().to_string());
+                if let Some(data) = span.extensions().get::<SpanData>() {
+                    fields.extend(data.fields.clone());
+                }
+            }
+        }
+
+        let mut visitor = FieldVisitor(fields);
+        event.record(&mut visitor);
+        let message = visitor.0.remove("message").unwrap_or_default();
+
+        write_record(LogRecord {
+            timestamp: unix_millis(),
+            level: (*event.metadata().level()).into(),
+            target: event.metadata().target().into(),
+            message,
+            spans,
+            fields: HashMap::from_iter(visitor.0),
+            duration_ms: None,
+        });
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(data) = span.extensions_mut().remove::<SpanData>() else {
+            return;
+        };
+
+        write_record(LogRecord {
+            timestamp: unix_millis(),
+            level: (*span.metadata().level()).into(),
+            target: span.metadata().target().into(),
+            message: format!(
+                "{} finished",
+                data.fields
+                    .get("operation")
+                    .map(String::as_str)
+                    .unwrap_or(span.name())
+            ),
+            spans: span
+                .scope()
+                .from_root()
+                .map(|s| s.name().to_string())
+                .collect(),
+            fields: HashMap::from_iter(data.fields),
+            duration_ms: Some(data.start.elapsed().as_secs_f64() * 1000.0),
+        });
+    }
+}
+
+impl From<Level> for LogLevel {
+    fn from(value: Level) -> Self {
+        if value == Level::ERROR {
+            Self::Error
+        } else if value == Level::WARN {
+            Self::Warn
+        } else if value == Level::INFO {
+            Self::Info
+        } else if value == Level::DEBUG {
+            Self::Debug
+        } else {
+            Self::Trace
+        }
+    }
+}
+
+impl From<LogLevel> for Level {
+    fn from(value: LogLevel) -> Self {
+        match value {
+            LogLevel::Error => Self::ERROR,
+            LogLevel::Warn => Self::WARN,
+            LogLevel::Info => Self::INFO,
+            LogLevel::Debug => Self::DEBUG,
+            LogLevel::Trace => Self::TRACE,
+        }
+    }
+}
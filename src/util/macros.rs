@@ -5,7 +5,8 @@ macro_rules! check_account_exists {
             return Err(napi::Error::new(
                 napi::Status::GenericFailure,
                 "The passport account does not exist",
-            ));
+            )
+            .into());
         }
     };
 }
@@ -4,8 +4,22 @@ pub trait MapNapiError<T> {
     fn map_napi_error(self) -> napi::Result<T>;
 }
 
-impl<T, U: Error> MapNapiError<T> for Result<T, U> {
+impl<T, U: Error + 'static> MapNapiError<T> for Result<T, U> {
     fn map_napi_error(self) -> napi::Result<T> {
-        self.map_err(|e| napi::Error::from_reason(e.to_string()))
+        self.map_err(|e| {
+            // Keep the HRESULT of windows errors, as the message
+            // alone is often not enough to find out what went wrong
+            #[cfg(windows)]
+            if let Some(win_error) = (&e as &dyn Error).downcast_ref::<windows::core::Error>() {
+                let hresult = format!("{:#010X}", win_error.code().0);
+                tracing::debug!(hresult, "{}", e);
+                return napi::Error::from_reason(format!("{} (HRESULT {})", e, hresult));
+            }
+
+            // Many errors are expected, e.g. canceled prompts or missing
+            // accounts, so the callers decide which ones are logged as errors
+            tracing::debug!("{}", e);
+            napi::Error::from_reason(e.to_string())
+        })
     }
 }
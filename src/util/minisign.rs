@@ -0,0 +1,150 @@
+use crate::util::vault::key_fingerprint;
+use base64ct::{Base64, Encoding};
+use rsa::pkcs1::DecodeRsaPublicKey;
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+use rsa::signature::Verifier;
+use rsa::RsaPublicKey;
+use sha2::{Digest, Sha256, Sha512};
+
+/// The signature algorithm of passport keys, RSASSA-PKCS1-v1_5 using SHA-256.
+const ALGORITHM: &[u8; 2] = b"RS";
+/// The length of a key ID, the fingerprint of the account key.
+const KEY_ID_LENGTH: usize = 32;
+
+const UNTRUSTED_COMMENT: &str = "untrusted comment: ";
+const TRUSTED_COMMENT: &str = "trusted comment: ";
+
+fn invalid(reason: &str) -> napi::Error {
+    napi::Error::from_reason(format!("The signature is invalid: {}", reason))
+}
+
+fn check_comment(comment: &str) -> napi::Result<()> {
+    if comment.contains(['\r', '\n']) {
+        return Err(napi::Error::from_reason(
+            "The comment must not contain line breaks",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Encode a public key in the format modelled after minisign: an
+/// untrusted comment, followed by the base64 encoded algorithm,
+/// key ID and PKCS#1 encoded public key.
+pub fn public_key(pkcs1: &[u8]) -> String {
+    let key_id = key_fingerprint(pkcs1);
+    let mut blob = ALGORITHM.to_vec();
+    blob.extend_from_slice(&key_id);
+    blob.extend_from_slice(pkcs1);
+
+    format!(
+        "{}passport public key {}\n{}\n",
+        UNTRUSTED_COMMENT,
+        hex::encode_upper(key_id),
+        Base64::encode_string(&blob)
+    )
+}
+
+/// The data the account key signs: the algorithm, the key ID, the
+/// SHA-512 hash of the data and the trusted comment. Unlike minisign,
+/// there is no separate signature over the trusted comment, so the
+/// user only has to verify their identity once.
+pub fn signed_data(
+    key_id: &[u8; KEY_ID_LENGTH],
+    data: &[u8],
+    trusted_comment: &str,
+) -> napi::Result<Vec<u8>> {
+    check_comment(trusted_comment)?;
+
+    let mut signed = ALGORITHM.to_vec();
+    signed.extend_from_slice(key_id);
+    signed.extend_from_slice(&Sha512::digest(data));
+    signed.extend_from_slice(trusted_comment.as_bytes());
+    Ok(signed)
+}
+
+/// Encode a signature over the data returned by [`signed_data`]: an
+/// untrusted comment, the base64 encoded algorithm, key ID and
+/// signature, and the trusted comment.
+pub fn signature(key_id: &[u8; KEY_ID_LENGTH], trusted_comment: &str, signature: &[u8]) -> String {
+    let mut blob = ALGORITHM.to_vec();
+    blob.extend_from_slice(key_id);
+    blob.extend_from_slice(signature);
+
+    format!(
+        "{}signature from passport key {}\n{}\n{}{}\n",
+        UNTRUSTED_COMMENT,
+        hex::encode_upper(key_id),
+        Base64::encode_string(&blob),
+        TRUSTED_COMMENT,
+        trusted_comment
+    )
+}
+
+/// Split the lines of a public key or signature, requiring the
+/// untrusted comment in the first line, and decode the blob in
+/// the second line. Returns the blob and the remaining lines.
+fn decode<'a>(text: &'a str, name: &str) -> napi::Result<(Vec<u8>, Vec<&'a str>)> {
+    let lines = text.lines().collect::<Vec<_>>();
+    let malformed = || invalid(&format!("the {} is malformed", name));
+    match lines.as_slice() {
+        [comment, blob, rest @ ..] if comment.starts_with(UNTRUSTED_COMMENT) => {
+            let blob = Base64::decode_vec(blob.trim_end()).map_err(|_| malformed())?;
+            if blob.len() < ALGORITHM.len() + KEY_ID_LENGTH {
+                return Err(malformed());
+            }
+            if &blob[..ALGORITHM.len()] != ALGORITHM {
+                return Err(invalid(&format!(
+                    "the {} does not use the algorithm of passport keys",
+                    name
+                )));
+            }
+
+            Ok((blob[ALGORITHM.len()..].to_vec(), rest.to_vec()))
+        }
+        _ => Err(malformed()),
+    }
+}
+
+/// A verified signature.
+pub struct Verified {
+    pub key_id: [u8; KEY_ID_LENGTH],
+    pub trusted_comment: String,
+}
+
+/// Verify a signature created by [`signature`] using
+/// a public key encoded using [`public_key`].
+pub fn verify(public_key: &str, signature: &str, data: &[u8]) -> napi::Result<Verified> {
+    let (key, _) = decode(public_key, "public key")?;
+    let (key_id, pkcs1) = key.split_at(KEY_ID_LENGTH);
+    let key_id: [u8; KEY_ID_LENGTH] = key_id.try_into().unwrap();
+    if key_fingerprint(pkcs1) != key_id {
+        return Err(invalid("the key ID does not match the public key"));
+    }
+
+    let (blob, rest) = decode(signature, "signature")?;
+    let (signature_key_id, value) = blob.split_at(KEY_ID_LENGTH);
+    if signature_key_id != key_id {
+        return Err(invalid("the signature was created by another key"));
+    }
+
+    let trusted_comment = match rest.as_slice() {
+        [comment] => comment
+            .trim_end_matches('\r')
+            .strip_prefix(TRUSTED_COMMENT)
+            .ok_or_else(|| invalid("the trusted comment is missing"))?,
+        _ => return Err(invalid("the trusted comment is missing")),
+    };
+
+    let key =
+        RsaPublicKey::from_pkcs1_der(pkcs1).map_err(|_| invalid("the public key is malformed"))?;
+    let value = Signature::try_from(value).map_err(|_| invalid("the signature is malformed"))?;
+    VerifyingKey::<Sha256>::new(key)
+        .verify(&signed_data(&key_id, data, trusted_comment)?, &value)
+        .map_err(|_| invalid("the signature does not match the data"))?;
+
+    Ok(Verified {
+        key_id,
+        trusted_comment: trusted_comment.to_string(),
+    })
+}
@@ -1,4 +1,14 @@
+pub(crate) mod audit;
+pub(crate) mod digest;
 pub(crate) mod errors;
+pub(crate) mod file_lock;
+pub(crate) mod logging;
 pub(crate) mod macros;
 pub(crate) mod mappers;
+pub(crate) mod minisign;
+pub(crate) mod otp;
+pub(crate) mod session;
+pub(crate) mod token_file;
+#[cfg(windows)]
 pub(crate) mod traits;
+pub(crate) mod vault;
@@ -0,0 +1,66 @@
+use crate::node::otp_algorithm::OtpAlgorithm;
+use hmac::digest::KeyInit;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+
+/// The alphabet of base32 encoded secrets, defined in RFC 4648.
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Decode a base32 encoded secret as shown by authenticator setup
+/// pages. Case, spaces, dashes and padding are ignored.
+pub fn decode_base32(secret: &str) -> napi::Result<Vec<u8>> {
+    let mut decoded = vec![];
+    let mut buffer = 0u64;
+    let mut bits = 0;
+    for c in secret.chars().filter(|c| !matches!(c, ' ' | '-' | '=')) {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|a| *a as char == c.to_ascii_uppercase())
+            .ok_or_else(|| napi::Error::from_reason("The secret is not a valid base32 string"))?;
+
+        buffer = (buffer << 5) | value as u64;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+
+    if decoded.is_empty() {
+        return Err(napi::Error::from_reason("The secret must not be empty"));
+    }
+
+    Ok(decoded)
+}
+
+fn hmac<M: Mac + KeyInit>(secret: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = <M as KeyInit>::new_from_slice(secret).unwrap();
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Generate an HOTP code as defined in RFC 4226.
+pub fn hotp(secret: &[u8], counter: u64, algorithm: OtpAlgorithm, digits: u32) -> String {
+    let message = counter.to_be_bytes();
+    let hash = match algorithm {
+        OtpAlgorithm::Sha1 => hmac::<Hmac<Sha1>>(secret, &message),
+        OtpAlgorithm::Sha256 => hmac::<Hmac<Sha256>>(secret, &message),
+        OtpAlgorithm::Sha512 => hmac::<Hmac<Sha512>>(secret, &message),
+    };
+
+    // Dynamic truncation
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let code = u32::from_be_bytes(hash[offset..offset + 4].try_into().unwrap()) & 0x7fff_ffff;
+    format!(
+        "{:0width$}",
+        code as u64 % 10u64.pow(digits),
+        width = digits as usize
+    )
+}
+
+/// Generate a TOTP code as defined in RFC 6238, for a time
+/// in seconds since the unix epoch and a period in seconds.
+pub fn totp(secret: &[u8], time: u64, period: u64, algorithm: OtpAlgorithm, digits: u32) -> String {
+    hotp(secret, time / period, algorithm, digits)
+}
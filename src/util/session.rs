@@ -0,0 +1,136 @@
+use crate::backend::software::SoftwareKey;
+use crate::node::public_key_encoding::PublicKeyEncoding;
+use crate::node::session::{SessionInfo, SessionOptions};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// The start of every session statement. A signature over data starting
+/// with it endorses a session key, so it must only be created by
+/// [`PendingSession`], which chose the key.
+pub const STATEMENT_PREFIX: &[u8] = b"passport-desktop session v1\n";
+
+/// Fail if the data is a session statement, so nobody
+/// but this crate can get a session key endorsed.
+pub fn refuse_statement(data: &[u8]) -> napi::Result<()> {
+    if data.starts_with(STATEMENT_PREFIX) {
+        return Err(napi::Error::from_reason(
+            "Session statements are only signed to unlock a session",
+        ));
+    }
+
+    Ok(())
+}
+
+/// A signing session unlocked by a single signature of the account key.
+/// The session key is dropped, and with it zeroized, once the session
+/// is locked, so it cannot be used beyond the limits of the session.
+pub struct Session {
+    key: SoftwareKey,
+    public_key: Vec<u8>,
+    statement: Vec<u8>,
+    endorsement: Vec<u8>,
+    expires: Instant,
+    expires_at: i64,
+    max_signatures: u32,
+    remaining_signatures: u32,
+}
+
+/// A session which still needs to be endorsed by the account key.
+pub struct PendingSession {
+    key: SoftwareKey,
+    public_key: Vec<u8>,
+    expires: Instant,
+    expires_at: i64,
+    max_signatures: u32,
+    /// The statement which must be signed by the account key.
+    pub statement: Vec<u8>,
+}
+
+impl PendingSession {
+    /// Generate a session key and the statement binding it to an account.
+    pub async fn new(account_id: &str, options: &SessionOptions) -> napi::Result<Self> {
+        if options.duration_seconds == 0 || options.max_signatures == 0 {
+            return Err(napi::Error::from_reason(
+                "The session duration and the maximum number of signatures must not be zero",
+            ));
+        }
+
+        let key = SoftwareKey::generate_async().await?;
+        let public_key = key.public_key(PublicKeyEncoding::Pkcs1RsaPublicKey)?;
+        let duration = Duration::from_secs(options.duration_seconds as u64);
+        let expires_at = SystemTime::now()
+            .checked_add(duration)
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or_default();
+
+        let mut statement = STATEMENT_PREFIX.to_vec();
+        statement.extend_from_slice(
+            format!(
+                "account: {}\nexpires: {}\nsignatures: {}\nkey: {}\n",
+                account_id,
+                expires_at,
+                options.max_signatures,
+                hex::encode(&public_key)
+            )
+            .as_bytes(),
+        );
+
+        Ok(Self {
+            key,
+            public_key,
+            expires: Instant::now() + duration,
+            expires_at,
+            max_signatures: options.max_signatures,
+            statement,
+        })
+    }
+
+    /// Unlock the session using the signature of the account key over the statement.
+    pub fn endorse(self, endorsement: Vec<u8>) -> Session {
+        Session {
+            key: self.key,
+            public_key: self.public_key,
+            statement: self.statement,
+            endorsement,
+            expires: self.expires,
+            expires_at: self.expires_at,
+            max_signatures: self.max_signatures,
+            remaining_signatures: self.max_signatures,
+        }
+    }
+}
+
+impl Session {
+    /// Whether the session has neither expired nor run out of signatures.
+    pub fn is_valid(&self) -> bool {
+        self.remaining_signatures > 0 && Instant::now() < self.expires
+    }
+
+    /// Sign data using the session key, if the session is still valid.
+    pub fn sign(&mut self, data: &[u8]) -> napi::Result<Vec<u8>> {
+        if !self.is_valid() {
+            return Err(napi::Error::from_reason(
+                "The session has expired or no signatures are left",
+            ));
+        }
+
+        self.remaining_signatures -= 1;
+        self.key.sign(data)
+    }
+
+    /// The PKCS#1 encoded public key of the session.
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    pub fn info(&self) -> SessionInfo {
+        SessionInfo {
+            public_key: self.public_key.clone().into(),
+            statement: self.statement.clone().into(),
+            endorsement: self.endorsement.clone().into(),
+            expires_at: self.expires_at,
+            max_signatures: self.max_signatures,
+            remaining_signatures: self.remaining_signatures,
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use crate::util::mappers::MapNapiError;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+/// Write an envelope containing a token or secrets, replacing the previous
+/// file at once, so a crash cannot leave a partially written file behind.
+/// The file is only readable by the current user.
+pub fn write(path: &Path, envelope: &[u8]) -> napi::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+
+    // A file left behind by a crash may be readable by others,
+    // so it is removed and the file is always created anew
+    match fs::remove_file(&tmp) {
+        Err(e) if e.kind() != ErrorKind::NotFound => return Err(e).map_napi_error(),
+        _ => {}
+    }
+
+    let mut options = OpenOptions::new();
+    options.create_new(true).write(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let mut out = options.open(&tmp).map_napi_error()?;
+    out.write_all(envelope)
+        .and_then(|_| out.sync_all())
+        .map_napi_error()?;
+
+    fs::rename(tmp, path).map_napi_error()
+}
+
+/// Read an envelope written using [`write`].
+pub fn read(path: &Path) -> napi::Result<Vec<u8>> {
+    fs::read(path).map_err(|e| {
+        napi::Error::from_reason(format!(
+            "The token file '{}' could not be read: {}",
+            path.display(),
+            e
+        ))
+    })
+}
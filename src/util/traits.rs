@@ -9,9 +9,9 @@ pub trait IntoWinBuffer {
         Self: Sized;
 }
 
-impl IntoWinBuffer for napi::bindgen_prelude::Buffer {
+impl IntoWinBuffer for Vec<u8> {
     fn into_win_buffer(self) -> windows::core::Result<IBuffer> {
-        CryptographicBuffer::CreateFromByteArray(self.as_ref())
+        CryptographicBuffer::CreateFromByteArray(&self)
     }
 
     fn from_win_buffer(buffer: IBuffer) -> windows::core::Result<Self>
@@ -20,6 +20,6 @@ impl IntoWinBuffer for napi::bindgen_prelude::Buffer {
     {
         let mut buf = Array::<u8>::with_len(buffer.Length()? as usize);
         CryptographicBuffer::CopyToByteArray(&buffer, &mut buf)?;
-        Ok(napi::bindgen_prelude::Buffer::from(buf.to_vec()))
+        Ok(buf.to_vec())
     }
 }
@@ -0,0 +1,270 @@
+use crate::util::mappers::MapNapiError;
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use hkdf::Hkdf;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+/// The magic bytes every envelope starts with.
+const MAGIC: &[u8; 4] = b"PPVT";
+/// The first version, without the account ID and key fingerprint.
+const VERSION_1: u8 = 1;
+/// The version written by [`Envelope::seal`].
+const VERSION_2: u8 = 2;
+/// The length of the random HKDF salt.
+const SALT_LENGTH: usize = 32;
+/// The length of an AES-GCM nonce.
+const NONCE_LENGTH: usize = 12;
+/// The length of a key fingerprint, a SHA-256 hash.
+const FINGERPRINT_LENGTH: usize = 32;
+/// The HKDF info binding the derived keys to their purpose.
+const KEY_INFO: &[u8] = b"passport-desktop vault key v1";
+
+/// The start of every vault challenge. Signatures over data starting
+/// with it must never be handed out, as they are vault keys.
+pub const CHALLENGE_PREFIX: &[u8] = b"passport-desktop vault v1\n";
+
+/// Fail if the data is a vault challenge, so it
+/// is not signed for anyone but the vault itself.
+pub fn refuse_challenge(data: &[u8]) -> napi::Result<()> {
+    if data.starts_with(CHALLENGE_PREFIX) {
+        return Err(napi::Error::from_reason(
+            "Vault challenges are only signed to derive the vault key",
+        ));
+    }
+
+    Ok(())
+}
+
+/// The challenge signed by the account key to derive the vault key.
+/// PKCS#1 v1.5 signatures are deterministic, so signing the same
+/// challenge with the same key always yields the same key material.
+pub fn vault_challenge(account_id: &str) -> Vec<u8> {
+    let mut challenge = CHALLENGE_PREFIX.to_vec();
+    challenge.extend_from_slice(format!("account: {}\n", account_id).as_bytes());
+    challenge
+}
+
+/// The account the new key is created for while rewrapping envelopes,
+/// until it replaces the key of the account once all were sealed.
+pub fn rewrap_account_id(account_id: &str) -> String {
+    format!("passport-desktop rewrap\n{}", account_id)
+}
+
+/// The fingerprint of an account key, the SHA-256
+/// hash of its PKCS#1 encoded public key.
+pub fn key_fingerprint(public_key: &[u8]) -> [u8; FINGERPRINT_LENGTH] {
+    Sha256::digest(public_key).into()
+}
+
+fn invalid(reason: &str) -> napi::Error {
+    napi::Error::from_reason(format!("The envelope is invalid: {}", reason))
+}
+
+/// Reads the fields of an envelope, failing
+/// if the envelope ends before a field.
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> napi::Result<&'a [u8]> {
+        if self.data.len() < length {
+            return Err(invalid("the envelope is truncated"));
+        }
+
+        let (field, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(field)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> napi::Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+}
+
+/// Data encrypted with a key derived from the signature of the vault
+/// challenge. Every envelope uses a new salt, and with it a new key.
+/// The header is authenticated together with the ciphertext.
+///
+/// Version 2, all integers are little endian:
+///
+/// | Field             | Length            |
+/// |-------------------|-------------------|
+/// | `PPVT`            | 4                 |
+/// | version           | 1                 |
+/// | account ID length | 2                 |
+/// | account ID        | account ID length |
+/// | key fingerprint   | 32                |
+/// | salt              | 32                |
+/// | nonce             | 12                |
+/// | ciphertext        | rest              |
+///
+/// Version 1 envelopes have no account ID and key fingerprint.
+pub struct Envelope {
+    pub version: u8,
+    pub account_id: Option<String>,
+    pub key_fingerprint: Option<[u8; FINGERPRINT_LENGTH]>,
+    pub salt: [u8; SALT_LENGTH],
+    pub nonce: [u8; NONCE_LENGTH],
+    pub ciphertext: Vec<u8>,
+}
+
+impl Envelope {
+    /// Encrypt data for an account.
+    pub fn seal(
+        signature: &[u8],
+        account_id: &str,
+        key_fingerprint: [u8; FINGERPRINT_LENGTH],
+        plaintext: &[u8],
+    ) -> napi::Result<Self> {
+        let mut envelope = Self {
+            version: VERSION_2,
+            account_id: Some(account_id.to_string()),
+            key_fingerprint: Some(key_fingerprint),
+            salt: [0u8; SALT_LENGTH],
+            nonce: [0u8; NONCE_LENGTH],
+            ciphertext: vec![],
+        };
+        rand::thread_rng().fill_bytes(&mut envelope.salt);
+        rand::thread_rng().fill_bytes(&mut envelope.nonce);
+
+        let header = envelope.header()?;
+        let payload = Payload {
+            msg: plaintext,
+            aad: &header,
+        };
+
+        envelope.ciphertext = envelope
+            .cipher(signature)?
+            .encrypt(Nonce::from_slice(&envelope.nonce), payload)
+            .map_err(|_| napi::Error::from_reason("Failed to encrypt the data"))?;
+
+        Ok(envelope)
+    }
+
+    /// Decrypt the data.
+    pub fn open(&self, signature: &[u8]) -> napi::Result<Vec<u8>> {
+        let header = self.header()?;
+        let payload = Payload {
+            msg: &self.ciphertext,
+            aad: &header,
+        };
+
+        self.cipher(signature)?
+            .decrypt(Nonce::from_slice(&self.nonce), payload)
+            .map_err(|_| {
+                napi::Error::from_reason(
+                    "Failed to decrypt the data, it was protected by another key or was modified",
+                )
+            })
+    }
+
+    fn cipher(&self, signature: &[u8]) -> napi::Result<Aes256Gcm> {
+        let mut key = Zeroizing::new([0u8; 32]);
+        Hkdf::<Sha256>::new(Some(&self.salt), signature)
+            .expand(KEY_INFO, key.as_mut())
+            .map_err(|_| napi::Error::from_reason("Failed to derive the vault key"))?;
+
+        Aes256Gcm::new_from_slice(key.as_ref()).map_napi_error()
+    }
+
+    /// Encode everything but the ciphertext.
+    fn header(&self) -> napi::Result<Vec<u8>> {
+        let mut header = MAGIC.to_vec();
+        header.push(self.version);
+
+        match (self.version, &self.account_id, &self.key_fingerprint) {
+            (VERSION_1, None, None) => {}
+            (VERSION_2, Some(account_id), Some(fingerprint)) => {
+                let length = u16::try_from(account_id.len())
+                    .map_err(|_| invalid("the account ID is too long"))?;
+
+                header.extend_from_slice(&length.to_le_bytes());
+                header.extend_from_slice(account_id.as_bytes());
+                header.extend_from_slice(fingerprint);
+            }
+            (VERSION_1, _, _) => {
+                return Err(invalid(
+                    "version 1 envelopes have no account ID and key fingerprint",
+                ))
+            }
+            (VERSION_2, _, _) => {
+                return Err(invalid(
+                    "version 2 envelopes require an account ID and key fingerprint",
+                ))
+            }
+            (version, _, _) => {
+                return Err(invalid(&format!("version {} is not supported", version)))
+            }
+        }
+
+        header.extend_from_slice(&self.salt);
+        header.extend_from_slice(&self.nonce);
+        Ok(header)
+    }
+
+    /// Encode the envelope.
+    pub fn encode(&self) -> napi::Result<Vec<u8>> {
+        let mut data = self.header()?;
+        data.extend_from_slice(&self.ciphertext);
+        Ok(data)
+    }
+
+    /// Decode an envelope without decrypting it.
+    pub fn decode(data: &[u8]) -> napi::Result<Self> {
+        let mut reader = Reader { data };
+        if reader.take(MAGIC.len()).ok() != Some(&MAGIC[..]) {
+            return Err(napi::Error::from_reason(
+                "The data was not protected using a passport account",
+            ));
+        }
+
+        let version = reader.take(1)?[0];
+        let (account_id, key_fingerprint) = match version {
+            VERSION_1 => (None, None),
+            VERSION_2 => {
+                let length = u16::from_le_bytes(reader.take_array()?);
+                let account_id = std::str::from_utf8(reader.take(length as usize)?)
+                    .map_err(|_| invalid("the account ID is not valid UTF-8"))?;
+
+                (Some(account_id.to_string()), Some(reader.take_array()?))
+            }
+            version => return Err(invalid(&format!("version {} is not supported", version))),
+        };
+
+        Ok(Self {
+            version,
+            account_id,
+            key_fingerprint,
+            salt: reader.take_array()?,
+            nonce: reader.take_array()?,
+            ciphertext: reader.data.to_vec(),
+        })
+    }
+
+    /// Check whether this envelope can be opened using the key of an
+    /// account, without asking the user to sign the vault challenge.
+    pub fn check_recipient(
+        &self,
+        account_id: &str,
+        key_fingerprint: &[u8; FINGERPRINT_LENGTH],
+    ) -> napi::Result<()> {
+        if let Some(recipient) = &self.account_id {
+            if recipient != account_id {
+                return Err(napi::Error::from_reason(format!(
+                    "The data was protected by the account '{}'",
+                    recipient
+                )));
+            }
+        }
+
+        match &self.key_fingerprint {
+            Some(fingerprint) if fingerprint != key_fingerprint => Err(napi::Error::from_reason(
+                "The data was protected by another key of this account",
+            )),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use crate::util::mappers::MapNapiError;
+use crate::x509::{encode_time, signature_algorithm};
+use rand::RngCore;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use x509_cert::der::asn1::BitString;
+use x509_cert::der::{Decode, DecodePem};
+use x509_cert::ext::Extension;
+use x509_cert::name::Name;
+use x509_cert::serial_number::SerialNumber;
+use x509_cert::spki::SubjectPublicKeyInfoOwned;
+use x509_cert::time::Validity;
+use x509_cert::{Certificate, TbsCertificate, Version};
+
+/// The length of the random serial numbers, the
+/// 128 bits recommended by the CA/Browser Forum.
+const SERIAL_NUMBER_LENGTH: usize = 16;
+/// The latest time a certificate can be valid until, 9999-12-31T23:59:59Z,
+/// as the year of a `GeneralizedTime` only has four digits.
+const MAX_NOT_AFTER: Duration = Duration::from_secs(253_402_300_799);
+
+/// Build the `TBSCertificate` of a self-signed certificate,
+/// valid from now on for the given duration.
+pub fn self_signed_tbs_certificate(
+    subject: Name,
+    public_key: &[u8],
+    validity: Duration,
+    extensions: Vec<Extension>,
+) -> napi::Result<TbsCertificate> {
+    let mut serial_number = [0u8; SERIAL_NUMBER_LENGTH];
+    rand::thread_rng().fill_bytes(&mut serial_number);
+    // Serial numbers must be positive
+    serial_number[0] &= 0x7f;
+
+    let now = SystemTime::now();
+    let not_after = now
+        .checked_add(validity)
+        .filter(|time| {
+            time.duration_since(UNIX_EPOCH)
+                .is_ok_and(|time| time <= MAX_NOT_AFTER)
+        })
+        .ok_or_else(|| {
+            napi::Error::from_reason("The certificate can't be valid beyond 9999-12-31")
+        })?;
+
+    Ok(TbsCertificate {
+        version: Version::V3,
+        serial_number: SerialNumber::new(&serial_number).map_napi_error()?,
+        signature: signature_algorithm(),
+        issuer: subject.clone(),
+        validity: Validity {
+            not_before: encode_time(now)?,
+            not_after: encode_time(not_after)?,
+        },
+        subject,
+        subject_public_key_info: SubjectPublicKeyInfoOwned::from_der(public_key)
+            .map_napi_error()?,
+        issuer_unique_id: None,
+        subject_unique_id: None,
+        extensions: (!extensions.is_empty()).then_some(extensions),
+    })
+}
+
+/// Combine a `TBSCertificate` and its signature to a certificate.
+pub fn certificate(tbs_certificate: TbsCertificate, signature: &[u8]) -> napi::Result<Certificate> {
+    Ok(Certificate {
+        tbs_certificate,
+        signature_algorithm: signature_algorithm(),
+        signature: BitString::from_bytes(signature).map_napi_error()?,
+    })
+}
+
+/// Parse a PEM or DER encoded certificate.
+pub fn parse(data: &[u8]) -> napi::Result<Certificate> {
+    let result = if data.starts_with(b"-----BEGIN") {
+        Certificate::from_pem(data)
+    } else {
+        Certificate::from_der(data)
+    };
+
+    result.map_err(|e| napi::Error::from_reason(format!("The certificate is invalid: {}", e)))
+}
+
+/// Check whether a certificate was issued for a public key.
+pub fn has_public_key(certificate: &Certificate, public_key: &[u8]) -> napi::Result<bool> {
+    let public_key = SubjectPublicKeyInfoOwned::from_der(public_key).map_napi_error()?;
+    Ok(certificate.tbs_certificate.subject_public_key_info == public_key)
+}
@@ -0,0 +1,237 @@
+use crate::util::mappers::MapNapiError;
+use crate::x509::encode_time;
+use cms::cert::{CertificateChoices, IssuerAndSerialNumber};
+use cms::content_info::{CmsVersion, ContentInfo};
+use cms::signed_data::{
+    CertificateSet, EncapsulatedContentInfo, SignedAttributes, SignedData, SignerIdentifier,
+    SignerInfo, SignerInfos,
+};
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+use rsa::pkcs8::DecodePublicKey;
+use rsa::signature::Verifier;
+use rsa::RsaPublicKey;
+use sha2::{Digest, Sha256};
+use std::time::SystemTime;
+use x509_cert::attr::{Attribute, AttributeValue};
+use x509_cert::der::asn1::{Any, OctetString, SetOfVec};
+use x509_cert::der::oid::db::rfc5911::{
+    ID_CONTENT_TYPE, ID_DATA, ID_MESSAGE_DIGEST, ID_SIGNED_DATA, ID_SIGNING_TIME,
+};
+use x509_cert::der::oid::db::rfc5912::{ID_SHA_256, RSA_ENCRYPTION, SHA_256_WITH_RSA_ENCRYPTION};
+use x509_cert::der::oid::ObjectIdentifier;
+use x509_cert::der::pem::{self, LineEnding};
+use x509_cert::der::{Decode, Encode, EncodeValue, Tagged};
+use x509_cert::spki::AlgorithmIdentifierOwned;
+use x509_cert::time::Time;
+use x509_cert::Certificate;
+
+/// The PEM label used by `openssl cms`.
+const PEM_LABEL: &str = "CMS";
+
+fn sha256_algorithm() -> AlgorithmIdentifierOwned {
+    AlgorithmIdentifierOwned {
+        oid: ID_SHA_256,
+        parameters: None,
+    }
+}
+
+fn attribute<T: Tagged + EncodeValue>(oid: ObjectIdentifier, value: &T) -> napi::Result<Attribute> {
+    let mut values: SetOfVec<AttributeValue> = SetOfVec::new();
+    values
+        .insert(Any::encode_from(value).map_napi_error()?)
+        .map_napi_error()?;
+
+    Ok(Attribute { oid, values })
+}
+
+/// Build the signed attributes of a detached signature: the content
+/// type, the SHA-256 digest of the content and the signing time.
+/// The DER encoding of the attributes is what the account key signs.
+pub fn signed_attributes(
+    content: &[u8],
+    signing_time: SystemTime,
+) -> napi::Result<SignedAttributes> {
+    let digest = OctetString::new(Sha256::digest(content).to_vec()).map_napi_error()?;
+    let mut attributes = SignedAttributes::new();
+    for attribute in [
+        attribute(ID_CONTENT_TYPE, &ID_DATA)?,
+        attribute(ID_MESSAGE_DIGEST, &digest)?,
+        attribute(ID_SIGNING_TIME, &encode_time(signing_time)?)?,
+    ] {
+        attributes.insert(attribute).map_napi_error()?;
+    }
+
+    Ok(attributes)
+}
+
+/// Build a detached `SignedData` structure with a single signer,
+/// identified by the issuer and serial number of its certificate.
+pub fn signed_data(
+    certificate: Certificate,
+    attributes: SignedAttributes,
+    signature: &[u8],
+) -> napi::Result<ContentInfo> {
+    let signer = SignerInfo {
+        version: CmsVersion::V1,
+        sid: SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+            issuer: certificate.tbs_certificate.issuer.clone(),
+            serial_number: certificate.tbs_certificate.serial_number.clone(),
+        }),
+        digest_alg: sha256_algorithm(),
+        signed_attrs: Some(attributes),
+        signature_algorithm: AlgorithmIdentifierOwned {
+            oid: RSA_ENCRYPTION,
+            parameters: Some(Any::null()),
+        },
+        signature: OctetString::new(signature).map_napi_error()?,
+        unsigned_attrs: None,
+    };
+
+    let signed_data = SignedData {
+        version: CmsVersion::V1,
+        digest_algorithms: SetOfVec::try_from(vec![sha256_algorithm()]).map_napi_error()?,
+        encap_content_info: EncapsulatedContentInfo {
+            econtent_type: ID_DATA,
+            econtent: None,
+        },
+        certificates: Some(
+            CertificateSet::try_from(vec![CertificateChoices::Certificate(certificate)])
+                .map_napi_error()?,
+        ),
+        crls: None,
+        signer_infos: SignerInfos::try_from(vec![signer]).map_napi_error()?,
+    };
+
+    Ok(ContentInfo {
+        content_type: ID_SIGNED_DATA,
+        content: Any::encode_from(&signed_data).map_napi_error()?,
+    })
+}
+
+/// Encode a `ContentInfo` as PEM, the way `openssl cms -outform PEM` does.
+pub fn to_pem(der: &[u8]) -> napi::Result<String> {
+    pem::encode_string(PEM_LABEL, LineEnding::LF, der)
+        .map_err(x509_cert::der::Error::from)
+        .map_napi_error()
+}
+
+/// The signer of a valid signature.
+pub struct Signer {
+    pub certificate: Certificate,
+    pub signing_time: Option<Time>,
+}
+
+fn invalid(reason: &str) -> napi::Error {
+    napi::Error::from_reason(format!("The signature is invalid: {}", reason))
+}
+
+/// Get the single value of a signed attribute.
+fn attribute_value(attributes: &SignedAttributes, oid: ObjectIdentifier) -> Option<&Any> {
+    attributes
+        .iter()
+        .find(|attribute| attribute.oid == oid)
+        .and_then(|attribute| attribute.values.get(0))
+}
+
+/// Verify a detached signature using SHA-256 and RSA over the content.
+/// The signer's certificate is taken from the signature, unless one is
+/// passed. The certificate itself is not validated, so the caller has
+/// to check whether it trusts the signer.
+pub fn verify(
+    signature: &[u8],
+    content: &[u8],
+    certificate: Option<Certificate>,
+) -> napi::Result<Signer> {
+    let der = match pem::decode_vec(signature) {
+        Ok((_, der)) => der,
+        Err(_) => signature.to_vec(),
+    };
+
+    let content_info = ContentInfo::from_der(&der).map_err(|_| invalid("not a CMS structure"))?;
+    if content_info.content_type != ID_SIGNED_DATA {
+        return Err(invalid("not a CMS signed data structure"));
+    }
+
+    let signed_data: SignedData = content_info
+        .content
+        .decode_as()
+        .map_err(|e| invalid(&e.to_string()))?;
+    if signed_data.encap_content_info.econtent.is_some() {
+        return Err(invalid("the signature is not detached"));
+    }
+
+    let signer = match signed_data.signer_infos.0.as_slice() {
+        [signer] => signer,
+        _ => return Err(invalid("the signature must have exactly one signer")),
+    };
+
+    let SignerIdentifier::IssuerAndSerialNumber(sid) = &signer.sid else {
+        return Err(invalid("the signer is not identified by its certificate"));
+    };
+
+    // A certificate passed by the caller replaces the embedded ones
+    let certificates: Vec<Certificate> = match certificate {
+        Some(certificate) => vec![certificate],
+        None => signed_data
+            .certificates
+            .iter()
+            .flat_map(|set| set.0.iter())
+            .filter_map(|choice| match choice {
+                CertificateChoices::Certificate(certificate) => Some(certificate.clone()),
+                _ => None,
+            })
+            .collect(),
+    };
+    let certificate = certificates
+        .into_iter()
+        .find(|certificate| {
+            certificate.tbs_certificate.issuer == sid.issuer
+                && certificate.tbs_certificate.serial_number == sid.serial_number
+        })
+        .ok_or_else(|| invalid("the certificate of the signer was not found"))?;
+
+    if signer.digest_alg.oid != ID_SHA_256 {
+        return Err(invalid("only SHA-256 digests are supported"));
+    }
+    if signer.signature_algorithm.oid != RSA_ENCRYPTION
+        && signer.signature_algorithm.oid != SHA_256_WITH_RSA_ENCRYPTION
+    {
+        return Err(invalid("only RSA signatures are supported"));
+    }
+
+    let attributes = signer
+        .signed_attrs
+        .as_ref()
+        .ok_or_else(|| invalid("the signed attributes are missing"))?;
+    let content_type = attribute_value(attributes, ID_CONTENT_TYPE)
+        .and_then(|value| value.decode_as::<ObjectIdentifier>().ok());
+    if content_type != Some(signed_data.encap_content_info.econtent_type) {
+        return Err(invalid("the content type does not match"));
+    }
+
+    let digest = attribute_value(attributes, ID_MESSAGE_DIGEST)
+        .and_then(|value| value.decode_as::<OctetString>().ok())
+        .ok_or_else(|| invalid("the message digest is missing"))?;
+    if digest.as_bytes() != Sha256::digest(content).as_slice() {
+        return Err(invalid("the content does not match the message digest"));
+    }
+
+    let public_key = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_napi_error()?;
+    let key = RsaPublicKey::from_public_key_der(&public_key)
+        .map_err(|_| invalid("the certificate does not contain an RSA key"))?;
+    let value = Signature::try_from(signer.signature.as_bytes())
+        .map_err(|_| invalid("the signature value is malformed"))?;
+    VerifyingKey::<Sha256>::new(key)
+        .verify(&attributes.to_der().map_napi_error()?, &value)
+        .map_err(|_| invalid("the signature does not match the signed attributes"))?;
+
+    Ok(Signer {
+        signing_time: attribute_value(attributes, ID_SIGNING_TIME)
+            .and_then(|value| Time::from_der(&value.to_der().ok()?).ok()),
+        certificate,
+    })
+}
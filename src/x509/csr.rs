@@ -0,0 +1,40 @@
+use crate::util::mappers::MapNapiError;
+use crate::x509::signature_algorithm;
+use x509_cert::attr::Attributes;
+use x509_cert::der::asn1::BitString;
+use x509_cert::der::Decode;
+use x509_cert::ext::Extension;
+use x509_cert::name::Name;
+use x509_cert::request::{CertReq, CertReqInfo, ExtensionReq, Version};
+use x509_cert::spki::SubjectPublicKeyInfoOwned;
+
+/// Build the `CertificationRequestInfo` signed by the account key.
+/// The extensions are requested using an `extensionRequest` attribute.
+pub fn request_info(
+    subject: Name,
+    public_key: &[u8],
+    extensions: Vec<Extension>,
+) -> napi::Result<CertReqInfo> {
+    let mut attributes = Attributes::new();
+    if !extensions.is_empty() {
+        attributes
+            .insert(ExtensionReq(extensions).try_into().map_napi_error()?)
+            .map_napi_error()?;
+    }
+
+    Ok(CertReqInfo {
+        version: Version::V1,
+        subject,
+        public_key: SubjectPublicKeyInfoOwned::from_der(public_key).map_napi_error()?,
+        attributes,
+    })
+}
+
+/// Combine the request info and its signature to a certificate request.
+pub fn certificate_request(info: CertReqInfo, signature: &[u8]) -> napi::Result<CertReq> {
+    Ok(CertReq {
+        info,
+        algorithm: signature_algorithm(),
+        signature: BitString::from_bytes(signature).map_napi_error()?,
+    })
+}
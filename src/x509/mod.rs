@@ -0,0 +1,111 @@
+use crate::node::x509_extension::X509Extension;
+use crate::util::mappers::MapNapiError;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::time::SystemTime;
+use x509_cert::der::asn1::{Any, GeneralizedTime, Ia5String, OctetString, UtcTime};
+use x509_cert::der::oid::db::rfc5912::{ID_CE_SUBJECT_ALT_NAME, SHA_256_WITH_RSA_ENCRYPTION};
+use x509_cert::der::oid::ObjectIdentifier;
+use x509_cert::der::{DateTime, Encode};
+use x509_cert::ext::pkix::name::GeneralName;
+use x509_cert::ext::pkix::SubjectAltName;
+use x509_cert::ext::Extension;
+use x509_cert::name::Name;
+use x509_cert::spki::AlgorithmIdentifierOwned;
+use x509_cert::time::Time;
+
+pub(crate) mod certificate;
+pub(crate) mod cms;
+pub(crate) mod csr;
+
+/// `sha256WithRSAEncryption`, the algorithm of passport signatures.
+pub fn signature_algorithm() -> AlgorithmIdentifierOwned {
+    AlgorithmIdentifierOwned {
+        oid: SHA_256_WITH_RSA_ENCRYPTION,
+        parameters: Some(Any::null()),
+    }
+}
+
+/// Encode a time as required by RFC 5280, as `UTCTime`
+/// until 2049 and as `GeneralizedTime` afterwards.
+pub fn encode_time(time: SystemTime) -> napi::Result<Time> {
+    let time = DateTime::from_system_time(time).map_napi_error()?;
+    Ok(match UtcTime::from_date_time(time) {
+        Ok(time) => Time::UtcTime(time),
+        Err(_) => Time::GeneralTime(GeneralizedTime::from_date_time(time)),
+    })
+}
+
+/// Parse a distinguished name in the string format
+/// of RFC 4514, e.g. `CN=device-1,O=Example`.
+pub fn parse_name(name: &str) -> napi::Result<Name> {
+    Name::from_str(name)
+        .map_err(|e| napi::Error::from_reason(format!("The name '{}' is invalid: {}", name, e)))
+}
+
+/// Parse a subject alternative name in the format used by
+/// OpenSSL, e.g. `DNS:example.com` or `IP:192.168.0.1`.
+fn parse_general_name(name: &str) -> napi::Result<GeneralName> {
+    let invalid = || {
+        napi::Error::from_reason(format!(
+            "The subject alternative name '{}' is invalid, \
+            it must start with DNS:, IP:, email: or URI:",
+            name
+        ))
+    };
+
+    let (kind, value) = name.split_once(':').ok_or_else(invalid)?;
+    let ia5 = || Ia5String::new(value).map_err(|_| invalid());
+    match kind {
+        "DNS" => Ok(GeneralName::DnsName(ia5()?)),
+        "email" => Ok(GeneralName::Rfc822Name(ia5()?)),
+        "URI" => Ok(GeneralName::UniformResourceIdentifier(ia5()?)),
+        "IP" => Ok(IpAddr::from_str(value).map_err(|_| invalid())?.into()),
+        _ => Err(invalid()),
+    }
+}
+
+/// Build the extensions of a certificate or certificate request. The
+/// subject alternative names are added as extension, which is critical
+/// if the subject is empty, as required by RFC 5280.
+pub fn build_extensions(
+    subject: &Name,
+    subject_alt_names: &[String],
+    extensions: Vec<X509Extension>,
+) -> napi::Result<Vec<Extension>> {
+    let mut result = vec![];
+    if !subject_alt_names.is_empty() {
+        let names = subject_alt_names
+            .iter()
+            .map(|name| parse_general_name(name))
+            .collect::<napi::Result<Vec<_>>>()?;
+
+        result.push(Extension {
+            extn_id: ID_CE_SUBJECT_ALT_NAME,
+            critical: subject.is_empty(),
+            extn_value: OctetString::new(SubjectAltName(names).to_der().map_napi_error()?)
+                .map_napi_error()?,
+        });
+    }
+
+    for extension in extensions {
+        let extn_id = ObjectIdentifier::new(&extension.oid).map_err(|_| {
+            napi::Error::from_reason(format!("The extension OID '{}' is invalid", extension.oid))
+        })?;
+
+        if result.iter().any(|e: &Extension| e.extn_id == extn_id) {
+            return Err(napi::Error::from_reason(format!(
+                "The extension {} was added more than once",
+                extn_id
+            )));
+        }
+
+        result.push(Extension {
+            extn_id,
+            critical: extension.critical.unwrap_or(false),
+            extn_value: OctetString::new(extension.value.to_vec()).map_napi_error()?,
+        });
+    }
+
+    Ok(result)
+}
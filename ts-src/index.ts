@@ -7,11 +7,79 @@ export = createDummies({
             accountWithIdExists: DummyType.Function,
             available: DummyType.Function,
             requestVerification: DummyType.Function,
+            configurePkcs11: DummyType.Function,
+            setPolkitAction: DummyType.Function,
+            setTpmConsentProvider: DummyType.Function,
+            configureKeyStore: DummyType.Function,
+            setKeyStorePassphrase: DummyType.Function,
+            decodeEnvelope: DummyType.Function,
+            encodeEnvelope: DummyType.Function,
+            verifyCms: DummyType.Function,
+            verifyMinisign: DummyType.Function,
+            verifyHttpSignature: DummyType.Function,
+            verifyDpopProof: DummyType.Function,
+            verifyEntitlement: DummyType.Function,
+            setAuditSink: DummyType.Function,
+            clearAuditSink: DummyType.Function,
         },
         overrides: {
             available: () => false,
         },
     },
+    AuditSink: {
+        isClass: true,
+        dummies: {
+            file: DummyType.Function,
+            callback: DummyType.Function,
+            verifyFile: DummyType.Function,
+            verifyEntries: DummyType.Function,
+        },
+    },
+    SshAgent: {
+        isClass: true,
+        dummies: {
+            listen: DummyType.Function,
+        },
+    },
+    Logger: {
+        isClass: true,
+        dummies: {
+            logToCallback: DummyType.Function,
+            logToFile: DummyType.Function,
+            disable: DummyType.Function,
+        },
+    },
+    LogLevel: {
+        dummies: {
+            Error: DummyType.Getter,
+            Warn: DummyType.Getter,
+            Info: DummyType.Getter,
+            Debug: DummyType.Getter,
+            Trace: DummyType.Getter,
+        },
+    },
+    Signer: {
+        isClass: true,
+        dummies: {},
+    },
+    OtpStore: {
+        isClass: true,
+        dummies: {},
+    },
+    OtpAlgorithm: {
+        dummies: {
+            Sha1: DummyType.Getter,
+            Sha256: DummyType.Getter,
+            Sha512: DummyType.Getter,
+        },
+    },
+    DigestAlgorithm: {
+        dummies: {
+            Sha256: DummyType.Getter,
+            Sha384: DummyType.Getter,
+            Sha512: DummyType.Getter,
+        },
+    },
     VerificationResult: {
         dummies: {
             Canceled: DummyType.Getter,
@@ -23,6 +91,22 @@ export = createDummies({
             Verified: DummyType.Getter,
         },
     },
+    PassportBackend: {
+        dummies: {
+            WindowsHello: DummyType.Getter,
+            Software: DummyType.Getter,
+            Tpm: DummyType.Getter,
+            Pkcs11: DummyType.Getter,
+            Local: DummyType.Getter,
+        },
+    },
+    ConsentProvider: {
+        dummies: {
+            WindowsHello: DummyType.Getter,
+            Fprintd: DummyType.Getter,
+            Polkit: DummyType.Getter,
+        },
+    },
     PublicKeyEncoding: {
         dummies: {
             X509SubjectPublicKeyInfo: DummyType.Getter,
@@ -30,6 +114,7 @@ export = createDummies({
             Capi1PublicKey: DummyType.Getter,
             BCryptEccFullPublicKey: DummyType.Getter,
             Pkcs1RsaPublicKey: DummyType.Getter,
+            OpenSsh: DummyType.Getter,
         },
     },
     KeyCreationOption: {